@@ -0,0 +1,1114 @@
+//! GlBackend wrapper which remembers the current GL state (program, VAO, buffer, texture and sampler bindings,
+//! active texture unit, framebuffers and render state like blending, depth or viewport) and skips calls that would not change it.
+//!
+//! ```text
+//! let context = CachedBackend::new(gl);
+//! let renderer = RectangleRenderer::<Vertex2D, _>::init(&context, 1000)?;
+//! ...
+//! log(&format!("skipped calls: {}", context.get_counters().get_total_skipped()));
+//! ```
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use web_sys::HtmlImageElement;
+use super::interface::{ActiveInfo, GlBackend};
+use super::super::super::runtime_error::SWGLResult;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Number of calls of one kind, which were passed to the wrapped backend or skipped (they would not change the state).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CallCounter {
+    pub issued: u64,
+    pub skipped: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct StateCacheCounters {
+    pub use_program: CallCounter,
+    pub bind_vertex_array: CallCounter,
+    pub bind_buffer: CallCounter,
+    pub bind_texture: CallCounter,
+    pub active_texture: CallCounter,
+    pub bind_sampler: CallCounter,
+    pub bind_framebuffer: CallCounter,
+    /// Calls which change fixed-function state (enable/disable, blend, depth, stencil, cull face, scissor,
+    /// viewport, color mask and clear values).
+    pub render_state: CallCounter,
+}
+
+impl StateCacheCounters {
+    fn get_all(&self) -> [CallCounter; 8] {
+        [
+            self.use_program,
+            self.bind_vertex_array,
+            self.bind_buffer,
+            self.bind_texture,
+            self.active_texture,
+            self.bind_sampler,
+            self.bind_framebuffer,
+            self.render_state,
+        ]
+    }
+
+    pub fn get_total_issued(&self) -> u64 {
+        self.get_all().iter().map(|c| c.issued).sum()
+    }
+
+    /// This method returns number of avoided calls.
+    pub fn get_total_skipped(&self) -> u64 {
+        self.get_all().iter().map(|c| c.skipped).sum()
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// private:
+
+/// Active texture unit (TEXTURE0 + unit) and texture target.
+type TextureSlot = (u32, u32);
+
+/// Every cached value is an Option, None means unknown state (the next call is always issued).
+/// Bindings are stored as Some(None) when null object is bound.
+struct CachedState<B: GlBackend> {
+    program: Option<Option<B::Program>>,
+    vertex_array: Option<Option<B::VertexArray>>,
+    /// Element buffer binding is a part of the VAO state, so it is forgotten when VAO changes.
+    element_buffer: Option<Option<B::Buffer>>,
+    /// Bindings of other targets (ARRAY_BUFFER, UNIFORM_BUFFER, ...).
+    buffers: Vec<(u32, Option<B::Buffer>)>,
+    /// Active texture as GL enum (TEXTURE0 + unit).
+    active_texture: Option<u32>,
+    /// Bindings per (texture unit, target).
+    textures: Vec<(TextureSlot, Option<B::Texture>)>,
+    /// Bindings per texture unit (index, not TEXTURE0 + index).
+    samplers: Vec<(u32, Option<B::Sampler>)>,
+    draw_framebuffer: Option<Option<B::Framebuffer>>,
+    read_framebuffer: Option<Option<B::Framebuffer>>,
+    capabilities: Vec<(u32, bool)>,
+    /// Blend factors (src_rgb, dst_rgb, src_alpha, dst_alpha).
+    blend_func: Option<(u32, u32, u32, u32)>,
+    blend_equation: Option<(u32, u32)>,
+    depth_func: Option<u32>,
+    depth_mask: Option<bool>,
+    stencil_func: Option<(u32, i32, u32)>,
+    stencil_op: Option<(u32, u32, u32)>,
+    stencil_mask: Option<u32>,
+    cull_face: Option<u32>,
+    scissor: Option<(i32, i32, i32, i32)>,
+    viewport: Option<(i32, i32, i32, i32)>,
+    color_mask: Option<(bool, bool, bool, bool)>,
+    clear_color: Option<(f32, f32, f32, f32)>,
+    clear_depth: Option<f32>,
+    clear_stencil: Option<i32>,
+    counters: StateCacheCounters,
+}
+
+impl<B: GlBackend> CachedState<B> {
+    fn new(counters: StateCacheCounters) -> Self {
+        Self {
+            program: None,
+            vertex_array: None,
+            element_buffer: None,
+            buffers: Vec::new(),
+            active_texture: None,
+            textures: Vec::new(),
+            samplers: Vec::new(),
+            draw_framebuffer: None,
+            read_framebuffer: None,
+            capabilities: Vec::new(),
+            blend_func: None,
+            blend_equation: None,
+            depth_func: None,
+            depth_mask: None,
+            stencil_func: None,
+            stencil_op: None,
+            stencil_mask: None,
+            cull_face: None,
+            scissor: None,
+            viewport: None,
+            color_mask: None,
+            clear_color: None,
+            clear_depth: None,
+            clear_stencil: None,
+            counters,
+        }
+    }
+}
+
+/// This function stores the value and returns true if the call has to be issued.
+fn update<T: PartialEq>(cached: &mut Option<T>, value: T, counter: &mut CallCounter) -> bool {
+    if cached.as_ref() == Some(&value) {
+        counter.skipped += 1;
+        return false;
+    }
+    *cached = Some(value);
+    counter.issued += 1;
+    true
+}
+
+/// This function works like update, but for values identified by key (missing key means unknown state).
+fn update_keyed<K: PartialEq, T: PartialEq>(cached: &mut Vec<(K, T)>, key: K, value: T, counter: &mut CallCounter) -> bool {
+    match cached.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, v)) if *v == value => {
+            counter.skipped += 1;
+            false
+        }
+        Some((_, v)) => {
+            *v = value;
+            counter.issued += 1;
+            true
+        }
+        None => {
+            cached.push((key, value));
+            counter.issued += 1;
+            true
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Clones share the cached state, so the same CachedBackend (or its clones) should be used for every call.
+/// If the wrapped context is used directly, call invalidate afterwards.
+pub struct CachedBackend<B: GlBackend = crate::AppContext> {
+    inner: B,
+    state: Rc<RefCell<CachedState<B>>>,
+}
+
+impl<B: GlBackend> Clone for CachedBackend<B> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            state: Rc::clone(&self.state),
+        }
+    }
+}
+
+impl<B: GlBackend> CachedBackend<B> {
+    /// State of the context is unknown at the beginning, so the first call of every kind is always issued.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            state: Rc::new(RefCell::new(CachedState::new(StateCacheCounters::default()))),
+        }
+    }
+
+    /// This method returns wrapped backend. Calls made with it are not visible to the cache (see invalidate).
+    pub fn get_inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// This method forgets cached state (counters are kept).
+    pub fn invalidate(&self) {
+        let mut state = self.state.borrow_mut();
+        let counters = state.counters;
+        *state = CachedState::new(counters);
+    }
+
+    pub fn get_counters(&self) -> StateCacheCounters {
+        self.state.borrow().counters
+    }
+
+    pub fn reset_counters(&self) {
+        self.state.borrow_mut().counters = StateCacheCounters::default();
+    }
+
+    fn set_capability(&self, cap: u32, enabled: bool) -> bool {
+        let state = &mut *self.state.borrow_mut();
+        update_keyed(&mut state.capabilities, cap, enabled, &mut state.counters.render_state)
+    }
+
+    /// This method updates cached render state value selected by get_value and returns true if the call has to be issued.
+    fn set_render_state<T: PartialEq>(&self, get_value: fn(&mut CachedState<B>) -> &mut Option<T>, value: T) -> bool {
+        let state = &mut *self.state.borrow_mut();
+        let mut counter = state.counters.render_state;
+        let changed = update(get_value(state), value, &mut counter);
+        state.counters.render_state = counter;
+        changed
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+impl<B: GlBackend> GlBackend for CachedBackend<B> {
+    type Buffer = B::Buffer;
+    type VertexArray = B::VertexArray;
+    type Texture = B::Texture;
+    type Shader = B::Shader;
+    type Program = B::Program;
+    type Framebuffer = B::Framebuffer;
+    type Renderbuffer = B::Renderbuffer;
+    type Sampler = B::Sampler;
+    type UniformLocation = B::UniformLocation;
+
+    // basics
+
+    fn clear(&self, mask: u32) {
+        self.inner.clear(mask);
+    }
+
+    fn get_parameter_i32(&self, pname: u32) -> i32 {
+        self.inner.get_parameter_i32(pname)
+    }
+
+    fn enable_extension(&self, name: &str) -> bool {
+        self.inner.enable_extension(name)
+    }
+
+    fn enable(&self, cap: u32) {
+        if self.set_capability(cap, true) {
+            self.inner.enable(cap);
+        }
+    }
+
+    fn disable(&self, cap: u32) {
+        if self.set_capability(cap, false) {
+            self.inner.disable(cap);
+        }
+    }
+
+    /// Blend factors are cached together with blend_func_separate.
+    fn blend_func(&self, sfactor: u32, dfactor: u32) {
+        if self.set_render_state(|s| &mut s.blend_func, (sfactor, dfactor, sfactor, dfactor)) {
+            self.inner.blend_func(sfactor, dfactor);
+        }
+    }
+
+    fn blend_equation(&self, mode: u32) {
+        if self.set_render_state(|s| &mut s.blend_equation, (mode, mode)) {
+            self.inner.blend_equation(mode);
+        }
+    }
+
+    fn depth_func(&self, func: u32) {
+        if self.set_render_state(|s| &mut s.depth_func, func) {
+            self.inner.depth_func(func);
+        }
+    }
+
+    fn depth_mask(&self, flag: bool) {
+        if self.set_render_state(|s| &mut s.depth_mask, flag) {
+            self.inner.depth_mask(flag);
+        }
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        if self.set_render_state(|s| &mut s.viewport, (x, y, width, height)) {
+            self.inner.viewport(x, y, width, height);
+        }
+    }
+
+    fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32) {
+        if self.set_render_state(|s| &mut s.blend_func, (src_rgb, dst_rgb, src_alpha, dst_alpha)) {
+            self.inner.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+        }
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        if self.set_render_state(|s| &mut s.blend_equation, (mode_rgb, mode_alpha)) {
+            self.inner.blend_equation_separate(mode_rgb, mode_alpha);
+        }
+    }
+
+    fn stencil_func(&self, func: u32, reference: i32, mask: u32) {
+        if self.set_render_state(|s| &mut s.stencil_func, (func, reference, mask)) {
+            self.inner.stencil_func(func, reference, mask);
+        }
+    }
+
+    fn stencil_op(&self, fail: u32, depth_fail: u32, pass: u32) {
+        if self.set_render_state(|s| &mut s.stencil_op, (fail, depth_fail, pass)) {
+            self.inner.stencil_op(fail, depth_fail, pass);
+        }
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        if self.set_render_state(|s| &mut s.stencil_mask, mask) {
+            self.inner.stencil_mask(mask);
+        }
+    }
+
+    fn cull_face(&self, mode: u32) {
+        if self.set_render_state(|s| &mut s.cull_face, mode) {
+            self.inner.cull_face(mode);
+        }
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        if self.set_render_state(|s| &mut s.scissor, (x, y, width, height)) {
+            self.inner.scissor(x, y, width, height);
+        }
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        if self.set_render_state(|s| &mut s.color_mask, (red, green, blue, alpha)) {
+            self.inner.color_mask(red, green, blue, alpha);
+        }
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        if self.set_render_state(|s| &mut s.clear_color, (red, green, blue, alpha)) {
+            self.inner.clear_color(red, green, blue, alpha);
+        }
+    }
+
+    fn clear_depth(&self, depth: f32) {
+        if self.set_render_state(|s| &mut s.clear_depth, depth) {
+            self.inner.clear_depth(depth);
+        }
+    }
+
+    fn clear_stencil(&self, stencil: i32) {
+        if self.set_render_state(|s| &mut s.clear_stencil, stencil) {
+            self.inner.clear_stencil(stencil);
+        }
+    }
+
+    // shaders & programs
+
+    fn create_shader(&self, shader_type: u32) -> Option<Self::Shader> {
+        self.inner.create_shader(shader_type)
+    }
+
+    fn shader_source(&self, shader: &Self::Shader, source: &str) {
+        self.inner.shader_source(shader, source);
+    }
+
+    fn compile_shader(&self, shader: &Self::Shader) {
+        self.inner.compile_shader(shader);
+    }
+
+    fn get_shader_info_log(&self, shader: &Self::Shader) -> Option<String> {
+        self.inner.get_shader_info_log(shader)
+    }
+
+    fn create_program(&self) -> Option<Self::Program> {
+        self.inner.create_program()
+    }
+
+    /// Program in use is not unbound by deletion, but its cached binding is forgotten.
+    fn delete_program(&self, program: Option<&Self::Program>) {
+        {
+            let mut state = self.state.borrow_mut();
+            if program.is_some() && state.program.as_ref().map(|p| p.as_ref()) == Some(program) {
+                state.program = None;
+            }
+        }
+        self.inner.delete_program(program);
+    }
+
+    fn delete_shader(&self, shader: Option<&Self::Shader>) {
+        self.inner.delete_shader(shader);
+    }
+
+    fn attach_shader(&self, program: &Self::Program, shader: &Self::Shader) {
+        self.inner.attach_shader(program, shader);
+    }
+
+    fn detach_shader(&self, program: &Self::Program, shader: &Self::Shader) {
+        self.inner.detach_shader(program, shader);
+    }
+
+    fn link_program(&self, program: &Self::Program) {
+        self.inner.link_program(program);
+    }
+
+    fn get_program_info_log(&self, program: &Self::Program) -> Option<String> {
+        self.inner.get_program_info_log(program)
+    }
+
+    fn use_program(&self, program: Option<&Self::Program>) {
+        let state = &mut *self.state.borrow_mut();
+        if update(&mut state.program, program.cloned(), &mut state.counters.use_program) {
+            self.inner.use_program(program);
+        }
+    }
+
+    fn get_program_parameter(&self, program: &Self::Program, pname: u32) -> i32 {
+        self.inner.get_program_parameter(program, pname)
+    }
+
+    // uniforms
+
+    fn get_active_uniform(&self, program: &Self::Program, index: u32) -> Option<ActiveInfo> {
+        self.inner.get_active_uniform(program, index)
+    }
+
+    fn get_active_attrib(&self, program: &Self::Program, index: u32) -> Option<ActiveInfo> {
+        self.inner.get_active_attrib(program, index)
+    }
+
+    fn get_attrib_location(&self, program: &Self::Program, name: &str) -> i32 {
+        self.inner.get_attrib_location(program, name)
+    }
+
+    fn get_uniform_location(&self, program: &Self::Program, name: &str) -> Option<Self::UniformLocation> {
+        self.inner.get_uniform_location(program, name)
+    }
+
+    fn uniform1f(&self, location: Option<&Self::UniformLocation>, v1: f32) {
+        self.inner.uniform1f(location, v1);
+    }
+
+    fn uniform2f(&self, location: Option<&Self::UniformLocation>, v1: f32, v2: f32) {
+        self.inner.uniform2f(location, v1, v2);
+    }
+
+    fn uniform3f(&self, location: Option<&Self::UniformLocation>, v1: f32, v2: f32, v3: f32) {
+        self.inner.uniform3f(location, v1, v2, v3);
+    }
+
+    fn uniform4f(&self, location: Option<&Self::UniformLocation>, v1: f32, v2: f32, v3: f32, v4: f32) {
+        self.inner.uniform4f(location, v1, v2, v3, v4);
+    }
+
+    fn uniform1i(&self, location: Option<&Self::UniformLocation>, v1: i32) {
+        self.inner.uniform1i(location, v1);
+    }
+
+    fn uniform2i(&self, location: Option<&Self::UniformLocation>, v1: i32, v2: i32) {
+        self.inner.uniform2i(location, v1, v2);
+    }
+
+    fn uniform3i(&self, location: Option<&Self::UniformLocation>, v1: i32, v2: i32, v3: i32) {
+        self.inner.uniform3i(location, v1, v2, v3);
+    }
+
+    fn uniform4i(&self, location: Option<&Self::UniformLocation>, v1: i32, v2: i32, v3: i32, v4: i32) {
+        self.inner.uniform4i(location, v1, v2, v3, v4);
+    }
+
+    fn uniform1ui(&self, location: Option<&Self::UniformLocation>, v1: u32) {
+        self.inner.uniform1ui(location, v1);
+    }
+
+    fn uniform2ui(&self, location: Option<&Self::UniformLocation>, v1: u32, v2: u32) {
+        self.inner.uniform2ui(location, v1, v2);
+    }
+
+    fn uniform3ui(&self, location: Option<&Self::UniformLocation>, v1: u32, v2: u32, v3: u32) {
+        self.inner.uniform3ui(location, v1, v2, v3);
+    }
+
+    fn uniform4ui(&self, location: Option<&Self::UniformLocation>, v1: u32, v2: u32, v3: u32, v4: u32) {
+        self.inner.uniform4ui(location, v1, v2, v3, v4);
+    }
+
+    fn uniform1fv(&self, location: Option<&Self::UniformLocation>, data: &[f32]) {
+        self.inner.uniform1fv(location, data);
+    }
+
+    fn uniform2fv(&self, location: Option<&Self::UniformLocation>, data: &[f32]) {
+        self.inner.uniform2fv(location, data);
+    }
+
+    fn uniform3fv(&self, location: Option<&Self::UniformLocation>, data: &[f32]) {
+        self.inner.uniform3fv(location, data);
+    }
+
+    fn uniform4fv(&self, location: Option<&Self::UniformLocation>, data: &[f32]) {
+        self.inner.uniform4fv(location, data);
+    }
+
+    fn uniform1iv(&self, location: Option<&Self::UniformLocation>, data: &[i32]) {
+        self.inner.uniform1iv(location, data);
+    }
+
+    fn uniform1uiv(&self, location: Option<&Self::UniformLocation>, data: &[u32]) {
+        self.inner.uniform1uiv(location, data);
+    }
+
+    fn uniform_matrix2fv(&self, location: Option<&Self::UniformLocation>, transpose: bool, data: &[f32]) {
+        self.inner.uniform_matrix2fv(location, transpose, data);
+    }
+
+    fn uniform_matrix3fv(&self, location: Option<&Self::UniformLocation>, transpose: bool, data: &[f32]) {
+        self.inner.uniform_matrix3fv(location, transpose, data);
+    }
+
+    fn uniform_matrix4fv(&self, location: Option<&Self::UniformLocation>, transpose: bool, data: &[f32]) {
+        self.inner.uniform_matrix4fv(location, transpose, data);
+    }
+
+    // uniform blocks
+
+    fn get_uniform_block_index(&self, program: &Self::Program, name: &str) -> u32 {
+        self.inner.get_uniform_block_index(program, name)
+    }
+
+    fn uniform_block_binding(&self, program: &Self::Program, block_index: u32, binding: u32) {
+        self.inner.uniform_block_binding(program, block_index, binding);
+    }
+
+    // buffers
+
+    fn create_buffer(&self) -> Option<Self::Buffer> {
+        self.inner.create_buffer()
+    }
+
+    /// Deleted buffer is unbound from all targets of the current VAO and context.
+    fn delete_buffer(&self, buffer: Option<&Self::Buffer>) {
+        if let Some(buffer) = buffer {
+            let mut state = self.state.borrow_mut();
+            if state.element_buffer.as_ref().map(|b| b.as_ref()) == Some(Some(buffer)) {
+                state.element_buffer = None;
+            }
+            state.buffers.retain(|(_, b)| b.as_ref() != Some(buffer));
+        }
+        self.inner.delete_buffer(buffer);
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<&Self::Buffer>) {
+        let state = &mut *self.state.borrow_mut();
+        let changed = if target == crate::AppContext::ELEMENT_ARRAY_BUFFER {
+            update(&mut state.element_buffer, buffer.cloned(), &mut state.counters.bind_buffer)
+        } else {
+            update_keyed(&mut state.buffers, target, buffer.cloned(), &mut state.counters.bind_buffer)
+        };
+        if changed {
+            self.inner.bind_buffer(target, buffer);
+        }
+    }
+
+    /// Indexed binding also changes the generic binding of the target.
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<&Self::Buffer>) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.buffers.retain(|(t, _)| *t != target);
+            state.buffers.push((target, buffer.cloned()));
+        }
+        self.inner.bind_buffer_base(target, index, buffer);
+    }
+
+    fn buffer_data_with_size(&self, target: u32, size: i32, usage: u32) {
+        self.inner.buffer_data_with_size(target, size, usage);
+    }
+
+    fn buffer_data_with_u8_array(&self, target: u32, data: &[u8], usage: u32) {
+        self.inner.buffer_data_with_u8_array(target, data, usage);
+    }
+
+    fn buffer_sub_data_with_u8_array(&self, target: u32, offset: i32, data: &[u8]) {
+        self.inner.buffer_sub_data_with_u8_array(target, offset, data);
+    }
+
+    // vertex arrays
+
+    fn create_vertex_array(&self) -> Option<Self::VertexArray> {
+        self.inner.create_vertex_array()
+    }
+
+    fn delete_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+        {
+            let mut state = self.state.borrow_mut();
+            if vertex_array.is_some() && state.vertex_array.as_ref().map(|v| v.as_ref()) == Some(vertex_array) {
+                state.vertex_array = None;
+                state.element_buffer = None;
+            }
+        }
+        self.inner.delete_vertex_array(vertex_array);
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+        let state = &mut *self.state.borrow_mut();
+        if update(&mut state.vertex_array, vertex_array.cloned(), &mut state.counters.bind_vertex_array) {
+            state.element_buffer = None;
+            self.inner.bind_vertex_array(vertex_array);
+        }
+    }
+
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32) {
+        self.inner.vertex_attrib_pointer(index, size, data_type, normalized, stride, offset);
+    }
+
+    fn vertex_attrib_i_pointer(&self, index: u32, size: i32, data_type: u32, stride: i32, offset: i32) {
+        self.inner.vertex_attrib_i_pointer(index, size, data_type, stride, offset);
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.inner.enable_vertex_attrib_array(index);
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.inner.vertex_attrib_divisor(index, divisor);
+    }
+
+    // draw calls
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        self.inner.draw_arrays(mode, first, count);
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, data_type: u32, offset: i32) {
+        self.inner.draw_elements(mode, count, data_type, offset);
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instance_count: i32) {
+        self.inner.draw_arrays_instanced(mode, first, count, instance_count);
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, data_type: u32, offset: i32, instance_count: i32) {
+        self.inner.draw_elements_instanced(mode, count, data_type, offset, instance_count);
+    }
+
+    // textures
+
+    fn create_texture(&self) -> Option<Self::Texture> {
+        self.inner.create_texture()
+    }
+
+    /// Deleted texture is unbound from all texture units.
+    fn delete_texture(&self, texture: Option<&Self::Texture>) {
+        if let Some(texture) = texture {
+            self.state.borrow_mut().textures.retain(|(_, t)| t.as_ref() != Some(texture));
+        }
+        self.inner.delete_texture(texture);
+    }
+
+    /// Binding is cached per texture unit, so it is not cached when the active unit is unknown.
+    fn bind_texture(&self, target: u32, texture: Option<&Self::Texture>) {
+        let state = &mut *self.state.borrow_mut();
+        let changed = match state.active_texture {
+            Some(unit) => update_keyed(&mut state.textures, (unit, target), texture.cloned(), &mut state.counters.bind_texture),
+            None => {
+                state.counters.bind_texture.issued += 1;
+                true
+            }
+        };
+        if changed {
+            self.inner.bind_texture(target, texture);
+        }
+    }
+
+    fn active_texture(&self, texture: u32) {
+        let state = &mut *self.state.borrow_mut();
+        if update(&mut state.active_texture, texture, &mut state.counters.active_texture) {
+            self.inner.active_texture(texture);
+        }
+    }
+
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32) {
+        self.inner.tex_parameteri(target, pname, param);
+    }
+
+    fn tex_parameterf(&self, target: u32, pname: u32, param: f32) {
+        self.inner.tex_parameterf(target, pname, param);
+    }
+
+    fn generate_mipmap(&self, target: u32) {
+        self.inner.generate_mipmap(target);
+    }
+
+    fn pixel_storei(&self, pname: u32, param: i32) {
+        self.inner.pixel_storei(pname, param);
+    }
+
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        self.inner
+            .tex_image_2d_with_html_image_element(target, level, internal_format, format, data_type, img)
+    }
+
+    fn tex_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> SWGLResult<()> {
+        self.inner
+            .tex_image_2d_with_u8_array(target, level, internal_format, width, height, format, data_type, pixels)
+    }
+
+    fn tex_image_3d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        border: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        self.inner.tex_image_3d_with_html_image_element(
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            depth,
+            border,
+            format,
+            data_type,
+            img,
+        )
+    }
+
+    fn tex_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> SWGLResult<()> {
+        self.inner
+            .tex_image_3d_with_u8_array(target, level, internal_format, width, height, depth, format, data_type, pixels)
+    }
+
+    fn tex_sub_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: &[u8],
+    ) -> SWGLResult<()> {
+        self.inner
+            .tex_sub_image_2d_with_u8_array(target, level, x_offset, y_offset, width, height, format, data_type, pixels)
+    }
+
+    fn tex_sub_image_3d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        self.inner.tex_sub_image_3d_with_html_image_element(
+            target, level, x_offset, y_offset, z_offset, width, height, depth, format, data_type, img,
+        )
+    }
+
+    fn tex_sub_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pixels: &[u8],
+    ) -> SWGLResult<()> {
+        self.inner.tex_sub_image_3d_with_u8_array(
+            target, level, x_offset, y_offset, z_offset, width, height, depth, format, data_type, pixels,
+        )
+    }
+
+    fn compressed_tex_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        data: &[u8],
+    ) {
+        self.inner.compressed_tex_image_2d_with_u8_array(target, level, internal_format, width, height, data)
+    }
+
+    fn compressed_tex_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        data: &[u8],
+    ) {
+        self.inner
+            .compressed_tex_image_3d_with_u8_array(target, level, internal_format, width, height, depth, data)
+    }
+
+    // samplers
+
+    fn create_sampler(&self) -> Option<Self::Sampler> {
+        self.inner.create_sampler()
+    }
+
+    /// Deleted sampler is unbound from all texture units.
+    fn delete_sampler(&self, sampler: Option<&Self::Sampler>) {
+        if let Some(sampler) = sampler {
+            self.state.borrow_mut().samplers.retain(|(_, s)| s.as_ref() != Some(sampler));
+        }
+        self.inner.delete_sampler(sampler);
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<&Self::Sampler>) {
+        let state = &mut *self.state.borrow_mut();
+        if update_keyed(&mut state.samplers, unit, sampler.cloned(), &mut state.counters.bind_sampler) {
+            self.inner.bind_sampler(unit, sampler);
+        }
+    }
+
+    fn sampler_parameteri(&self, sampler: &Self::Sampler, pname: u32, param: i32) {
+        self.inner.sampler_parameteri(sampler, pname, param);
+    }
+
+    fn sampler_parameterf(&self, sampler: &Self::Sampler, pname: u32, param: f32) {
+        self.inner.sampler_parameterf(sampler, pname, param);
+    }
+
+    // framebuffers
+
+    fn create_framebuffer(&self) -> Option<Self::Framebuffer> {
+        self.inner.create_framebuffer()
+    }
+
+    /// Deleted framebuffer is unbound (the default framebuffer is bound instead).
+    fn delete_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) {
+        if framebuffer.is_some() {
+            let mut state = self.state.borrow_mut();
+            if state.draw_framebuffer.as_ref().map(|f| f.as_ref()) == Some(framebuffer) {
+                state.draw_framebuffer = None;
+            }
+            if state.read_framebuffer.as_ref().map(|f| f.as_ref()) == Some(framebuffer) {
+                state.read_framebuffer = None;
+            }
+        }
+        self.inner.delete_framebuffer(framebuffer);
+    }
+
+    /// FRAMEBUFFER target changes both draw and read bindings, so it is skipped only if both are the same.
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Self::Framebuffer>) {
+        let state = &mut *self.state.borrow_mut();
+        let value = Some(framebuffer.cloned());
+        let changed = match target {
+            crate::AppContext::DRAW_FRAMEBUFFER => state.draw_framebuffer != value,
+            crate::AppContext::READ_FRAMEBUFFER => state.read_framebuffer != value,
+            _ => state.draw_framebuffer != value || state.read_framebuffer != value,
+        };
+
+        if !changed {
+            state.counters.bind_framebuffer.skipped += 1;
+            return;
+        }
+        if target != crate::AppContext::READ_FRAMEBUFFER {
+            state.draw_framebuffer = value.clone();
+        }
+        if target != crate::AppContext::DRAW_FRAMEBUFFER {
+            state.read_framebuffer = value;
+        }
+        state.counters.bind_framebuffer.issued += 1;
+        self.inner.bind_framebuffer(target, framebuffer);
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, tex_target: u32, texture: Option<&Self::Texture>, level: i32) {
+        self.inner.framebuffer_texture_2d(target, attachment, tex_target, texture, level);
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffer_target: u32, renderbuffer: Option<&Self::Renderbuffer>) {
+        self.inner.framebuffer_renderbuffer(target, attachment, renderbuffer_target, renderbuffer);
+    }
+
+    fn check_framebuffer_status(&self, target: u32) -> u32 {
+        self.inner.check_framebuffer_status(target)
+    }
+
+    fn create_renderbuffer(&self) -> Option<Self::Renderbuffer> {
+        self.inner.create_renderbuffer()
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: Option<&Self::Renderbuffer>) {
+        self.inner.delete_renderbuffer(renderbuffer);
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<&Self::Renderbuffer>) {
+        self.inner.bind_renderbuffer(target, renderbuffer);
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32) {
+        self.inner.renderbuffer_storage(target, internal_format, width, height);
+    }
+
+    fn renderbuffer_storage_multisample(&self, target: u32, samples: i32, internal_format: u32, width: i32, height: i32) {
+        self.inner.renderbuffer_storage_multisample(target, samples, internal_format, width, height);
+    }
+
+    fn blit_framebuffer(
+        &self,
+        src_x0: i32,
+        src_y0: i32,
+        src_x1: i32,
+        src_y1: i32,
+        dst_x0: i32,
+        dst_y0: i32,
+        dst_x1: i32,
+        dst_y1: i32,
+        mask: u32,
+        filter: u32,
+    ) {
+        self.inner
+            .blit_framebuffer(src_x0, src_y0, src_x1, src_y1, dst_x0, dst_y0, dst_x1, dst_y1, mask, filter);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::recording_backend::{GlCall, RecordingBackend};
+
+    fn cached() -> (CachedBackend<RecordingBackend>, RecordingBackend) {
+        let recording = RecordingBackend::new();
+        (CachedBackend::new(recording.clone()), recording)
+    }
+
+    #[test]
+    fn skips_repeated_bindings() {
+        let (context, recording) = cached();
+        let program = context.create_program().unwrap();
+        let vertex_array = context.create_vertex_array().unwrap();
+        let texture = context.create_texture().unwrap();
+        recording.clear_calls();
+
+        for _ in 0..3 {
+            context.use_program(Some(&program));
+            context.bind_vertex_array(Some(&vertex_array));
+            context.active_texture(crate::AppContext::TEXTURE0);
+            context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&texture));
+        }
+        assert_eq!(
+            recording.calls(),
+            vec![
+                GlCall::UseProgram { program: Some(program) },
+                GlCall::BindVertexArray { vertex_array: Some(vertex_array) },
+                GlCall::ActiveTexture { texture: crate::AppContext::TEXTURE0 },
+                GlCall::BindTexture { target: crate::AppContext::TEXTURE_2D, texture: Some(texture) },
+            ]
+        );
+
+        let counters = context.get_counters();
+        assert_eq!(counters.use_program, CallCounter { issued: 1, skipped: 2 });
+        assert_eq!(counters.bind_vertex_array, CallCounter { issued: 1, skipped: 2 });
+        assert_eq!(counters.active_texture, CallCounter { issued: 1, skipped: 2 });
+        assert_eq!(counters.bind_texture, CallCounter { issued: 1, skipped: 2 });
+        assert_eq!((counters.get_total_issued(), counters.get_total_skipped()), (4, 8));
+
+        // unbinding is a change too
+        context.use_program(None);
+        assert_eq!(recording.calls().last(), Some(&GlCall::UseProgram { program: None }));
+    }
+
+    #[test]
+    fn forgets_bindings_of_deleted_objects() {
+        let (context, recording) = cached();
+        let program = context.create_program().unwrap();
+        let vertex_array = context.create_vertex_array().unwrap();
+        let texture = context.create_texture().unwrap();
+        let sampler = context.create_sampler().unwrap();
+        context.use_program(Some(&program));
+        context.bind_vertex_array(Some(&vertex_array));
+        context.active_texture(crate::AppContext::TEXTURE0);
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&texture));
+        context.bind_sampler(0, Some(&sampler));
+
+        context.delete_program(Some(&program));
+        context.delete_vertex_array(Some(&vertex_array));
+        context.delete_texture(Some(&texture));
+        context.delete_sampler(Some(&sampler));
+        recording.clear_calls();
+
+        // GL implementations may reuse names of deleted objects, so the next bindings have to be issued
+        context.use_program(Some(&program));
+        context.bind_vertex_array(Some(&vertex_array));
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&texture));
+        context.bind_sampler(0, Some(&sampler));
+        assert_eq!(recording.calls().len(), 4);
+        assert_eq!(context.get_counters().get_total_skipped(), 0);
+    }
+
+    #[test]
+    fn tracks_textures_and_samplers_per_unit() {
+        let (context, recording) = cached();
+        let first = context.create_texture().unwrap();
+        let second = context.create_texture().unwrap();
+        let sampler = context.create_sampler().unwrap();
+
+        // binding without known active unit is always issued
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&first));
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&first));
+        assert_eq!(context.get_counters().bind_texture, CallCounter { issued: 2, skipped: 0 });
+
+        context.active_texture(crate::AppContext::TEXTURE0);
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&first));
+        context.active_texture(crate::AppContext::TEXTURE1);
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&second));
+        // other target of the same unit is a separate binding
+        context.bind_texture(crate::AppContext::TEXTURE_2D_ARRAY, Some(&second));
+        recording.clear_calls();
+        context.reset_counters();
+
+        context.active_texture(crate::AppContext::TEXTURE0);
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&first));
+        context.active_texture(crate::AppContext::TEXTURE1);
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&second));
+        context.bind_texture(crate::AppContext::TEXTURE_2D, Some(&first));
+        assert_eq!(
+            recording.calls(),
+            vec![
+                GlCall::ActiveTexture { texture: crate::AppContext::TEXTURE0 },
+                GlCall::ActiveTexture { texture: crate::AppContext::TEXTURE1 },
+                GlCall::BindTexture { target: crate::AppContext::TEXTURE_2D, texture: Some(first) },
+            ]
+        );
+
+        context.bind_sampler(0, Some(&sampler));
+        context.bind_sampler(1, Some(&sampler));
+        context.bind_sampler(0, Some(&sampler));
+        context.bind_sampler(1, None);
+        assert_eq!(context.get_counters().bind_sampler, CallCounter { issued: 3, skipped: 1 });
+        assert_eq!((recording.bound_sampler(0), recording.bound_sampler(1)), (Some(sampler), None));
+    }
+
+    #[test]
+    fn invalidate_forgets_state_and_keeps_counters() {
+        let (context, recording) = cached();
+        let program = context.create_program().unwrap();
+        context.use_program(Some(&program));
+        context.use_program(Some(&program));
+
+        // calls made directly on the wrapped backend are not visible to the cache
+        context.get_inner().use_program(None);
+        context.invalidate();
+        context.use_program(Some(&program));
+        assert_eq!(recording.current_program(), Some(program));
+        assert_eq!(context.get_counters().use_program, CallCounter { issued: 2, skipped: 1 });
+    }
+}
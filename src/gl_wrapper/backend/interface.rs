@@ -0,0 +1,416 @@
+use web_sys::HtmlImageElement;
+use super::super::super::runtime_error::SWGLResult;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Enum values of EXT_texture_filter_anisotropic extension (they are not constants of `crate::AppContext`).
+pub const TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FE;
+pub const MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FF;
+
+/// Compressed formats of WEBGL_compressed_texture_s3tc and WEBGL_compressed_texture_s3tc_srgb extensions.
+pub const COMPRESSED_RGB_S3TC_DXT1_EXT: u32 = 0x83F0;
+pub const COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 0x83F1;
+pub const COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = 0x83F2;
+pub const COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 0x83F3;
+pub const COMPRESSED_SRGB_S3TC_DXT1_EXT: u32 = 0x8C4C;
+pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: u32 = 0x8C4D;
+pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: u32 = 0x8C4E;
+pub const COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: u32 = 0x8C4F;
+
+/// Compressed formats of WEBGL_compressed_texture_etc extension.
+pub const COMPRESSED_RGB8_ETC2: u32 = 0x9274;
+pub const COMPRESSED_SRGB8_ETC2: u32 = 0x9275;
+pub const COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32 = 0x9276;
+pub const COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32 = 0x9277;
+pub const COMPRESSED_RGBA8_ETC2_EAC: u32 = 0x9278;
+pub const COMPRESSED_SRGB8_ALPHA8_ETC2_EAC: u32 = 0x9279;
+
+/// The first compressed formats of WEBGL_compressed_texture_astc extension (4x4 blocks), formats of larger blocks
+/// follow them.
+pub const COMPRESSED_RGBA_ASTC_4X4_KHR: u32 = 0x93B0;
+pub const COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR: u32 = 0x93D0;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Description of active uniform or attribute (the same data as WebGLActiveInfo).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveInfo {
+    pub name: String,
+    pub size: i32,
+    pub data_type: u32,
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This trait describes every low level GL call used by gl_wrapper types and renderers.
+/// Method names and parameters follow WebGL2 (enum values are the same as `crate::AppContext` constants),
+/// so the implementation for WebGl2RenderingContext is a simple forwarding.
+/// Cloned backend must refer to the same context (like clone of WebGl2RenderingContext, which is a new handle
+/// of the same JS object), gl_wrapper types keep a clone to delete their GL objects on drop.
+/// Object handles are comparable, so bindings can be cached (see cached_backend::CachedBackend).
+pub trait GlBackend: Clone {
+    type Buffer: Clone + PartialEq;
+    type VertexArray: Clone + PartialEq;
+    type Texture: Clone + PartialEq;
+    type Shader: Clone + PartialEq;
+    type Program: Clone + PartialEq;
+    type Framebuffer: Clone + PartialEq;
+    type Renderbuffer: Clone + PartialEq;
+    type Sampler: Clone + PartialEq;
+    type UniformLocation: Clone;
+
+    // basics
+
+    fn clear(&self, mask: u32);
+    /// This method returns numeric context parameter (e.g. MAX_SAMPLES).
+    fn get_parameter_i32(&self, pname: u32) -> i32;
+    /// This method enables WebGL extension (e.g. EXT_color_buffer_float) and returns false if it is not supported.
+    fn enable_extension(&self, name: &str) -> bool;
+    /// This method enables capability like BLEND or DEPTH_TEST.
+    fn enable(&self, cap: u32);
+    fn disable(&self, cap: u32);
+    fn blend_func(&self, sfactor: u32, dfactor: u32);
+    fn blend_equation(&self, mode: u32);
+    fn depth_func(&self, func: u32);
+    fn depth_mask(&self, flag: bool);
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32);
+    /// This method sets blend factors separately for RGB and alpha components.
+    fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32);
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32);
+    /// This method sets stencil test for both front and back faces.
+    fn stencil_func(&self, func: u32, reference: i32, mask: u32);
+    fn stencil_op(&self, fail: u32, depth_fail: u32, pass: u32);
+    fn stencil_mask(&self, mask: u32);
+    fn cull_face(&self, mode: u32);
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32);
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool);
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+    fn clear_depth(&self, depth: f32);
+    fn clear_stencil(&self, stencil: i32);
+
+    // shaders & programs
+
+    fn create_shader(&self, shader_type: u32) -> Option<Self::Shader>;
+    fn shader_source(&self, shader: &Self::Shader, source: &str);
+    fn compile_shader(&self, shader: &Self::Shader);
+    fn get_shader_info_log(&self, shader: &Self::Shader) -> Option<String>;
+
+    fn create_program(&self) -> Option<Self::Program>;
+    fn delete_program(&self, program: Option<&Self::Program>);
+    fn delete_shader(&self, shader: Option<&Self::Shader>);
+    fn attach_shader(&self, program: &Self::Program, shader: &Self::Shader);
+    fn detach_shader(&self, program: &Self::Program, shader: &Self::Shader);
+    fn link_program(&self, program: &Self::Program);
+    fn get_program_info_log(&self, program: &Self::Program) -> Option<String>;
+    fn use_program(&self, program: Option<&Self::Program>);
+    /// This method returns numeric (or boolean as 0/1) program parameter, e.g. ACTIVE_UNIFORMS.
+    fn get_program_parameter(&self, program: &Self::Program, pname: u32) -> i32;
+
+    // uniforms
+
+    fn get_active_uniform(&self, program: &Self::Program, index: u32) -> Option<ActiveInfo>;
+    fn get_active_attrib(&self, program: &Self::Program, index: u32) -> Option<ActiveInfo>;
+    /// This method returns -1 if there is no active attribute of given name.
+    fn get_attrib_location(&self, program: &Self::Program, name: &str) -> i32;
+    fn get_uniform_location(&self, program: &Self::Program, name: &str) -> Option<Self::UniformLocation>;
+
+    fn uniform1f(&self, location: Option<&Self::UniformLocation>, v1: f32);
+    fn uniform2f(&self, location: Option<&Self::UniformLocation>, v1: f32, v2: f32);
+    fn uniform3f(&self, location: Option<&Self::UniformLocation>, v1: f32, v2: f32, v3: f32);
+    fn uniform4f(&self, location: Option<&Self::UniformLocation>, v1: f32, v2: f32, v3: f32, v4: f32);
+
+    fn uniform1i(&self, location: Option<&Self::UniformLocation>, v1: i32);
+    fn uniform2i(&self, location: Option<&Self::UniformLocation>, v1: i32, v2: i32);
+    fn uniform3i(&self, location: Option<&Self::UniformLocation>, v1: i32, v2: i32, v3: i32);
+    fn uniform4i(&self, location: Option<&Self::UniformLocation>, v1: i32, v2: i32, v3: i32, v4: i32);
+
+    fn uniform1ui(&self, location: Option<&Self::UniformLocation>, v1: u32);
+    fn uniform2ui(&self, location: Option<&Self::UniformLocation>, v1: u32, v2: u32);
+    fn uniform3ui(&self, location: Option<&Self::UniformLocation>, v1: u32, v2: u32, v3: u32);
+    fn uniform4ui(&self, location: Option<&Self::UniformLocation>, v1: u32, v2: u32, v3: u32, v4: u32);
+
+    fn uniform1fv(&self, location: Option<&Self::UniformLocation>, data: &[f32]);
+    fn uniform2fv(&self, location: Option<&Self::UniformLocation>, data: &[f32]);
+    fn uniform3fv(&self, location: Option<&Self::UniformLocation>, data: &[f32]);
+    fn uniform4fv(&self, location: Option<&Self::UniformLocation>, data: &[f32]);
+    fn uniform1iv(&self, location: Option<&Self::UniformLocation>, data: &[i32]);
+    fn uniform1uiv(&self, location: Option<&Self::UniformLocation>, data: &[u32]);
+
+    fn uniform_matrix2fv(&self, location: Option<&Self::UniformLocation>, transpose: bool, data: &[f32]);
+    fn uniform_matrix3fv(&self, location: Option<&Self::UniformLocation>, transpose: bool, data: &[f32]);
+    fn uniform_matrix4fv(&self, location: Option<&Self::UniformLocation>, transpose: bool, data: &[f32]);
+
+    // uniform blocks
+
+    /// This method returns INVALID_INDEX if there is no active uniform block of given name.
+    fn get_uniform_block_index(&self, program: &Self::Program, name: &str) -> u32;
+    fn uniform_block_binding(&self, program: &Self::Program, block_index: u32, binding: u32);
+
+    // buffers
+
+    fn create_buffer(&self) -> Option<Self::Buffer>;
+    fn delete_buffer(&self, buffer: Option<&Self::Buffer>);
+    fn bind_buffer(&self, target: u32, buffer: Option<&Self::Buffer>);
+    /// This method binds buffer to indexed binding point (e.g. UNIFORM_BUFFER binding used by uniform blocks).
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<&Self::Buffer>);
+    /// This method allocates buffer storage of given size (in bytes) without data.
+    fn buffer_data_with_size(&self, target: u32, size: i32, usage: u32);
+    fn buffer_data_with_u8_array(&self, target: u32, data: &[u8], usage: u32);
+    /// This method gets offset parameter in bytes.
+    fn buffer_sub_data_with_u8_array(&self, target: u32, offset: i32, data: &[u8]);
+
+    // vertex arrays
+
+    fn create_vertex_array(&self) -> Option<Self::VertexArray>;
+    fn delete_vertex_array(&self, vertex_array: Option<&Self::VertexArray>);
+    fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>);
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32);
+    /// This method sets pointer of integer attribute (values are not converted to float).
+    fn vertex_attrib_i_pointer(&self, index: u32, size: i32, data_type: u32, stride: i32, offset: i32);
+    fn enable_vertex_attrib_array(&self, index: u32);
+    /// This method sets how many instances use the same attribute value (0 means per-vertex attribute).
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32);
+
+    // draw calls
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32);
+    /// This method gets offset parameter in bytes.
+    fn draw_elements(&self, mode: u32, count: i32, data_type: u32, offset: i32);
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instance_count: i32);
+    /// This method gets offset parameter in bytes.
+    fn draw_elements_instanced(&self, mode: u32, count: i32, data_type: u32, offset: i32, instance_count: i32);
+
+    // textures
+
+    fn create_texture(&self) -> Option<Self::Texture>;
+    fn delete_texture(&self, texture: Option<&Self::Texture>);
+    fn bind_texture(&self, target: u32, texture: Option<&Self::Texture>);
+    fn active_texture(&self, texture: u32);
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32);
+    fn tex_parameterf(&self, target: u32, pname: u32, param: f32);
+    fn generate_mipmap(&self, target: u32);
+    /// This method sets pixel storage mode used by the next uploads (e.g. UNPACK_ALIGNMENT).
+    fn pixel_storei(&self, pname: u32, param: i32);
+
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()>;
+
+    /// This method allocates texture storage and fills it with pixels (None leaves the texture uninitialized).
+    #[allow(clippy::too_many_arguments)]
+    fn tex_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> SWGLResult<()>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn tex_image_3d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        border: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()>;
+
+    /// This method allocates storage of 3D texture or texture array and fills it with pixels (None leaves the
+    /// texture uninitialized).
+    #[allow(clippy::too_many_arguments)]
+    fn tex_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> SWGLResult<()>;
+
+    /// This method replaces pixels of rectangle inside already allocated texture.
+    #[allow(clippy::too_many_arguments)]
+    fn tex_sub_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: &[u8],
+    ) -> SWGLResult<()>;
+
+    /// This method replaces pixels of box inside already allocated 3D texture or texture array with image (its part
+    /// can be selected with UNPACK_SKIP_PIXELS, UNPACK_SKIP_ROWS and UNPACK_ROW_LENGTH pixel storage modes).
+    #[allow(clippy::too_many_arguments)]
+    fn tex_sub_image_3d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()>;
+
+    /// This method replaces pixels of box inside already allocated 3D texture or texture array.
+    #[allow(clippy::too_many_arguments)]
+    fn tex_sub_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pixels: &[u8],
+    ) -> SWGLResult<()>;
+
+    /// This method allocates storage of texture level and fills it with blocks of compressed format (the extension
+    /// of the format has to be enabled).
+    fn compressed_tex_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        data: &[u8],
+    );
+
+    /// This method allocates storage of texture array level and fills it with blocks of compressed format.
+    #[allow(clippy::too_many_arguments)]
+    fn compressed_tex_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        data: &[u8],
+    );
+
+    // samplers
+
+    fn create_sampler(&self) -> Option<Self::Sampler>;
+    fn delete_sampler(&self, sampler: Option<&Self::Sampler>);
+    /// This method binds sampler to texture unit (unit is an index, not TEXTURE0 + index), its parameters override
+    /// parameters of the texture bound to the unit. None restores parameters of the texture.
+    fn bind_sampler(&self, unit: u32, sampler: Option<&Self::Sampler>);
+    fn sampler_parameteri(&self, sampler: &Self::Sampler, pname: u32, param: i32);
+    fn sampler_parameterf(&self, sampler: &Self::Sampler, pname: u32, param: f32);
+
+    // framebuffers
+
+    fn create_framebuffer(&self) -> Option<Self::Framebuffer>;
+    fn delete_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>);
+    /// This method binds framebuffer to FRAMEBUFFER (both draw and read), DRAW_FRAMEBUFFER or READ_FRAMEBUFFER target.
+    /// None binds the default framebuffer (canvas).
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&Self::Framebuffer>);
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, tex_target: u32, texture: Option<&Self::Texture>, level: i32);
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffer_target: u32, renderbuffer: Option<&Self::Renderbuffer>);
+    /// This method returns FRAMEBUFFER_COMPLETE or the reason why the bound framebuffer can't be used.
+    fn check_framebuffer_status(&self, target: u32) -> u32;
+
+    fn create_renderbuffer(&self) -> Option<Self::Renderbuffer>;
+    fn delete_renderbuffer(&self, renderbuffer: Option<&Self::Renderbuffer>);
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<&Self::Renderbuffer>);
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32);
+    fn renderbuffer_storage_multisample(&self, target: u32, samples: i32, internal_format: u32, width: i32, height: i32);
+    /// This method copies rectangle from READ_FRAMEBUFFER to DRAW_FRAMEBUFFER (it also resolves multisampled images).
+    #[allow(clippy::too_many_arguments)]
+    fn blit_framebuffer(
+        &self,
+        src_x0: i32,
+        src_y0: i32,
+        src_x1: i32,
+        src_y1: i32,
+        dst_x0: i32,
+        dst_y0: i32,
+        dst_x1: i32,
+        dst_y1: i32,
+        mask: u32,
+        filter: u32,
+    );
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This trait is implemented only by plain numeric types (u8, u16, u32, f32, ...), whose values have no padding
+/// bytes and can be uploaded as raw memory (see as_byte_slice). It is sealed, so types of other crates can't
+/// implement it and traits requiring it (IndexType, PixelType) can't be implemented for them either.
+///
+/// ```compile_fail
+/// use swgl::gl_wrapper::texture::texture_config::PixelType;
+///
+/// // the padding between fields would be uploaded as uninitialized memory
+/// #[derive(Clone, Copy)]
+/// struct Padded(u8, u32);
+///
+/// impl PixelType for Padded {
+///     fn get_gl_property_value() -> u32 {
+///         0
+///     }
+/// }
+/// ```
+pub trait PlainData: Copy + sealed::Sealed {}
+
+impl PlainData for u8 {}
+impl PlainData for i8 {}
+impl PlainData for u16 {}
+impl PlainData for i16 {}
+impl PlainData for u32 {}
+impl PlainData for i32 {}
+impl PlainData for f32 {}
+
+/// This function returns raw memory view of plain numeric data (f32, u32, ...) for buffer uploads.
+pub(crate) fn as_byte_slice<T: PlainData>(data: &[T]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data)) }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// private:
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for i8 {}
+    impl Sealed for u16 {}
+    impl Sealed for i16 {}
+    impl Sealed for u32 {}
+    impl Sealed for i32 {}
+    impl Sealed for f32 {}
+}
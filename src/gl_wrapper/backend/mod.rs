@@ -0,0 +1,7 @@
+//! Backend abstraction over low level GL calls. gl_wrapper types and renderers are generic over GlBackend,
+//! WebGl2RenderingContext is used by default. CachedBackend can wrap any backend to skip redundant state changes.
+
+pub mod interface;
+pub mod web_gl_backend;
+pub mod recording_backend;
+pub mod cached_backend;
@@ -0,0 +1,1377 @@
+//! Headless GlBackend implementation. It does not render anything, it only records every call
+//! and keeps simple in-memory state (buffer contents, uniform values), so gl_wrapper types and renderers
+//! can be tested natively with `cargo test`. GLSL "#error message" directive makes shader compilation fail,
+//! so error reporting can be tested too.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use web_sys::HtmlImageElement;
+use super::interface::{self, ActiveInfo, GlBackend};
+use super::super::super::runtime_error::SWGLResult;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Every object created by RecordingBackend is identified by unique, non-zero number.
+pub type RecordedId = u32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedUniformLocation {
+    pub program: RecordedId,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniformData {
+    Float(Vec<f32>),
+    Int(Vec<i32>),
+    UInt(Vec<u32>),
+    Matrix2(Vec<f32>),
+    Matrix3(Vec<f32>),
+    Matrix4(Vec<f32>),
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlCall {
+    Clear { mask: u32 },
+    Enable { cap: u32 },
+    Disable { cap: u32 },
+    BlendFunc { sfactor: u32, dfactor: u32 },
+    BlendEquation { mode: u32 },
+    DepthFunc { func: u32 },
+    DepthMask { flag: bool },
+    Viewport { x: i32, y: i32, width: i32, height: i32 },
+    BlendFuncSeparate { src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32 },
+    BlendEquationSeparate { mode_rgb: u32, mode_alpha: u32 },
+    StencilFunc { func: u32, reference: i32, mask: u32 },
+    StencilOp { fail: u32, depth_fail: u32, pass: u32 },
+    StencilMask { mask: u32 },
+    CullFace { mode: u32 },
+    Scissor { x: i32, y: i32, width: i32, height: i32 },
+    ColorMask { red: bool, green: bool, blue: bool, alpha: bool },
+    ClearColor { red: f32, green: f32, blue: f32, alpha: f32 },
+    ClearDepth { depth: f32 },
+    ClearStencil { stencil: i32 },
+
+    CreateShader { shader_type: u32, shader: RecordedId },
+    ShaderSource { shader: RecordedId, source: String },
+    CompileShader { shader: RecordedId },
+    DeleteShader { shader: Option<RecordedId> },
+    CreateProgram { program: RecordedId },
+    DeleteProgram { program: Option<RecordedId> },
+    AttachShader { program: RecordedId, shader: RecordedId },
+    DetachShader { program: RecordedId, shader: RecordedId },
+    LinkProgram { program: RecordedId },
+    UseProgram { program: Option<RecordedId> },
+
+    GetUniformLocation { program: RecordedId, name: String },
+    Uniform { location: Option<RecordedUniformLocation>, data: UniformData },
+    UniformBlockBinding { program: RecordedId, block_index: u32, binding: u32 },
+
+    CreateBuffer { buffer: RecordedId },
+    DeleteBuffer { buffer: Option<RecordedId> },
+    BindBuffer { target: u32, buffer: Option<RecordedId> },
+    BindBufferBase { target: u32, index: u32, buffer: Option<RecordedId> },
+    BufferData { target: u32, size: usize, usage: u32 },
+    BufferSubData { target: u32, offset: usize, size: usize },
+
+    CreateVertexArray { vertex_array: RecordedId },
+    DeleteVertexArray { vertex_array: Option<RecordedId> },
+    BindVertexArray { vertex_array: Option<RecordedId> },
+    VertexAttribPointer { index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32 },
+    VertexAttribIPointer { index: u32, size: i32, data_type: u32, stride: i32, offset: i32 },
+    EnableVertexAttribArray { index: u32 },
+    VertexAttribDivisor { index: u32, divisor: u32 },
+
+    DrawArrays { mode: u32, first: i32, count: i32 },
+    DrawElements { mode: u32, count: i32, data_type: u32, offset: i32 },
+    DrawArraysInstanced { mode: u32, first: i32, count: i32, instance_count: i32 },
+    DrawElementsInstanced { mode: u32, count: i32, data_type: u32, offset: i32, instance_count: i32 },
+
+    CreateTexture { texture: RecordedId },
+    DeleteTexture { texture: Option<RecordedId> },
+    BindTexture { target: u32, texture: Option<RecordedId> },
+    ActiveTexture { texture: u32 },
+    TexParameteri { target: u32, pname: u32, param: i32 },
+    TexParameterf { target: u32, pname: u32, param: f32 },
+    GenerateMipmap { target: u32 },
+    PixelStorei { pname: u32, param: i32 },
+    TexImage2D { target: u32, level: i32, internal_format: i32, format: u32, data_type: u32 },
+    TexImage3D { target: u32, level: i32, internal_format: i32, width: i32, height: i32, depth: i32, format: u32, data_type: u32 },
+    /// data_size is None when texture is allocated without pixels.
+    TexImage2DWithData { target: u32, level: i32, internal_format: i32, width: i32, height: i32, format: u32, data_type: u32, data_size: Option<usize> },
+    /// data_size is None when texture is allocated without pixels.
+    TexImage3DWithData {
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        data_size: Option<usize>,
+    },
+    TexSubImage2D { target: u32, level: i32, x_offset: i32, y_offset: i32, width: i32, height: i32, format: u32, data_type: u32, data_size: usize },
+    TexSubImage3DWithImage {
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+    },
+    TexSubImage3D {
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        data_size: usize,
+    },
+
+    CompressedTexImage2D { target: u32, level: i32, internal_format: u32, width: i32, height: i32, data_size: usize },
+    CompressedTexImage3D {
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        data_size: usize,
+    },
+
+    CreateSampler { sampler: RecordedId },
+    DeleteSampler { sampler: Option<RecordedId> },
+    BindSampler { unit: u32, sampler: Option<RecordedId> },
+    SamplerParameteri { sampler: RecordedId, pname: u32, param: i32 },
+    SamplerParameterf { sampler: RecordedId, pname: u32, param: f32 },
+
+    CreateFramebuffer { framebuffer: RecordedId },
+    DeleteFramebuffer { framebuffer: Option<RecordedId> },
+    BindFramebuffer { target: u32, framebuffer: Option<RecordedId> },
+    FramebufferTexture2D { target: u32, attachment: u32, tex_target: u32, texture: Option<RecordedId>, level: i32 },
+    FramebufferRenderbuffer { target: u32, attachment: u32, renderbuffer: Option<RecordedId> },
+    CreateRenderbuffer { renderbuffer: RecordedId },
+    DeleteRenderbuffer { renderbuffer: Option<RecordedId> },
+    BindRenderbuffer { target: u32, renderbuffer: Option<RecordedId> },
+    RenderbufferStorage { target: u32, internal_format: u32, width: i32, height: i32 },
+    RenderbufferStorageMultisample { target: u32, samples: i32, internal_format: u32, width: i32, height: i32 },
+    BlitFramebuffer {
+        read_framebuffer: Option<RecordedId>,
+        draw_framebuffer: Option<RecordedId>,
+        src: [i32; 4],
+        dst: [i32; 4],
+        mask: u32,
+        filter: u32,
+    },
+}
+
+impl GlCall {
+    pub fn is_draw_call(&self) -> bool {
+        matches!(
+            self,
+            Self::DrawArrays { .. }
+                | Self::DrawElements { .. }
+                | Self::DrawArraysInstanced { .. }
+                | Self::DrawElementsInstanced { .. }
+        )
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// private:
+
+#[derive(Default)]
+struct RecordedProgram {
+    shaders: Vec<RecordedId>,
+    uniforms: Vec<ActiveInfo>,
+    attributes: Vec<(ActiveInfo, i32)>,
+    /// Uniform block names with their bindings.
+    uniform_blocks: Vec<(String, u32)>,
+}
+
+#[derive(Default)]
+struct RecordingState {
+    last_id: RecordedId,
+    calls: Vec<GlCall>,
+    errors: Vec<String>,
+    shader_types: HashMap<RecordedId, u32>,
+    shader_sources: HashMap<RecordedId, String>,
+    programs: HashMap<RecordedId, RecordedProgram>,
+    current_program: Option<RecordedId>,
+    uniforms: HashMap<(RecordedId, String), UniformData>,
+    bound_buffers: HashMap<u32, RecordedId>,
+    indexed_buffers: HashMap<(u32, u32), RecordedId>,
+    buffers: HashMap<RecordedId, Vec<u8>>,
+    /// Created and not deleted objects of every type.
+    live_objects: HashSet<RecordedId>,
+    /// Samplers bound to texture units.
+    bound_samplers: HashMap<u32, RecordedId>,
+    /// Framebuffers bound to DRAW_FRAMEBUFFER and READ_FRAMEBUFFER targets.
+    bound_framebuffers: HashMap<u32, RecordedId>,
+    /// Attached textures and renderbuffers of every framebuffer.
+    framebuffer_attachments: HashMap<RecordedId, HashMap<u32, RecordedId>>,
+    /// Context parameters changed by set_parameter.
+    parameters: HashMap<u32, i32>,
+    /// Extensions disabled by set_extension_supported.
+    unsupported_extensions: HashSet<String>,
+    /// Status returned for bound framebuffers by check_framebuffer_status (see set_framebuffer_status).
+    framebuffer_status: Option<u32>,
+}
+
+impl RecordingState {
+    fn next_id(&mut self) -> RecordedId {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn bound_buffer_mut(&mut self, target: u32) -> Option<&mut Vec<u8>> {
+        let id = *self.bound_buffers.get(&target)?;
+        self.buffers.get_mut(&id)
+    }
+}
+
+/// This function maps GLSL type name to GL type enum value.
+fn glsl_type_to_gl(type_name: &str) -> u32 {
+    match type_name {
+        "float" => crate::AppContext::FLOAT,
+        "vec2" => crate::AppContext::FLOAT_VEC2,
+        "vec3" => crate::AppContext::FLOAT_VEC3,
+        "vec4" => crate::AppContext::FLOAT_VEC4,
+        "int" => crate::AppContext::INT,
+        "ivec2" => crate::AppContext::INT_VEC2,
+        "ivec3" => crate::AppContext::INT_VEC3,
+        "ivec4" => crate::AppContext::INT_VEC4,
+        "uint" => crate::AppContext::UNSIGNED_INT,
+        "uvec2" => crate::AppContext::UNSIGNED_INT_VEC2,
+        "uvec3" => crate::AppContext::UNSIGNED_INT_VEC3,
+        "uvec4" => crate::AppContext::UNSIGNED_INT_VEC4,
+        "bool" => crate::AppContext::BOOL,
+        "mat2" => crate::AppContext::FLOAT_MAT2,
+        "mat3" => crate::AppContext::FLOAT_MAT3,
+        "mat4" => crate::AppContext::FLOAT_MAT4,
+        "sampler2D" => crate::AppContext::SAMPLER_2D,
+        "sampler3D" => crate::AppContext::SAMPLER_3D,
+        "samplerCube" => crate::AppContext::SAMPLER_CUBE,
+        "sampler2DArray" => crate::AppContext::SAMPLER_2D_ARRAY,
+        _ => 0,
+    }
+}
+
+/// This function builds info log (in the format used by browsers) for "#error" directives in GLSL source.
+fn error_directives_log(source: &str) -> String {
+    let mut log = String::new();
+    for (i, line) in source.lines().enumerate() {
+        let directive = line.trim().strip_prefix('#').map(|rest| rest.trim_start());
+        if let Some(message) = directive.and_then(|d| d.strip_prefix("error")) {
+            log.push_str(&format!("ERROR: 0:{}: '#error' : {}\n", i + 1, message.trim()));
+        }
+    }
+    log
+}
+
+/// This function returns names of uniform blocks declared in GLSL source ("uniform Name {" or "uniform Name" + "{" line).
+fn declared_uniform_blocks(source: &str) -> Vec<String> {
+    let mut blocks = vec![];
+    for line in source.lines() {
+        let line = line.trim();
+        let declaration = match line.find("uniform ") {
+            Some(pos) if pos == 0 || line[..pos].trim_end().ends_with(')') => &line[pos + "uniform".len()..],
+            _ => continue,
+        };
+        let tokens: Vec<&str> = declaration.split(|c: char| c.is_whitespace() || c == '{').filter(|t| !t.is_empty()).collect();
+        if tokens.len() == 1 {
+            blocks.push(String::from(tokens[0]));
+        }
+    }
+    blocks
+}
+
+/// This function returns value of "layout (location = N)" qualifier.
+fn layout_location(layout: &str) -> Option<i32> {
+    let compact: String = layout.chars().filter(|c| !c.is_whitespace()).collect();
+    let start = compact.find("location=")? + "location=".len();
+    let digits: String = compact[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// This function returns variables declared in GLSL source with given qualifier (e.g. "uniform") together
+/// with their layout location. Array variables are reported like in WebGL: "name[0]" with size equal to array length.
+fn declared_variables(source: &str, qualifier: &str) -> Vec<(ActiveInfo, Option<i32>)> {
+    let mut variables = vec![];
+    for line in source.lines() {
+        let line = line.trim();
+        let (layout, declaration) = match line.find(&format!("{} ", qualifier)) {
+            Some(pos) if pos == 0 || line[..pos].trim_end().ends_with(')') => (&line[..pos], &line[pos + qualifier.len()..]),
+            _ => continue,
+        };
+        let tokens: Vec<&str> = declaration.trim_end_matches(';').split_whitespace().collect();
+        if tokens.len() < 2 || declaration.contains('{') {
+            continue;
+        }
+
+        let type_name = tokens[tokens.len() - 2];
+        let name = tokens[tokens.len() - 1];
+        let (name, size) = match name.find('[') {
+            Some(pos) => (
+                format!("{}[0]", &name[..pos]),
+                name[pos + 1..].trim_end_matches(']').parse().unwrap_or(1),
+            ),
+            None => (String::from(name), 1),
+        };
+
+        variables.push((ActiveInfo { name, size, data_type: glsl_type_to_gl(type_name) }, layout_location(layout)));
+    }
+    variables
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This type can be used as GlBackend in tests. Calls are stored in order and can be inspected later.
+/// Clones share the recorded state (like clones of WebGl2RenderingContext refer to the same context).
+#[derive(Default, Clone)]
+pub struct RecordingBackend {
+    state: Rc<RefCell<RecordingState>>,
+}
+
+impl RecordingBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This method returns copy of all recorded calls.
+    pub fn calls(&self) -> Vec<GlCall> {
+        self.state.borrow().calls.clone()
+    }
+
+    /// This method returns recorded draw_arrays and draw_elements calls.
+    pub fn draw_calls(&self) -> Vec<GlCall> {
+        self.state.borrow().calls.iter().filter(|c| c.is_draw_call()).cloned().collect()
+    }
+
+    pub fn clear_calls(&self) {
+        self.state.borrow_mut().calls.clear();
+    }
+
+    /// This method returns invalid usages detected by backend (e.g. writing data out of buffer bounds).
+    pub fn errors(&self) -> Vec<String> {
+        self.state.borrow().errors.clone()
+    }
+
+    /// This method returns current content of the buffer.
+    pub fn buffer_data(&self, buffer: RecordedId) -> Option<Vec<u8>> {
+        self.state.borrow().buffers.get(&buffer).cloned()
+    }
+
+    /// This method returns current content of the buffer interpreted as floats.
+    pub fn buffer_data_f32(&self, buffer: RecordedId) -> Option<Vec<f32>> {
+        let data = self.buffer_data(buffer)?;
+        Some(
+            data.chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        )
+    }
+
+    /// This method returns last value uploaded to the given program uniform.
+    pub fn uniform(&self, program: RecordedId, name: &str) -> Option<UniformData> {
+        self.state.borrow().uniforms.get(&(program, String::from(name))).cloned()
+    }
+
+    pub fn current_program(&self) -> Option<RecordedId> {
+        self.state.borrow().current_program
+    }
+
+    /// This method returns binding point assigned to the program uniform block.
+    pub fn uniform_block_binding_point(&self, program: RecordedId, name: &str) -> Option<u32> {
+        let state = self.state.borrow();
+        state.programs.get(&program)?.uniform_blocks.iter().find(|b| b.0 == name).map(|b| b.1)
+    }
+
+    /// This method returns number of created objects (buffers, textures, programs, ...), which were not deleted yet.
+    pub fn live_object_count(&self) -> usize {
+        self.state.borrow().live_objects.len()
+    }
+
+    pub fn is_live_object(&self, id: RecordedId) -> bool {
+        self.state.borrow().live_objects.contains(&id)
+    }
+
+    /// This method overrides value returned by get_parameter_i32 (e.g. to simulate device with lower MAX_SAMPLES).
+    pub fn set_parameter(&self, pname: u32, value: i32) {
+        self.state.borrow_mut().parameters.insert(pname, value);
+    }
+
+    /// This method changes result of enable_extension (e.g. to simulate device without EXT_color_buffer_float).
+    pub fn set_extension_supported(&self, name: &str, supported: bool) {
+        let mut state = self.state.borrow_mut();
+        if supported {
+            state.unsupported_extensions.remove(name);
+        } else {
+            state.unsupported_extensions.insert(name.to_owned());
+        }
+    }
+
+    /// This method overrides status of bound framebuffers (e.g. to simulate FRAMEBUFFER_UNSUPPORTED), None restores
+    /// the check of attachments.
+    pub fn set_framebuffer_status(&self, status: Option<u32>) {
+        self.state.borrow_mut().framebuffer_status = status;
+    }
+
+    /// This method returns sampler bound to texture unit (unit is an index, not TEXTURE0 + index).
+    pub fn bound_sampler(&self, unit: u32) -> Option<RecordedId> {
+        self.state.borrow().bound_samplers.get(&unit).copied()
+    }
+
+    /// This method returns framebuffer bound to DRAW_FRAMEBUFFER or READ_FRAMEBUFFER target (None means canvas).
+    pub fn bound_framebuffer(&self, target: u32) -> Option<RecordedId> {
+        self.state.borrow().bound_framebuffers.get(&target).copied()
+    }
+
+    /// This method returns buffer bound to indexed binding point (see bind_buffer_base).
+    pub fn indexed_buffer(&self, target: u32, index: u32) -> Option<RecordedId> {
+        self.state.borrow().indexed_buffers.get(&(target, index)).copied()
+    }
+
+    // -------------------------------------------------------------------------------------------------------
+
+    fn record(&self, call: GlCall) {
+        self.state.borrow_mut().calls.push(call);
+    }
+
+    fn create_object(&self, make_call: fn(RecordedId) -> GlCall) -> RecordedId {
+        let mut state = self.state.borrow_mut();
+        let id = state.next_id();
+        state.live_objects.insert(id);
+        state.calls.push(make_call(id));
+        id
+    }
+
+    /// Deleting null object is ignored (like in WebGL), deleting the same object twice is reported as error.
+    fn delete_object(&self, id: Option<&RecordedId>, make_call: fn(Option<RecordedId>) -> GlCall) {
+        let mut state = self.state.borrow_mut();
+        if let Some(id) = id {
+            if !state.live_objects.remove(id) {
+                state.errors.push(format!("Deleted object {} doesn't exist", id));
+            }
+        }
+        state.calls.push(make_call(id.copied()));
+    }
+
+    /// FRAMEBUFFER target means DRAW_FRAMEBUFFER for attachments and status checks.
+    fn framebuffer_binding(target: u32) -> u32 {
+        match target {
+            crate::AppContext::FRAMEBUFFER => crate::AppContext::DRAW_FRAMEBUFFER,
+            _ => target,
+        }
+    }
+
+    fn attach_to_framebuffer(&self, target: u32, attachment: u32, object: Option<&RecordedId>) {
+        let mut state = self.state.borrow_mut();
+        let framebuffer = match state.bound_framebuffers.get(&Self::framebuffer_binding(target)) {
+            Some(framebuffer) => *framebuffer,
+            None => {
+                state.errors.push(format!("Framebuffer attachment {}: no framebuffer bound", attachment));
+                return;
+            }
+        };
+        let attachments = state.framebuffer_attachments.entry(framebuffer).or_default();
+        match object {
+            Some(object) => attachments.insert(attachment, *object),
+            None => attachments.remove(&attachment),
+        };
+    }
+
+    fn set_uniform(&self, location: Option<&RecordedUniformLocation>, data: UniformData) {
+        let mut state = self.state.borrow_mut();
+        if let Some(location) = location {
+            state.uniforms.insert((location.program, location.name.clone()), data.clone());
+        }
+        state.calls.push(GlCall::Uniform { location: location.cloned(), data });
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+impl GlBackend for RecordingBackend {
+    type Buffer = RecordedId;
+    type VertexArray = RecordedId;
+    type Texture = RecordedId;
+    type Shader = RecordedId;
+    type Program = RecordedId;
+    type Framebuffer = RecordedId;
+    type Renderbuffer = RecordedId;
+    type Sampler = RecordedId;
+    type UniformLocation = RecordedUniformLocation;
+
+    // basics
+
+    fn clear(&self, mask: u32) {
+        self.record(GlCall::Clear { mask });
+    }
+
+    /// Parameters which are not set by set_parameter have values of a typical WebGL2 device (or 0 if unknown).
+    fn get_parameter_i32(&self, pname: u32) -> i32 {
+        if let Some(value) = self.state.borrow().parameters.get(&pname) {
+            return *value;
+        }
+        match pname {
+            crate::AppContext::MAX_SAMPLES => 4,
+            crate::AppContext::MAX_TEXTURE_SIZE => 4096,
+            crate::AppContext::MAX_3D_TEXTURE_SIZE => 256,
+            crate::AppContext::MAX_ARRAY_TEXTURE_LAYERS => 256,
+            crate::AppContext::MAX_COMBINED_TEXTURE_IMAGE_UNITS => 32,
+            interface::MAX_TEXTURE_MAX_ANISOTROPY_EXT => 16,
+            _ => 0,
+        }
+    }
+
+    /// Every extension is supported unless it is disabled by set_extension_supported.
+    fn enable_extension(&self, name: &str) -> bool {
+        !self.state.borrow().unsupported_extensions.contains(name)
+    }
+
+    fn enable(&self, cap: u32) {
+        self.record(GlCall::Enable { cap });
+    }
+
+    fn disable(&self, cap: u32) {
+        self.record(GlCall::Disable { cap });
+    }
+
+    fn blend_func(&self, sfactor: u32, dfactor: u32) {
+        self.record(GlCall::BlendFunc { sfactor, dfactor });
+    }
+
+    fn blend_equation(&self, mode: u32) {
+        self.record(GlCall::BlendEquation { mode });
+    }
+
+    fn depth_func(&self, func: u32) {
+        self.record(GlCall::DepthFunc { func });
+    }
+
+    fn depth_mask(&self, flag: bool) {
+        self.record(GlCall::DepthMask { flag });
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.record(GlCall::Viewport { x, y, width, height });
+    }
+
+    fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32) {
+        self.record(GlCall::BlendFuncSeparate { src_rgb, dst_rgb, src_alpha, dst_alpha });
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        self.record(GlCall::BlendEquationSeparate { mode_rgb, mode_alpha });
+    }
+
+    fn stencil_func(&self, func: u32, reference: i32, mask: u32) {
+        self.record(GlCall::StencilFunc { func, reference, mask });
+    }
+
+    fn stencil_op(&self, fail: u32, depth_fail: u32, pass: u32) {
+        self.record(GlCall::StencilOp { fail, depth_fail, pass });
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        self.record(GlCall::StencilMask { mask });
+    }
+
+    fn cull_face(&self, mode: u32) {
+        self.record(GlCall::CullFace { mode });
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.record(GlCall::Scissor { x, y, width, height });
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        self.record(GlCall::ColorMask { red, green, blue, alpha });
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.record(GlCall::ClearColor { red, green, blue, alpha });
+    }
+
+    fn clear_depth(&self, depth: f32) {
+        self.record(GlCall::ClearDepth { depth });
+    }
+
+    fn clear_stencil(&self, stencil: i32) {
+        self.record(GlCall::ClearStencil { stencil });
+    }
+
+    // shaders & programs
+
+    fn create_shader(&self, shader_type: u32) -> Option<RecordedId> {
+        let mut state = self.state.borrow_mut();
+        let shader = state.next_id();
+        state.live_objects.insert(shader);
+        state.shader_types.insert(shader, shader_type);
+        state.calls.push(GlCall::CreateShader { shader_type, shader });
+        Some(shader)
+    }
+
+    fn shader_source(&self, shader: &RecordedId, source: &str) {
+        let mut state = self.state.borrow_mut();
+        state.shader_sources.insert(*shader, String::from(source));
+        state.calls.push(GlCall::ShaderSource { shader: *shader, source: String::from(source) });
+    }
+
+    fn compile_shader(&self, shader: &RecordedId) {
+        self.record(GlCall::CompileShader { shader: *shader });
+    }
+
+    fn get_shader_info_log(&self, shader: &RecordedId) -> Option<String> {
+        let state = self.state.borrow();
+        let source = state.shader_sources.get(shader).map(|s| s.as_str()).unwrap_or("");
+        Some(error_directives_log(source))
+    }
+
+    fn create_program(&self) -> Option<RecordedId> {
+        let program = self.create_object(|program| GlCall::CreateProgram { program });
+        self.state.borrow_mut().programs.insert(program, RecordedProgram::default());
+        Some(program)
+    }
+
+    fn delete_program(&self, program: Option<&RecordedId>) {
+        self.delete_object(program, |program| GlCall::DeleteProgram { program });
+    }
+
+    fn delete_shader(&self, shader: Option<&RecordedId>) {
+        self.delete_object(shader, |shader| GlCall::DeleteShader { shader });
+    }
+
+    fn attach_shader(&self, program: &RecordedId, shader: &RecordedId) {
+        let mut state = self.state.borrow_mut();
+        if let Some(recorded) = state.programs.get_mut(program) {
+            recorded.shaders.push(*shader);
+        }
+        state.calls.push(GlCall::AttachShader { program: *program, shader: *shader });
+    }
+
+    fn detach_shader(&self, program: &RecordedId, shader: &RecordedId) {
+        self.state
+            .borrow_mut()
+            .calls
+            .push(GlCall::DetachShader { program: *program, shader: *shader });
+    }
+
+    fn link_program(&self, program: &RecordedId) {
+        let mut state = self.state.borrow_mut();
+        let shaders = state.programs.get(program).map(|p| p.shaders.clone()).unwrap_or_default();
+        let mut uniforms: Vec<ActiveInfo> = vec![];
+        let mut attributes: Vec<(ActiveInfo, i32)> = vec![];
+        let mut uniform_blocks = vec![];
+        for shader in shaders {
+            let source = match state.shader_sources.get(&shader) {
+                Some(source) => source,
+                None => continue,
+            };
+            for (uniform, _) in declared_variables(source, "uniform") {
+                if !uniforms.iter().any(|u| u.name == uniform.name) {
+                    uniforms.push(uniform);
+                }
+            }
+            for block in declared_uniform_blocks(source) {
+                if !uniform_blocks.iter().any(|b: &(String, u32)| b.0 == block) {
+                    uniform_blocks.push((block, 0));
+                }
+            }
+            if state.shader_types.get(&shader) == Some(&crate::AppContext::VERTEX_SHADER) {
+                for (attribute, location) in declared_variables(source, "in") {
+                    let location = location.unwrap_or_else(|| attributes.iter().map(|a| a.1 + 1).max().unwrap_or(0));
+                    attributes.push((attribute, location));
+                }
+            }
+        }
+        if let Some(recorded) = state.programs.get_mut(program) {
+            recorded.uniforms = uniforms;
+            recorded.attributes = attributes;
+            recorded.uniform_blocks = uniform_blocks;
+        }
+        state.calls.push(GlCall::LinkProgram { program: *program });
+    }
+
+    fn get_program_info_log(&self, _program: &RecordedId) -> Option<String> {
+        Some(String::new())
+    }
+
+    fn use_program(&self, program: Option<&RecordedId>) {
+        let mut state = self.state.borrow_mut();
+        state.current_program = program.copied();
+        state.calls.push(GlCall::UseProgram { program: program.copied() });
+    }
+
+    fn get_program_parameter(&self, program: &RecordedId, pname: u32) -> i32 {
+        let state = self.state.borrow();
+        let recorded = match state.programs.get(program) {
+            Some(recorded) => recorded,
+            None => return 0,
+        };
+        match pname {
+            crate::AppContext::ACTIVE_UNIFORMS => recorded.uniforms.len() as i32,
+            crate::AppContext::ACTIVE_ATTRIBUTES => recorded.attributes.len() as i32,
+            crate::AppContext::LINK_STATUS => 1,
+            _ => 0,
+        }
+    }
+
+    // uniforms
+
+    fn get_active_uniform(&self, program: &RecordedId, index: u32) -> Option<ActiveInfo> {
+        let state = self.state.borrow();
+        state.programs.get(program)?.uniforms.get(index as usize).cloned()
+    }
+
+    fn get_active_attrib(&self, program: &RecordedId, index: u32) -> Option<ActiveInfo> {
+        let state = self.state.borrow();
+        state.programs.get(program)?.attributes.get(index as usize).map(|a| a.0.clone())
+    }
+
+    fn get_attrib_location(&self, program: &RecordedId, name: &str) -> i32 {
+        let state = self.state.borrow();
+        state
+            .programs
+            .get(program)
+            .and_then(|p| p.attributes.iter().find(|a| a.0.name == name))
+            .map(|a| a.1)
+            .unwrap_or(-1)
+    }
+
+    fn get_uniform_location(&self, program: &RecordedId, name: &str) -> Option<RecordedUniformLocation> {
+        let mut state = self.state.borrow_mut();
+        state.calls.push(GlCall::GetUniformLocation { program: *program, name: String::from(name) });
+        let (base_name, index) = match name.find('[') {
+            Some(pos) => (&name[..pos], name[pos + 1..].trim_end_matches(']').parse().unwrap_or(i32::MAX)),
+            None => (name, 0),
+        };
+        let declared = state
+            .programs
+            .get(program)
+            .map(|p| {
+                p.uniforms.iter().any(|u| {
+                    let is_array = u.name.ends_with("[0]");
+                    let matches_name = u.name.trim_end_matches("[0]") == base_name;
+                    matches_name && (index == 0 || (is_array && index < u.size))
+                })
+            })
+            .unwrap_or(false);
+
+        if declared {
+            return Some(RecordedUniformLocation { program: *program, name: String::from(name) });
+        }
+        None
+    }
+
+    fn uniform1f(&self, location: Option<&RecordedUniformLocation>, v1: f32) {
+        self.set_uniform(location, UniformData::Float(vec![v1]));
+    }
+
+    fn uniform2f(&self, location: Option<&RecordedUniformLocation>, v1: f32, v2: f32) {
+        self.set_uniform(location, UniformData::Float(vec![v1, v2]));
+    }
+
+    fn uniform3f(&self, location: Option<&RecordedUniformLocation>, v1: f32, v2: f32, v3: f32) {
+        self.set_uniform(location, UniformData::Float(vec![v1, v2, v3]));
+    }
+
+    fn uniform4f(&self, location: Option<&RecordedUniformLocation>, v1: f32, v2: f32, v3: f32, v4: f32) {
+        self.set_uniform(location, UniformData::Float(vec![v1, v2, v3, v4]));
+    }
+
+    fn uniform1i(&self, location: Option<&RecordedUniformLocation>, v1: i32) {
+        self.set_uniform(location, UniformData::Int(vec![v1]));
+    }
+
+    fn uniform2i(&self, location: Option<&RecordedUniformLocation>, v1: i32, v2: i32) {
+        self.set_uniform(location, UniformData::Int(vec![v1, v2]));
+    }
+
+    fn uniform3i(&self, location: Option<&RecordedUniformLocation>, v1: i32, v2: i32, v3: i32) {
+        self.set_uniform(location, UniformData::Int(vec![v1, v2, v3]));
+    }
+
+    fn uniform4i(&self, location: Option<&RecordedUniformLocation>, v1: i32, v2: i32, v3: i32, v4: i32) {
+        self.set_uniform(location, UniformData::Int(vec![v1, v2, v3, v4]));
+    }
+
+    fn uniform1ui(&self, location: Option<&RecordedUniformLocation>, v1: u32) {
+        self.set_uniform(location, UniformData::UInt(vec![v1]));
+    }
+
+    fn uniform2ui(&self, location: Option<&RecordedUniformLocation>, v1: u32, v2: u32) {
+        self.set_uniform(location, UniformData::UInt(vec![v1, v2]));
+    }
+
+    fn uniform3ui(&self, location: Option<&RecordedUniformLocation>, v1: u32, v2: u32, v3: u32) {
+        self.set_uniform(location, UniformData::UInt(vec![v1, v2, v3]));
+    }
+
+    fn uniform4ui(&self, location: Option<&RecordedUniformLocation>, v1: u32, v2: u32, v3: u32, v4: u32) {
+        self.set_uniform(location, UniformData::UInt(vec![v1, v2, v3, v4]));
+    }
+
+    fn uniform1fv(&self, location: Option<&RecordedUniformLocation>, data: &[f32]) {
+        self.set_uniform(location, UniformData::Float(data.to_vec()));
+    }
+
+    fn uniform2fv(&self, location: Option<&RecordedUniformLocation>, data: &[f32]) {
+        self.set_uniform(location, UniformData::Float(data.to_vec()));
+    }
+
+    fn uniform3fv(&self, location: Option<&RecordedUniformLocation>, data: &[f32]) {
+        self.set_uniform(location, UniformData::Float(data.to_vec()));
+    }
+
+    fn uniform4fv(&self, location: Option<&RecordedUniformLocation>, data: &[f32]) {
+        self.set_uniform(location, UniformData::Float(data.to_vec()));
+    }
+
+    fn uniform1iv(&self, location: Option<&RecordedUniformLocation>, data: &[i32]) {
+        self.set_uniform(location, UniformData::Int(data.to_vec()));
+    }
+
+    fn uniform1uiv(&self, location: Option<&RecordedUniformLocation>, data: &[u32]) {
+        self.set_uniform(location, UniformData::UInt(data.to_vec()));
+    }
+
+    fn uniform_matrix2fv(&self, location: Option<&RecordedUniformLocation>, _transpose: bool, data: &[f32]) {
+        self.set_uniform(location, UniformData::Matrix2(data.to_vec()));
+    }
+
+    fn uniform_matrix3fv(&self, location: Option<&RecordedUniformLocation>, _transpose: bool, data: &[f32]) {
+        self.set_uniform(location, UniformData::Matrix3(data.to_vec()));
+    }
+
+    fn uniform_matrix4fv(&self, location: Option<&RecordedUniformLocation>, _transpose: bool, data: &[f32]) {
+        self.set_uniform(location, UniformData::Matrix4(data.to_vec()));
+    }
+
+    // uniform blocks
+
+    fn get_uniform_block_index(&self, program: &RecordedId, name: &str) -> u32 {
+        let state = self.state.borrow();
+        state
+            .programs
+            .get(program)
+            .and_then(|p| p.uniform_blocks.iter().position(|b| b.0 == name))
+            .map(|index| index as u32)
+            .unwrap_or(crate::AppContext::INVALID_INDEX)
+    }
+
+    fn uniform_block_binding(&self, program: &RecordedId, block_index: u32, binding: u32) {
+        let mut state = self.state.borrow_mut();
+        let block = state.programs.get_mut(program).and_then(|p| p.uniform_blocks.get_mut(block_index as usize));
+        match block {
+            Some(block) => block.1 = binding,
+            None => state.errors.push(format!("uniform_block_binding: invalid block index {}", block_index)),
+        }
+        state.calls.push(GlCall::UniformBlockBinding { program: *program, block_index, binding });
+    }
+
+    // buffers
+
+    fn create_buffer(&self) -> Option<RecordedId> {
+        let buffer = self.create_object(|buffer| GlCall::CreateBuffer { buffer });
+        self.state.borrow_mut().buffers.insert(buffer, vec![]);
+        Some(buffer)
+    }
+
+    fn delete_buffer(&self, buffer: Option<&RecordedId>) {
+        self.delete_object(buffer, |buffer| GlCall::DeleteBuffer { buffer });
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<&RecordedId>) {
+        let mut state = self.state.borrow_mut();
+        match buffer {
+            Some(buffer) => state.bound_buffers.insert(target, *buffer),
+            None => state.bound_buffers.remove(&target),
+        };
+        state.calls.push(GlCall::BindBuffer { target, buffer: buffer.copied() });
+    }
+
+    /// Like in GL, this method also binds the buffer to the generic binding point of the target.
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<&RecordedId>) {
+        let mut state = self.state.borrow_mut();
+        match buffer {
+            Some(buffer) => {
+                state.indexed_buffers.insert((target, index), *buffer);
+                state.bound_buffers.insert(target, *buffer)
+            }
+            None => state.indexed_buffers.remove(&(target, index)),
+        };
+        state.calls.push(GlCall::BindBufferBase { target, index, buffer: buffer.copied() });
+    }
+
+    fn buffer_data_with_size(&self, target: u32, size: i32, usage: u32) {
+        let mut state = self.state.borrow_mut();
+        match state.bound_buffer_mut(target) {
+            Some(data) => *data = vec![0u8; size as usize],
+            None => state.errors.push(format!("BufferData: no buffer bound to target {}", target)),
+        }
+        state.calls.push(GlCall::BufferData { target, size: size as usize, usage });
+    }
+
+    fn buffer_data_with_u8_array(&self, target: u32, data: &[u8], usage: u32) {
+        let mut state = self.state.borrow_mut();
+        match state.bound_buffer_mut(target) {
+            Some(content) => *content = data.to_vec(),
+            None => state.errors.push(format!("BufferData: no buffer bound to target {}", target)),
+        }
+        state.calls.push(GlCall::BufferData { target, size: data.len(), usage });
+    }
+
+    fn buffer_sub_data_with_u8_array(&self, target: u32, offset: i32, data: &[u8]) {
+        let mut state = self.state.borrow_mut();
+        let offset = offset as usize;
+        let error = match state.bound_buffer_mut(target) {
+            Some(content) if offset + data.len() <= content.len() => {
+                content[offset..offset + data.len()].copy_from_slice(data);
+                None
+            }
+            Some(_) => Some(format!("BufferSubData: write out of bounds of buffer bound to target {}", target)),
+            None => Some(format!("BufferSubData: no buffer bound to target {}", target)),
+        };
+        if let Some(error) = error {
+            state.errors.push(error);
+        }
+        state.calls.push(GlCall::BufferSubData { target, offset, size: data.len() });
+    }
+
+    // vertex arrays
+
+    fn create_vertex_array(&self) -> Option<RecordedId> {
+        Some(self.create_object(|vertex_array| GlCall::CreateVertexArray { vertex_array }))
+    }
+
+    fn delete_vertex_array(&self, vertex_array: Option<&RecordedId>) {
+        self.delete_object(vertex_array, |vertex_array| GlCall::DeleteVertexArray { vertex_array });
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<&RecordedId>) {
+        self.record(GlCall::BindVertexArray { vertex_array: vertex_array.copied() });
+    }
+
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32) {
+        self.record(GlCall::VertexAttribPointer { index, size, data_type, normalized, stride, offset });
+    }
+
+    fn vertex_attrib_i_pointer(&self, index: u32, size: i32, data_type: u32, stride: i32, offset: i32) {
+        self.record(GlCall::VertexAttribIPointer { index, size, data_type, stride, offset });
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.record(GlCall::EnableVertexAttribArray { index });
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.record(GlCall::VertexAttribDivisor { index, divisor });
+    }
+
+    // draw calls
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        self.record(GlCall::DrawArrays { mode, first, count });
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, data_type: u32, offset: i32) {
+        self.record(GlCall::DrawElements { mode, count, data_type, offset });
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instance_count: i32) {
+        self.record(GlCall::DrawArraysInstanced { mode, first, count, instance_count });
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, data_type: u32, offset: i32, instance_count: i32) {
+        self.record(GlCall::DrawElementsInstanced { mode, count, data_type, offset, instance_count });
+    }
+
+    // textures
+
+    fn create_texture(&self) -> Option<RecordedId> {
+        Some(self.create_object(|texture| GlCall::CreateTexture { texture }))
+    }
+
+    fn delete_texture(&self, texture: Option<&RecordedId>) {
+        self.delete_object(texture, |texture| GlCall::DeleteTexture { texture });
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<&RecordedId>) {
+        self.record(GlCall::BindTexture { target, texture: texture.copied() });
+    }
+
+    fn active_texture(&self, texture: u32) {
+        self.record(GlCall::ActiveTexture { texture });
+    }
+
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32) {
+        self.record(GlCall::TexParameteri { target, pname, param });
+    }
+
+    fn tex_parameterf(&self, target: u32, pname: u32, param: f32) {
+        self.record(GlCall::TexParameterf { target, pname, param });
+    }
+
+    fn generate_mipmap(&self, target: u32) {
+        self.record(GlCall::GenerateMipmap { target });
+    }
+
+    fn pixel_storei(&self, pname: u32, param: i32) {
+        self.record(GlCall::PixelStorei { pname, param });
+    }
+
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        format: u32,
+        data_type: u32,
+        _img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        self.record(GlCall::TexImage2D { target, level, internal_format, format, data_type });
+        Ok(())
+    }
+
+    fn tex_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> SWGLResult<()> {
+        self.record(GlCall::TexImage2DWithData {
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            format,
+            data_type,
+            data_size: pixels.map(|p| p.len()),
+        });
+        Ok(())
+    }
+
+    fn tex_image_3d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        _border: i32,
+        format: u32,
+        data_type: u32,
+        _img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        self.record(GlCall::TexImage3D { target, level, internal_format, width, height, depth, format, data_type });
+        Ok(())
+    }
+
+    fn tex_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> SWGLResult<()> {
+        self.record(GlCall::TexImage3DWithData {
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            depth,
+            format,
+            data_type,
+            data_size: pixels.map(|p| p.len()),
+        });
+        Ok(())
+    }
+
+    fn tex_sub_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: &[u8],
+    ) -> SWGLResult<()> {
+        self.record(GlCall::TexSubImage2D {
+            target,
+            level,
+            x_offset,
+            y_offset,
+            width,
+            height,
+            format,
+            data_type,
+            data_size: pixels.len(),
+        });
+        Ok(())
+    }
+
+    fn tex_sub_image_3d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        _img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        self.record(GlCall::TexSubImage3DWithImage {
+            target,
+            level,
+            x_offset,
+            y_offset,
+            z_offset,
+            width,
+            height,
+            depth,
+            format,
+            data_type,
+        });
+        Ok(())
+    }
+
+    fn tex_sub_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pixels: &[u8],
+    ) -> SWGLResult<()> {
+        self.record(GlCall::TexSubImage3D {
+            target,
+            level,
+            x_offset,
+            y_offset,
+            z_offset,
+            width,
+            height,
+            depth,
+            format,
+            data_type,
+            data_size: pixels.len(),
+        });
+        Ok(())
+    }
+
+    fn compressed_tex_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        data: &[u8],
+    ) {
+        self.record(GlCall::CompressedTexImage2D {
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            data_size: data.len(),
+        });
+    }
+
+    fn compressed_tex_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        data: &[u8],
+    ) {
+        self.record(GlCall::CompressedTexImage3D {
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            depth,
+            data_size: data.len(),
+        });
+    }
+
+    // samplers
+
+    fn create_sampler(&self) -> Option<RecordedId> {
+        Some(self.create_object(|sampler| GlCall::CreateSampler { sampler }))
+    }
+
+    fn delete_sampler(&self, sampler: Option<&RecordedId>) {
+        self.delete_object(sampler, |sampler| GlCall::DeleteSampler { sampler });
+        if let Some(sampler) = sampler {
+            self.state.borrow_mut().bound_samplers.retain(|_, bound| bound != sampler);
+        }
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<&RecordedId>) {
+        let mut state = self.state.borrow_mut();
+        match sampler {
+            Some(sampler) => state.bound_samplers.insert(unit, *sampler),
+            None => state.bound_samplers.remove(&unit),
+        };
+        state.calls.push(GlCall::BindSampler { unit, sampler: sampler.copied() });
+    }
+
+    fn sampler_parameteri(&self, sampler: &RecordedId, pname: u32, param: i32) {
+        self.record(GlCall::SamplerParameteri { sampler: *sampler, pname, param });
+    }
+
+    fn sampler_parameterf(&self, sampler: &RecordedId, pname: u32, param: f32) {
+        self.record(GlCall::SamplerParameterf { sampler: *sampler, pname, param });
+    }
+
+    // framebuffers
+
+    fn create_framebuffer(&self) -> Option<RecordedId> {
+        let framebuffer = self.create_object(|framebuffer| GlCall::CreateFramebuffer { framebuffer });
+        self.state.borrow_mut().framebuffer_attachments.insert(framebuffer, HashMap::new());
+        Some(framebuffer)
+    }
+
+    fn delete_framebuffer(&self, framebuffer: Option<&RecordedId>) {
+        self.delete_object(framebuffer, |framebuffer| GlCall::DeleteFramebuffer { framebuffer });
+        if let Some(framebuffer) = framebuffer {
+            let mut state = self.state.borrow_mut();
+            state.framebuffer_attachments.remove(framebuffer);
+            state.bound_framebuffers.retain(|_, bound| bound != framebuffer);
+        }
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&RecordedId>) {
+        let mut state = self.state.borrow_mut();
+        let targets = match target {
+            crate::AppContext::FRAMEBUFFER => vec![crate::AppContext::DRAW_FRAMEBUFFER, crate::AppContext::READ_FRAMEBUFFER],
+            _ => vec![target],
+        };
+        for target in targets {
+            match framebuffer {
+                Some(framebuffer) => state.bound_framebuffers.insert(target, *framebuffer),
+                None => state.bound_framebuffers.remove(&target),
+            };
+        }
+        state.calls.push(GlCall::BindFramebuffer { target, framebuffer: framebuffer.copied() });
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, tex_target: u32, texture: Option<&RecordedId>, level: i32) {
+        self.attach_to_framebuffer(target, attachment, texture);
+        self.record(GlCall::FramebufferTexture2D { target, attachment, tex_target, texture: texture.copied(), level });
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, _renderbuffer_target: u32, renderbuffer: Option<&RecordedId>) {
+        self.attach_to_framebuffer(target, attachment, renderbuffer);
+        self.record(GlCall::FramebufferRenderbuffer { target, attachment, renderbuffer: renderbuffer.copied() });
+    }
+
+    /// Bound framebuffer is complete if it has at least one attachment (sizes and formats are not checked), unless
+    /// the status is overridden by set_framebuffer_status.
+    fn check_framebuffer_status(&self, target: u32) -> u32 {
+        let state = self.state.borrow();
+        let framebuffer = match state.bound_framebuffers.get(&Self::framebuffer_binding(target)) {
+            Some(framebuffer) => framebuffer,
+            None => return crate::AppContext::FRAMEBUFFER_COMPLETE,
+        };
+        if let Some(status) = state.framebuffer_status {
+            return status;
+        }
+        match state.framebuffer_attachments.get(framebuffer) {
+            Some(attachments) if !attachments.is_empty() => crate::AppContext::FRAMEBUFFER_COMPLETE,
+            _ => crate::AppContext::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT,
+        }
+    }
+
+    fn create_renderbuffer(&self) -> Option<RecordedId> {
+        Some(self.create_object(|renderbuffer| GlCall::CreateRenderbuffer { renderbuffer }))
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: Option<&RecordedId>) {
+        self.delete_object(renderbuffer, |renderbuffer| GlCall::DeleteRenderbuffer { renderbuffer });
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<&RecordedId>) {
+        self.record(GlCall::BindRenderbuffer { target, renderbuffer: renderbuffer.copied() });
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32) {
+        self.record(GlCall::RenderbufferStorage { target, internal_format, width, height });
+    }
+
+    fn renderbuffer_storage_multisample(&self, target: u32, samples: i32, internal_format: u32, width: i32, height: i32) {
+        self.record(GlCall::RenderbufferStorageMultisample { target, samples, internal_format, width, height });
+    }
+
+    fn blit_framebuffer(
+        &self,
+        src_x0: i32,
+        src_y0: i32,
+        src_x1: i32,
+        src_y1: i32,
+        dst_x0: i32,
+        dst_y0: i32,
+        dst_x1: i32,
+        dst_y1: i32,
+        mask: u32,
+        filter: u32,
+    ) {
+        let read_framebuffer = self.bound_framebuffer(crate::AppContext::READ_FRAMEBUFFER);
+        let draw_framebuffer = self.bound_framebuffer(crate::AppContext::DRAW_FRAMEBUFFER);
+        self.record(GlCall::BlitFramebuffer {
+            read_framebuffer,
+            draw_framebuffer,
+            src: [src_x0, src_y0, src_x1, src_y1],
+            dst: [dst_x0, dst_y0, dst_x1, dst_y1],
+            mask,
+            filter,
+        });
+    }
+}
@@ -0,0 +1,727 @@
+//! GlBackend implementation for the browser WebGL2 context.
+
+use web_sys::{
+    HtmlImageElement, WebGl2RenderingContext, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlRenderbuffer,
+    WebGlSampler, WebGlShader, WebGlTexture, WebGlUniformLocation, WebGlVertexArrayObject,
+};
+use super::interface::{ActiveInfo, GlBackend};
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+
+// -----------------------------------------------------------------------------------------------------------
+
+impl GlBackend for WebGl2RenderingContext {
+    type Buffer = WebGlBuffer;
+    type VertexArray = WebGlVertexArrayObject;
+    type Texture = WebGlTexture;
+    type Shader = WebGlShader;
+    type Program = WebGlProgram;
+    type Framebuffer = WebGlFramebuffer;
+    type Renderbuffer = WebGlRenderbuffer;
+    type Sampler = WebGlSampler;
+    type UniformLocation = WebGlUniformLocation;
+
+    // basics
+
+    fn clear(&self, mask: u32) {
+        WebGl2RenderingContext::clear(self, mask);
+    }
+
+    fn get_parameter_i32(&self, pname: u32) -> i32 {
+        WebGl2RenderingContext::get_parameter(self, pname)
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map(|value| value as i32)
+            .unwrap_or(0)
+    }
+
+    fn enable_extension(&self, name: &str) -> bool {
+        matches!(WebGl2RenderingContext::get_extension(self, name), Ok(Some(_)))
+    }
+
+    fn enable(&self, cap: u32) {
+        WebGl2RenderingContext::enable(self, cap);
+    }
+
+    fn disable(&self, cap: u32) {
+        WebGl2RenderingContext::disable(self, cap);
+    }
+
+    fn blend_func(&self, sfactor: u32, dfactor: u32) {
+        WebGl2RenderingContext::blend_func(self, sfactor, dfactor);
+    }
+
+    fn blend_equation(&self, mode: u32) {
+        WebGl2RenderingContext::blend_equation(self, mode);
+    }
+
+    fn depth_func(&self, func: u32) {
+        WebGl2RenderingContext::depth_func(self, func);
+    }
+
+    fn depth_mask(&self, flag: bool) {
+        WebGl2RenderingContext::depth_mask(self, flag);
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        WebGl2RenderingContext::viewport(self, x, y, width, height);
+    }
+
+    fn blend_func_separate(&self, src_rgb: u32, dst_rgb: u32, src_alpha: u32, dst_alpha: u32) {
+        WebGl2RenderingContext::blend_func_separate(self, src_rgb, dst_rgb, src_alpha, dst_alpha);
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        WebGl2RenderingContext::blend_equation_separate(self, mode_rgb, mode_alpha);
+    }
+
+    fn stencil_func(&self, func: u32, reference: i32, mask: u32) {
+        WebGl2RenderingContext::stencil_func(self, func, reference, mask);
+    }
+
+    fn stencil_op(&self, fail: u32, depth_fail: u32, pass: u32) {
+        WebGl2RenderingContext::stencil_op(self, fail, depth_fail, pass);
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        WebGl2RenderingContext::stencil_mask(self, mask);
+    }
+
+    fn cull_face(&self, mode: u32) {
+        WebGl2RenderingContext::cull_face(self, mode);
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        WebGl2RenderingContext::scissor(self, x, y, width, height);
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        WebGl2RenderingContext::color_mask(self, red, green, blue, alpha);
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        WebGl2RenderingContext::clear_color(self, red, green, blue, alpha);
+    }
+
+    fn clear_depth(&self, depth: f32) {
+        WebGl2RenderingContext::clear_depth(self, depth);
+    }
+
+    fn clear_stencil(&self, stencil: i32) {
+        WebGl2RenderingContext::clear_stencil(self, stencil);
+    }
+
+    // shaders & programs
+
+    fn create_shader(&self, shader_type: u32) -> Option<WebGlShader> {
+        WebGl2RenderingContext::create_shader(self, shader_type)
+    }
+
+    fn shader_source(&self, shader: &WebGlShader, source: &str) {
+        WebGl2RenderingContext::shader_source(self, shader, source);
+    }
+
+    fn compile_shader(&self, shader: &WebGlShader) {
+        WebGl2RenderingContext::compile_shader(self, shader);
+    }
+
+    fn get_shader_info_log(&self, shader: &WebGlShader) -> Option<String> {
+        WebGl2RenderingContext::get_shader_info_log(self, shader)
+    }
+
+    fn create_program(&self) -> Option<WebGlProgram> {
+        WebGl2RenderingContext::create_program(self)
+    }
+
+    fn delete_program(&self, program: Option<&WebGlProgram>) {
+        WebGl2RenderingContext::delete_program(self, program);
+    }
+
+    fn delete_shader(&self, shader: Option<&WebGlShader>) {
+        WebGl2RenderingContext::delete_shader(self, shader);
+    }
+
+    fn attach_shader(&self, program: &WebGlProgram, shader: &WebGlShader) {
+        WebGl2RenderingContext::attach_shader(self, program, shader);
+    }
+
+    fn detach_shader(&self, program: &WebGlProgram, shader: &WebGlShader) {
+        WebGl2RenderingContext::detach_shader(self, program, shader);
+    }
+
+    fn link_program(&self, program: &WebGlProgram) {
+        WebGl2RenderingContext::link_program(self, program);
+    }
+
+    fn get_program_info_log(&self, program: &WebGlProgram) -> Option<String> {
+        WebGl2RenderingContext::get_program_info_log(self, program)
+    }
+
+    fn use_program(&self, program: Option<&WebGlProgram>) {
+        WebGl2RenderingContext::use_program(self, program);
+    }
+
+    fn get_program_parameter(&self, program: &WebGlProgram, pname: u32) -> i32 {
+        let value = WebGl2RenderingContext::get_program_parameter(self, program, pname);
+        value
+            .as_f64()
+            .map(|v| v as i32)
+            .or_else(|| value.as_bool().map(|v| v as i32))
+            .unwrap_or(0)
+    }
+
+    // uniforms
+
+    fn get_active_uniform(&self, program: &WebGlProgram, index: u32) -> Option<ActiveInfo> {
+        WebGl2RenderingContext::get_active_uniform(self, program, index).map(|info| ActiveInfo {
+            name: info.name(),
+            size: info.size(),
+            data_type: info.type_(),
+        })
+    }
+
+    fn get_active_attrib(&self, program: &WebGlProgram, index: u32) -> Option<ActiveInfo> {
+        WebGl2RenderingContext::get_active_attrib(self, program, index).map(|info| ActiveInfo {
+            name: info.name(),
+            size: info.size(),
+            data_type: info.type_(),
+        })
+    }
+
+    fn get_attrib_location(&self, program: &WebGlProgram, name: &str) -> i32 {
+        WebGl2RenderingContext::get_attrib_location(self, program, name)
+    }
+
+    fn get_uniform_location(&self, program: &WebGlProgram, name: &str) -> Option<WebGlUniformLocation> {
+        WebGl2RenderingContext::get_uniform_location(self, program, name)
+    }
+
+    fn uniform1f(&self, location: Option<&WebGlUniformLocation>, v1: f32) {
+        WebGl2RenderingContext::uniform1f(self, location, v1);
+    }
+
+    fn uniform2f(&self, location: Option<&WebGlUniformLocation>, v1: f32, v2: f32) {
+        WebGl2RenderingContext::uniform2f(self, location, v1, v2);
+    }
+
+    fn uniform3f(&self, location: Option<&WebGlUniformLocation>, v1: f32, v2: f32, v3: f32) {
+        WebGl2RenderingContext::uniform3f(self, location, v1, v2, v3);
+    }
+
+    fn uniform4f(&self, location: Option<&WebGlUniformLocation>, v1: f32, v2: f32, v3: f32, v4: f32) {
+        WebGl2RenderingContext::uniform4f(self, location, v1, v2, v3, v4);
+    }
+
+    fn uniform1i(&self, location: Option<&WebGlUniformLocation>, v1: i32) {
+        WebGl2RenderingContext::uniform1i(self, location, v1);
+    }
+
+    fn uniform2i(&self, location: Option<&WebGlUniformLocation>, v1: i32, v2: i32) {
+        WebGl2RenderingContext::uniform2i(self, location, v1, v2);
+    }
+
+    fn uniform3i(&self, location: Option<&WebGlUniformLocation>, v1: i32, v2: i32, v3: i32) {
+        WebGl2RenderingContext::uniform3i(self, location, v1, v2, v3);
+    }
+
+    fn uniform4i(&self, location: Option<&WebGlUniformLocation>, v1: i32, v2: i32, v3: i32, v4: i32) {
+        WebGl2RenderingContext::uniform4i(self, location, v1, v2, v3, v4);
+    }
+
+    fn uniform1ui(&self, location: Option<&WebGlUniformLocation>, v1: u32) {
+        WebGl2RenderingContext::uniform1ui(self, location, v1);
+    }
+
+    fn uniform2ui(&self, location: Option<&WebGlUniformLocation>, v1: u32, v2: u32) {
+        WebGl2RenderingContext::uniform2ui(self, location, v1, v2);
+    }
+
+    fn uniform3ui(&self, location: Option<&WebGlUniformLocation>, v1: u32, v2: u32, v3: u32) {
+        WebGl2RenderingContext::uniform3ui(self, location, v1, v2, v3);
+    }
+
+    fn uniform4ui(&self, location: Option<&WebGlUniformLocation>, v1: u32, v2: u32, v3: u32, v4: u32) {
+        WebGl2RenderingContext::uniform4ui(self, location, v1, v2, v3, v4);
+    }
+
+    fn uniform1fv(&self, location: Option<&WebGlUniformLocation>, data: &[f32]) {
+        self.uniform1fv_with_f32_array(location, data);
+    }
+
+    fn uniform2fv(&self, location: Option<&WebGlUniformLocation>, data: &[f32]) {
+        self.uniform2fv_with_f32_array(location, data);
+    }
+
+    fn uniform3fv(&self, location: Option<&WebGlUniformLocation>, data: &[f32]) {
+        self.uniform3fv_with_f32_array(location, data);
+    }
+
+    fn uniform4fv(&self, location: Option<&WebGlUniformLocation>, data: &[f32]) {
+        self.uniform4fv_with_f32_array(location, data);
+    }
+
+    fn uniform1iv(&self, location: Option<&WebGlUniformLocation>, data: &[i32]) {
+        self.uniform1iv_with_i32_array(location, data);
+    }
+
+    fn uniform1uiv(&self, location: Option<&WebGlUniformLocation>, data: &[u32]) {
+        self.uniform1uiv_with_u32_array(location, data);
+    }
+
+    fn uniform_matrix2fv(&self, location: Option<&WebGlUniformLocation>, transpose: bool, data: &[f32]) {
+        self.uniform_matrix2fv_with_f32_array(location, transpose, data);
+    }
+
+    fn uniform_matrix3fv(&self, location: Option<&WebGlUniformLocation>, transpose: bool, data: &[f32]) {
+        self.uniform_matrix3fv_with_f32_array(location, transpose, data);
+    }
+
+    fn uniform_matrix4fv(&self, location: Option<&WebGlUniformLocation>, transpose: bool, data: &[f32]) {
+        self.uniform_matrix4fv_with_f32_array_and_src_offset_and_src_length(
+            location,
+            transpose,
+            data,
+            0,
+            data.len() as u32,
+        );
+    }
+
+    // uniform blocks
+
+    fn get_uniform_block_index(&self, program: &WebGlProgram, name: &str) -> u32 {
+        WebGl2RenderingContext::get_uniform_block_index(self, program, name)
+    }
+
+    fn uniform_block_binding(&self, program: &WebGlProgram, block_index: u32, binding: u32) {
+        WebGl2RenderingContext::uniform_block_binding(self, program, block_index, binding);
+    }
+
+    // buffers
+
+    fn create_buffer(&self) -> Option<WebGlBuffer> {
+        WebGl2RenderingContext::create_buffer(self)
+    }
+
+    fn delete_buffer(&self, buffer: Option<&WebGlBuffer>) {
+        WebGl2RenderingContext::delete_buffer(self, buffer);
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<&WebGlBuffer>) {
+        WebGl2RenderingContext::bind_buffer(self, target, buffer);
+    }
+
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<&WebGlBuffer>) {
+        WebGl2RenderingContext::bind_buffer_base(self, target, index, buffer);
+    }
+
+    fn buffer_data_with_size(&self, target: u32, size: i32, usage: u32) {
+        self.buffer_data_with_i32(target, size, usage);
+    }
+
+    fn buffer_data_with_u8_array(&self, target: u32, data: &[u8], usage: u32) {
+        WebGl2RenderingContext::buffer_data_with_u8_array(self, target, data, usage);
+    }
+
+    fn buffer_sub_data_with_u8_array(&self, target: u32, offset: i32, data: &[u8]) {
+        self.buffer_sub_data_with_i32_and_u8_array(target, offset, data);
+    }
+
+    // vertex arrays
+
+    fn create_vertex_array(&self) -> Option<WebGlVertexArrayObject> {
+        WebGl2RenderingContext::create_vertex_array(self)
+    }
+
+    fn delete_vertex_array(&self, vertex_array: Option<&WebGlVertexArrayObject>) {
+        WebGl2RenderingContext::delete_vertex_array(self, vertex_array);
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<&WebGlVertexArrayObject>) {
+        WebGl2RenderingContext::bind_vertex_array(self, vertex_array);
+    }
+
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32) {
+        self.vertex_attrib_pointer_with_i32(index, size, data_type, normalized, stride, offset);
+    }
+
+    fn vertex_attrib_i_pointer(&self, index: u32, size: i32, data_type: u32, stride: i32, offset: i32) {
+        self.vertex_attrib_i_pointer_with_i32(index, size, data_type, stride, offset);
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        WebGl2RenderingContext::enable_vertex_attrib_array(self, index);
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        WebGl2RenderingContext::vertex_attrib_divisor(self, index, divisor);
+    }
+
+    // draw calls
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        WebGl2RenderingContext::draw_arrays(self, mode, first, count);
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, data_type: u32, offset: i32) {
+        self.draw_elements_with_i32(mode, count, data_type, offset);
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instance_count: i32) {
+        WebGl2RenderingContext::draw_arrays_instanced(self, mode, first, count, instance_count);
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, data_type: u32, offset: i32, instance_count: i32) {
+        self.draw_elements_instanced_with_i32(mode, count, data_type, offset, instance_count);
+    }
+
+    // textures
+
+    fn create_texture(&self) -> Option<WebGlTexture> {
+        WebGl2RenderingContext::create_texture(self)
+    }
+
+    fn delete_texture(&self, texture: Option<&WebGlTexture>) {
+        WebGl2RenderingContext::delete_texture(self, texture);
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<&WebGlTexture>) {
+        WebGl2RenderingContext::bind_texture(self, target, texture);
+    }
+
+    fn active_texture(&self, texture: u32) {
+        WebGl2RenderingContext::active_texture(self, texture);
+    }
+
+    fn tex_parameteri(&self, target: u32, pname: u32, param: i32) {
+        WebGl2RenderingContext::tex_parameteri(self, target, pname, param);
+    }
+
+    fn tex_parameterf(&self, target: u32, pname: u32, param: f32) {
+        WebGl2RenderingContext::tex_parameterf(self, target, pname, param);
+    }
+
+    fn generate_mipmap(&self, target: u32) {
+        WebGl2RenderingContext::generate_mipmap(self, target);
+    }
+
+    fn pixel_storei(&self, pname: u32, param: i32) {
+        WebGl2RenderingContext::pixel_storei(self, pname, param);
+    }
+
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        self.tex_image_2d_with_u32_and_u32_and_html_image_element(target, level, internal_format, format, data_type, img)
+            .ok()
+            .ok_or(SWGLRuntimeError::new("GlBackend::TexImage2DError"))
+    }
+
+    fn tex_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> SWGLResult<()> {
+        self.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            0,
+            format,
+            data_type,
+            pixels.map(|pixels| pixel_array_view(data_type, pixels)).as_ref(),
+        )
+        .ok()
+        .ok_or(SWGLRuntimeError::new("GlBackend::TexImage2DError"))
+    }
+
+    fn tex_image_3d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        border: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        WebGl2RenderingContext::tex_image_3d_with_html_image_element(
+            self,
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            depth,
+            border,
+            format,
+            data_type,
+            img,
+        )
+        .ok()
+        .ok_or(SWGLRuntimeError::new("GlBackend::TexImage3DError"))
+    }
+
+    fn tex_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> SWGLResult<()> {
+        self.tex_image_3d_with_opt_array_buffer_view(
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            depth,
+            0,
+            format,
+            data_type,
+            pixels.map(|pixels| pixel_array_view(data_type, pixels)).as_ref(),
+        )
+        .ok()
+        .ok_or(SWGLRuntimeError::new("GlBackend::TexImage3DError"))
+    }
+
+    fn tex_sub_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: &[u8],
+    ) -> SWGLResult<()> {
+        self.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_array_buffer_view(
+            target,
+            level,
+            x_offset,
+            y_offset,
+            width,
+            height,
+            format,
+            data_type,
+            Some(&pixel_array_view(data_type, pixels)),
+        )
+        .ok()
+        .ok_or(SWGLRuntimeError::new("GlBackend::TexSubImage2DError"))
+    }
+
+    fn tex_sub_image_3d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        img: &HtmlImageElement,
+    ) -> SWGLResult<()> {
+        WebGl2RenderingContext::tex_sub_image_3d_with_html_image_element(
+            self, target, level, x_offset, y_offset, z_offset, width, height, depth, format, data_type, img,
+        )
+        .ok()
+        .ok_or(SWGLRuntimeError::new("GlBackend::TexSubImage3DError"))
+    }
+
+    fn tex_sub_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: u32,
+        data_type: u32,
+        pixels: &[u8],
+    ) -> SWGLResult<()> {
+        self.tex_sub_image_3d_with_opt_array_buffer_view(
+            target,
+            level,
+            x_offset,
+            y_offset,
+            z_offset,
+            width,
+            height,
+            depth,
+            format,
+            data_type,
+            Some(&pixel_array_view(data_type, pixels)),
+        )
+        .ok()
+        .ok_or(SWGLRuntimeError::new("GlBackend::TexSubImage3DError"))
+    }
+
+    fn compressed_tex_image_2d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        data: &[u8],
+    ) {
+        WebGl2RenderingContext::compressed_tex_image_2d_with_u8_array(
+            self, target, level, internal_format, width, height, 0, data,
+        );
+    }
+
+    fn compressed_tex_image_3d_with_u8_array(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        data: &[u8],
+    ) {
+        WebGl2RenderingContext::compressed_tex_image_3d_with_u8_array(
+            self, target, level, internal_format, width, height, depth, 0, data,
+        );
+    }
+
+    // samplers
+
+    fn create_sampler(&self) -> Option<WebGlSampler> {
+        WebGl2RenderingContext::create_sampler(self)
+    }
+
+    fn delete_sampler(&self, sampler: Option<&WebGlSampler>) {
+        WebGl2RenderingContext::delete_sampler(self, sampler);
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<&WebGlSampler>) {
+        WebGl2RenderingContext::bind_sampler(self, unit, sampler);
+    }
+
+    fn sampler_parameteri(&self, sampler: &WebGlSampler, pname: u32, param: i32) {
+        WebGl2RenderingContext::sampler_parameteri(self, sampler, pname, param);
+    }
+
+    fn sampler_parameterf(&self, sampler: &WebGlSampler, pname: u32, param: f32) {
+        WebGl2RenderingContext::sampler_parameterf(self, sampler, pname, param);
+    }
+
+    // framebuffers
+
+    fn create_framebuffer(&self) -> Option<WebGlFramebuffer> {
+        WebGl2RenderingContext::create_framebuffer(self)
+    }
+
+    fn delete_framebuffer(&self, framebuffer: Option<&WebGlFramebuffer>) {
+        WebGl2RenderingContext::delete_framebuffer(self, framebuffer);
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<&WebGlFramebuffer>) {
+        WebGl2RenderingContext::bind_framebuffer(self, target, framebuffer);
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, tex_target: u32, texture: Option<&WebGlTexture>, level: i32) {
+        WebGl2RenderingContext::framebuffer_texture_2d(self, target, attachment, tex_target, texture, level);
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffer_target: u32, renderbuffer: Option<&WebGlRenderbuffer>) {
+        WebGl2RenderingContext::framebuffer_renderbuffer(self, target, attachment, renderbuffer_target, renderbuffer);
+    }
+
+    fn check_framebuffer_status(&self, target: u32) -> u32 {
+        WebGl2RenderingContext::check_framebuffer_status(self, target)
+    }
+
+    fn create_renderbuffer(&self) -> Option<WebGlRenderbuffer> {
+        WebGl2RenderingContext::create_renderbuffer(self)
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: Option<&WebGlRenderbuffer>) {
+        WebGl2RenderingContext::delete_renderbuffer(self, renderbuffer);
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<&WebGlRenderbuffer>) {
+        WebGl2RenderingContext::bind_renderbuffer(self, target, renderbuffer);
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32) {
+        WebGl2RenderingContext::renderbuffer_storage(self, target, internal_format, width, height);
+    }
+
+    fn renderbuffer_storage_multisample(&self, target: u32, samples: i32, internal_format: u32, width: i32, height: i32) {
+        WebGl2RenderingContext::renderbuffer_storage_multisample(self, target, samples, internal_format, width, height);
+    }
+
+    fn blit_framebuffer(
+        &self,
+        src_x0: i32,
+        src_y0: i32,
+        src_x1: i32,
+        src_y1: i32,
+        dst_x0: i32,
+        dst_y0: i32,
+        dst_x1: i32,
+        dst_y1: i32,
+        mask: u32,
+        filter: u32,
+    ) {
+        WebGl2RenderingContext::blit_framebuffer(
+            self, src_x0, src_y0, src_x1, src_y1, dst_x0, dst_y0, dst_x1, dst_y1, mask, filter,
+        );
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// WebGL rejects texture uploads when type of the array doesn't match data_type (e.g. FLOAT needs Float32Array),
+/// so pixels are copied into JS memory and viewed as the matching typed array.
+fn pixel_array_view(data_type: u32, pixels: &[u8]) -> js_sys::Object {
+    let bytes = js_sys::Uint8Array::from(pixels);
+    let buffer = bytes.buffer();
+    match data_type {
+        WebGl2RenderingContext::BYTE => js_sys::Int8Array::new(&buffer).into(),
+        WebGl2RenderingContext::SHORT => js_sys::Int16Array::new(&buffer).into(),
+        WebGl2RenderingContext::INT => js_sys::Int32Array::new(&buffer).into(),
+        WebGl2RenderingContext::FLOAT => js_sys::Float32Array::new(&buffer).into(),
+        WebGl2RenderingContext::UNSIGNED_SHORT
+        | WebGl2RenderingContext::HALF_FLOAT
+        | WebGl2RenderingContext::UNSIGNED_SHORT_5_6_5
+        | WebGl2RenderingContext::UNSIGNED_SHORT_4_4_4_4
+        | WebGl2RenderingContext::UNSIGNED_SHORT_5_5_5_1 => js_sys::Uint16Array::new(&buffer).into(),
+        WebGl2RenderingContext::UNSIGNED_INT
+        | WebGl2RenderingContext::UNSIGNED_INT_24_8
+        | WebGl2RenderingContext::UNSIGNED_INT_2_10_10_10_REV
+        | WebGl2RenderingContext::UNSIGNED_INT_10F_11F_11F_REV
+        | WebGl2RenderingContext::UNSIGNED_INT_5_9_9_9_REV => js_sys::Uint32Array::new(&buffer).into(),
+        _ => bytes.into(),
+    }
+}
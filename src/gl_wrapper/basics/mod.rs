@@ -1,4 +1,88 @@
+use super::backend::interface::GlBackend;
+use super::super::graphics_2d::color::Color;
 
-pub fn clear_canvas(context: &crate::AppContext) {
+pub mod render_state;
+
+// -----------------------------------------------------------------------------------------------------------
+
+pub fn clear_canvas<B: GlBackend>(context: &B) {
     context.clear(crate::AppContext::COLOR_BUFFER_BIT | crate::AppContext::DEPTH_BUFFER_BIT);
 }
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Values written by clear, None means the buffer is not cleared.
+/// Color and depth writes are affected by the current color mask and depth write flag (see render_state).
+#[derive(Debug, Clone, Copy)]
+pub struct ClearValues {
+    pub color: Option<Color>,
+    pub depth: Option<f32>,
+    pub stencil: Option<i32>,
+}
+
+impl Default for ClearValues {
+    /// Transparent black color and the farthest depth, stencil buffer is not cleared.
+    fn default() -> Self {
+        Self {
+            color: Some(Color::new(0.0, 0.0, 0.0, 0.0)),
+            depth: Some(1.0),
+            stencil: None,
+        }
+    }
+}
+
+pub fn clear<B: GlBackend>(context: &B, values: &ClearValues) {
+    let mut mask = 0;
+    if let Some(color) = &values.color {
+        context.clear_color(color.red, color.green, color.blue, color.alpha);
+        mask |= crate::AppContext::COLOR_BUFFER_BIT;
+    }
+    if let Some(depth) = values.depth {
+        context.clear_depth(depth);
+        mask |= crate::AppContext::DEPTH_BUFFER_BIT;
+    }
+    if let Some(stencil) = values.stencil {
+        context.clear_stencil(stencil);
+        mask |= crate::AppContext::STENCIL_BUFFER_BIT;
+    }
+
+    if mask != 0 {
+        context.clear(mask);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::backend::recording_backend::{GlCall, RecordingBackend};
+
+    #[test]
+    fn clear_sends_only_requested_buffers() {
+        let context = RecordingBackend::new();
+        clear(&context, &ClearValues::default());
+        assert_eq!(
+            context.calls(),
+            vec![
+                GlCall::ClearColor { red: 0.0, green: 0.0, blue: 0.0, alpha: 0.0 },
+                GlCall::ClearDepth { depth: 1.0 },
+                GlCall::Clear { mask: crate::AppContext::COLOR_BUFFER_BIT | crate::AppContext::DEPTH_BUFFER_BIT },
+            ]
+        );
+
+        let context = RecordingBackend::new();
+        clear(&context, &ClearValues { color: None, depth: None, stencil: Some(1) });
+        assert_eq!(
+            context.calls(),
+            vec![GlCall::ClearStencil { stencil: 1 }, GlCall::Clear { mask: crate::AppContext::STENCIL_BUFFER_BIT }]
+        );
+    }
+
+    #[test]
+    fn clear_without_values_does_nothing() {
+        let context = RecordingBackend::new();
+        clear(&context, &ClearValues { color: None, depth: None, stencil: None });
+        assert!(context.calls().is_empty());
+    }
+}
@@ -0,0 +1,554 @@
+//! Fixed-function state used by draw calls (blending, depth and stencil tests, culling, scissor, viewport, color mask).
+
+use super::super::backend::interface::GlBackend;
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    SrcAlphaSaturate,
+}
+
+impl BlendFactor {
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Zero => crate::AppContext::ZERO,
+            Self::One => crate::AppContext::ONE,
+            Self::SrcColor => crate::AppContext::SRC_COLOR,
+            Self::OneMinusSrcColor => crate::AppContext::ONE_MINUS_SRC_COLOR,
+            Self::DstColor => crate::AppContext::DST_COLOR,
+            Self::OneMinusDstColor => crate::AppContext::ONE_MINUS_DST_COLOR,
+            Self::SrcAlpha => crate::AppContext::SRC_ALPHA,
+            Self::OneMinusSrcAlpha => crate::AppContext::ONE_MINUS_SRC_ALPHA,
+            Self::DstAlpha => crate::AppContext::DST_ALPHA,
+            Self::OneMinusDstAlpha => crate::AppContext::ONE_MINUS_DST_ALPHA,
+            Self::SrcAlphaSaturate => crate::AppContext::SRC_ALPHA_SATURATE,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendEquation {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendEquation {
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Add => crate::AppContext::FUNC_ADD,
+            Self::Subtract => crate::AppContext::FUNC_SUBTRACT,
+            Self::ReverseSubtract => crate::AppContext::FUNC_REVERSE_SUBTRACT,
+            Self::Min => crate::AppContext::MIN,
+            Self::Max => crate::AppContext::MAX,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Result color is: equation(src * src_factor, dst * dst_factor), computed separately for RGB and alpha.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlendState {
+    pub equation_rgb: BlendEquation,
+    pub equation_alpha: BlendEquation,
+    pub src_rgb: BlendFactor,
+    pub dst_rgb: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+}
+
+impl BlendState {
+    /// This method creates state with the same equation and factors for RGB and alpha.
+    pub fn new(equation: BlendEquation, src: BlendFactor, dst: BlendFactor) -> Self {
+        Self {
+            equation_rgb: equation,
+            equation_alpha: equation,
+            src_rgb: src,
+            dst_rgb: dst,
+            src_alpha: src,
+            dst_alpha: dst,
+        }
+    }
+
+    /// Standard transparency for colors which are not premultiplied by alpha, alpha channel is blended with the same
+    /// factors as colors (default blending of the context).
+    pub fn alpha() -> Self {
+        Self::new(BlendEquation::Add, BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    /// Transparency like alpha, but alpha channel is composed with "over" operator (src + dst * (1 - src_alpha)),
+    /// so transparent canvas or render target keeps correct coverage.
+    pub fn alpha_over() -> Self {
+        Self {
+            src_alpha: BlendFactor::One,
+            ..Self::alpha()
+        }
+    }
+
+    /// Transparency for colors premultiplied by alpha (e.g. textures exported with premultiplied alpha).
+    pub fn premultiplied() -> Self {
+        Self::new(BlendEquation::Add, BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    /// Source color is added to the destination (e.g. lights, particles).
+    pub fn additive() -> Self {
+        Self {
+            src_alpha: BlendFactor::One,
+            dst_alpha: BlendFactor::One,
+            ..Self::new(BlendEquation::Add, BlendFactor::SrcAlpha, BlendFactor::One)
+        }
+    }
+
+    /// Destination color is multiplied by the source color (e.g. shadows, tinting).
+    pub fn multiply() -> Self {
+        Self {
+            src_alpha: BlendFactor::Zero,
+            dst_alpha: BlendFactor::One,
+            ..Self::new(BlendEquation::Add, BlendFactor::DstColor, BlendFactor::Zero)
+        }
+    }
+
+    pub fn apply<B: GlBackend>(&self, context: &B) {
+        context.blend_equation_separate(
+            self.equation_rgb.get_gl_property_value(),
+            self.equation_alpha.get_gl_property_value(),
+        );
+        context.blend_func_separate(
+            self.src_rgb.get_gl_property_value(),
+            self.dst_rgb.get_gl_property_value(),
+            self.src_alpha.get_gl_property_value(),
+            self.dst_alpha.get_gl_property_value(),
+        );
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+impl CompareFunction {
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Never => crate::AppContext::NEVER,
+            Self::Less => crate::AppContext::LESS,
+            Self::Equal => crate::AppContext::EQUAL,
+            Self::LessOrEqual => crate::AppContext::LEQUAL,
+            Self::Greater => crate::AppContext::GREATER,
+            Self::NotEqual => crate::AppContext::NOTEQUAL,
+            Self::GreaterOrEqual => crate::AppContext::GEQUAL,
+            Self::Always => crate::AppContext::ALWAYS,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Depth buffer is written only when the depth test is enabled (GL rule), but write flag also affects clearing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthState {
+    pub test: bool,
+    pub write: bool,
+    pub func: CompareFunction,
+}
+
+impl DepthState {
+    /// Depth test is disabled, so draws neither test nor write depth. Write flag stays enabled (GL default),
+    /// so clear still resets the depth buffer.
+    pub fn disabled() -> Self {
+        Self {
+            test: false,
+            write: true,
+            func: CompareFunction::Less,
+        }
+    }
+
+    /// Fragments with lower depth (z_index) cover the ones drawn earlier.
+    pub fn less() -> Self {
+        Self {
+            test: true,
+            write: true,
+            func: CompareFunction::Less,
+        }
+    }
+
+    /// Depth is tested, but not written (e.g. for transparent objects drawn after the opaque ones).
+    pub fn read_only() -> Self {
+        Self {
+            test: true,
+            write: false,
+            func: CompareFunction::LessOrEqual,
+        }
+    }
+
+    pub fn apply<B: GlBackend>(&self, context: &B) {
+        if self.test {
+            context.enable(crate::AppContext::DEPTH_TEST);
+        } else {
+            context.disable(crate::AppContext::DEPTH_TEST);
+        }
+        context.depth_func(self.func.get_gl_property_value());
+        context.depth_mask(self.write);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StencilOperation {
+    Keep,
+    Zero,
+    Replace,
+    Increment,
+    IncrementWrap,
+    Decrement,
+    DecrementWrap,
+    Invert,
+}
+
+impl StencilOperation {
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Keep => crate::AppContext::KEEP,
+            Self::Zero => crate::AppContext::ZERO,
+            Self::Replace => crate::AppContext::REPLACE,
+            Self::Increment => crate::AppContext::INCR,
+            Self::IncrementWrap => crate::AppContext::INCR_WRAP,
+            Self::Decrement => crate::AppContext::DECR,
+            Self::DecrementWrap => crate::AppContext::DECR_WRAP,
+            Self::Invert => crate::AppContext::INVERT,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Fragment passes the stencil test if: func(reference & read_mask, stencil & read_mask) is true.
+/// The same state is used for front and back faces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StencilState {
+    pub func: CompareFunction,
+    pub reference: i32,
+    pub read_mask: u32,
+    pub write_mask: u32,
+    /// Operation used when the stencil test fails.
+    pub fail: StencilOperation,
+    /// Operation used when the stencil test passes, but the depth test fails.
+    pub depth_fail: StencilOperation,
+    /// Operation used when both tests pass.
+    pub pass: StencilOperation,
+}
+
+impl StencilState {
+    /// This method creates state which writes reference value wherever something is drawn (e.g. mask shape).
+    pub fn write(reference: i32) -> Self {
+        Self {
+            func: CompareFunction::Always,
+            reference,
+            read_mask: 0xff,
+            write_mask: 0xff,
+            fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Replace,
+        }
+    }
+
+    /// This method creates state which draws only where stencil buffer is equal to the reference value.
+    pub fn test_equal(reference: i32) -> Self {
+        Self {
+            func: CompareFunction::Equal,
+            reference,
+            read_mask: 0xff,
+            write_mask: 0,
+            fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Keep,
+        }
+    }
+
+    pub fn apply<B: GlBackend>(&self, context: &B) {
+        context.stencil_func(self.func.get_gl_property_value(), self.reference, self.read_mask);
+        context.stencil_op(
+            self.fail.get_gl_property_value(),
+            self.depth_fail.get_gl_property_value(),
+            self.pass.get_gl_property_value(),
+        );
+        context.stencil_mask(self.write_mask);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CullFace {
+    Front,
+    Back,
+    FrontAndBack,
+}
+
+impl CullFace {
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Front => crate::AppContext::FRONT,
+            Self::Back => crate::AppContext::BACK,
+            Self::FrontAndBack => crate::AppContext::FRONT_AND_BACK,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Rectangle in framebuffer pixels, (x, y) is the left bottom corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl PixelRect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorMask {
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+    pub alpha: bool,
+}
+
+impl ColorMask {
+    pub fn all() -> Self {
+        Self {
+            red: true,
+            green: true,
+            blue: true,
+            alpha: true,
+        }
+    }
+
+    /// Color is not written (e.g. when only stencil buffer is filled).
+    pub fn none() -> Self {
+        Self {
+            red: false,
+            green: false,
+            blue: false,
+            alpha: false,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Complete render state, it is applied as a whole, so state left by previous draws doesn't matter.
+/// None disables the feature, except viewport, where None leaves the current value.
+/// Wrap the context in CachedBackend to skip calls which don't change anything.
+///
+/// ```text
+/// let sprites = RenderState { depth: DepthState::less(), ..RenderState::default() };
+/// let lights = RenderState { blend: Some(BlendState::additive()), depth: DepthState::read_only(), ..sprites };
+/// sprites.apply(&context);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderState {
+    pub blend: Option<BlendState>,
+    pub depth: DepthState,
+    pub stencil: Option<StencilState>,
+    pub cull_face: Option<CullFace>,
+    pub scissor: Option<PixelRect>,
+    pub viewport: Option<PixelRect>,
+    pub color_mask: ColorMask,
+}
+
+impl Default for RenderState {
+    /// Alpha blending without depth test, culling and scissor.
+    fn default() -> Self {
+        Self {
+            blend: Some(BlendState::alpha()),
+            depth: DepthState::disabled(),
+            stencil: None,
+            cull_face: None,
+            scissor: None,
+            viewport: None,
+            color_mask: ColorMask::all(),
+        }
+    }
+}
+
+impl RenderState {
+    pub fn apply<B: GlBackend>(&self, context: &B) {
+        match &self.blend {
+            Some(blend) => {
+                context.enable(crate::AppContext::BLEND);
+                blend.apply(context);
+            }
+            None => context.disable(crate::AppContext::BLEND),
+        }
+
+        self.depth.apply(context);
+
+        match &self.stencil {
+            Some(stencil) => {
+                context.enable(crate::AppContext::STENCIL_TEST);
+                stencil.apply(context);
+            }
+            None => context.disable(crate::AppContext::STENCIL_TEST),
+        }
+
+        match &self.cull_face {
+            Some(cull_face) => {
+                context.enable(crate::AppContext::CULL_FACE);
+                context.cull_face(cull_face.get_gl_property_value());
+            }
+            None => context.disable(crate::AppContext::CULL_FACE),
+        }
+
+        match &self.scissor {
+            Some(rect) => {
+                context.enable(crate::AppContext::SCISSOR_TEST);
+                context.scissor(rect.x, rect.y, rect.width, rect.height);
+            }
+            None => context.disable(crate::AppContext::SCISSOR_TEST),
+        }
+
+        if let Some(rect) = &self.viewport {
+            context.viewport(rect.x, rect.y, rect.width, rect.height);
+        }
+
+        context.color_mask(self.color_mask.red, self.color_mask.green, self.color_mask.blue, self.color_mask.alpha);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::backend::recording_backend::{GlCall, RecordingBackend};
+
+    #[test]
+    fn default_state_blends_all_channels_like_context_default() {
+        let context = RecordingBackend::new();
+        RenderState::default().apply(&context);
+
+        let calls = context.calls();
+        assert!(calls.contains(&GlCall::Enable { cap: crate::AppContext::BLEND }));
+        assert!(calls.contains(&GlCall::BlendFuncSeparate {
+            src_rgb: crate::AppContext::SRC_ALPHA,
+            dst_rgb: crate::AppContext::ONE_MINUS_SRC_ALPHA,
+            src_alpha: crate::AppContext::SRC_ALPHA,
+            dst_alpha: crate::AppContext::ONE_MINUS_SRC_ALPHA,
+        }));
+        assert!(calls.contains(&GlCall::Disable { cap: crate::AppContext::DEPTH_TEST }));
+        assert!(calls.contains(&GlCall::DepthMask { flag: true }));
+    }
+
+    #[test]
+    fn alpha_over_blends_alpha_separately() {
+        let blend = BlendState::alpha_over();
+        assert_eq!(blend.src_alpha, BlendFactor::One);
+        assert_eq!(blend.dst_alpha, BlendFactor::OneMinusSrcAlpha);
+        assert_eq!((blend.src_rgb, blend.dst_rgb), (BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha));
+    }
+
+    #[test]
+    fn apply_sets_every_enabled_feature() {
+        let context = RecordingBackend::new();
+        let state = RenderState {
+            blend: Some(BlendState::additive()),
+            depth: DepthState::read_only(),
+            stencil: Some(StencilState::test_equal(3)),
+            cull_face: Some(CullFace::Back),
+            scissor: Some(PixelRect::new(1, 2, 30, 40)),
+            viewport: Some(PixelRect::new(0, 0, 800, 600)),
+            color_mask: ColorMask { alpha: false, ..ColorMask::all() },
+        };
+        state.apply(&context);
+
+        assert_eq!(
+            context.calls(),
+            vec![
+                GlCall::Enable { cap: crate::AppContext::BLEND },
+                GlCall::BlendEquationSeparate { mode_rgb: crate::AppContext::FUNC_ADD, mode_alpha: crate::AppContext::FUNC_ADD },
+                GlCall::BlendFuncSeparate {
+                    src_rgb: crate::AppContext::SRC_ALPHA,
+                    dst_rgb: crate::AppContext::ONE,
+                    src_alpha: crate::AppContext::ONE,
+                    dst_alpha: crate::AppContext::ONE,
+                },
+                GlCall::Enable { cap: crate::AppContext::DEPTH_TEST },
+                GlCall::DepthFunc { func: crate::AppContext::LEQUAL },
+                GlCall::DepthMask { flag: false },
+                GlCall::Enable { cap: crate::AppContext::STENCIL_TEST },
+                GlCall::StencilFunc { func: crate::AppContext::EQUAL, reference: 3, mask: 0xff },
+                GlCall::StencilOp { fail: crate::AppContext::KEEP, depth_fail: crate::AppContext::KEEP, pass: crate::AppContext::KEEP },
+                GlCall::StencilMask { mask: 0 },
+                GlCall::Enable { cap: crate::AppContext::CULL_FACE },
+                GlCall::CullFace { mode: crate::AppContext::BACK },
+                GlCall::Enable { cap: crate::AppContext::SCISSOR_TEST },
+                GlCall::Scissor { x: 1, y: 2, width: 30, height: 40 },
+                GlCall::Viewport { x: 0, y: 0, width: 800, height: 600 },
+                GlCall::ColorMask { red: true, green: true, blue: true, alpha: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_disables_missing_features_and_keeps_viewport() {
+        let context = RecordingBackend::new();
+        let state = RenderState { blend: None, ..RenderState::default() };
+        state.apply(&context);
+
+        let calls = context.calls();
+        for cap in [
+            crate::AppContext::BLEND,
+            crate::AppContext::DEPTH_TEST,
+            crate::AppContext::STENCIL_TEST,
+            crate::AppContext::CULL_FACE,
+            crate::AppContext::SCISSOR_TEST,
+        ]
+        .iter()
+        {
+            assert!(calls.contains(&GlCall::Disable { cap: *cap }), "{}", cap);
+        }
+        assert!(!calls.iter().any(|call| matches!(
+            call,
+            GlCall::Enable { .. }
+                | GlCall::BlendFuncSeparate { .. }
+                | GlCall::StencilFunc { .. }
+                | GlCall::Scissor { .. }
+                | GlCall::Viewport { .. }
+        )));
+        // depth writes stay enabled, so clear resets the depth buffer
+        assert!(calls.contains(&GlCall::DepthMask { flag: true }));
+    }
+}
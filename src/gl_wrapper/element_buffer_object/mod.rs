@@ -1,59 +1,158 @@
-//! WebGL EBO Object abstraction. 
+//! WebGL EBO Object abstraction.
 
-use web_sys::WebGlBuffer;
 use super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::backend::interface::{self, GlBackend};
+use super::vertex_buffer_object::DataStorageType;
 
 // -----------------------------------------------------------------------------------------------------------
 
-pub struct ElementBufferObject {
-    ebo: WebGlBuffer,
+/// This trait is implemented by types that can be used as indices (u8, u16 and u32), it can't be implemented
+/// outside of this crate (see interface::PlainData).
+pub trait IndexType: interface::PlainData {
+    fn get_gl_property_value() -> u32;
+}
+
+impl IndexType for u8 {
+    fn get_gl_property_value() -> u32 {
+        crate::AppContext::UNSIGNED_BYTE
+    }
+}
+
+impl IndexType for u16 {
+    fn get_gl_property_value() -> u32 {
+        crate::AppContext::UNSIGNED_SHORT
+    }
+}
+
+impl IndexType for u32 {
+    fn get_gl_property_value() -> u32 {
+        crate::AppContext::UNSIGNED_INT
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// GL buffer is deleted when this object is dropped.
+pub struct ElementBufferObject<B: GlBackend = crate::AppContext, I: IndexType = u32> {
+    ebo: B::Buffer,
     size: usize,
+    #[allow(dead_code)]
+    data_storage_type: DataStorageType,
+    index_type: std::marker::PhantomData<I>,
+    context: B,
 }
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl ElementBufferObject {
-    pub fn new(context: &crate::AppContext, indices: &[u32]) -> SWGLResult<Self> {
-        let buffer;
-        let try_buffer = context.create_buffer();
+impl<B: GlBackend, I: IndexType> ElementBufferObject<B, I> {
+    /// This method creates buffer with DataStorageType::StaticDraw.
+    pub fn new(context: &B, indices: &[I]) -> SWGLResult<Self> {
+        Self::new_with_storage(context, indices, DataStorageType::StaticDraw)
+    }
 
-        if let Some(val) = try_buffer {
-            buffer = val;
-        } else {
-            return Err(SWGLRuntimeError::new(
-                "ElementBufferObject::CreationError",
-            ));
+    pub fn new_with_storage(context: &B, indices: &[I], data_storage_type: DataStorageType) -> SWGLResult<Self> {
+        if let Some(buffer) = context.create_buffer() {
+            let ebo = ElementBufferObject {
+                ebo: buffer,
+                size: indices.len(),
+                data_storage_type,
+                index_type: std::marker::PhantomData,
+                context: context.clone(),
+            };
+            ebo.bind_for_upload(context);
+
+            context.buffer_data_with_u8_array(
+                crate::AppContext::ELEMENT_ARRAY_BUFFER,
+                interface::as_byte_slice(indices),
+                data_storage_type.get_gl_property_value(),
+            );
+            return Ok(ebo);
         }
 
-        let ebo = ElementBufferObject {
-            ebo: buffer,
-            size: indices.len(),
-        };
-        ebo.bind(context);
+        Err(SWGLRuntimeError::new("ElementBufferObject::CreationError"))
+    }
 
-        unsafe {
-            let vert_array = js_sys::Uint32Array::view(&indices);
-            context.buffer_data_with_array_buffer_view(
+    /// This method gets size parameter in elements (not in bytes).
+    pub fn new_empty(context: &B, size: usize, data_storage_type: DataStorageType) -> SWGLResult<Self> {
+        if let Some(buffer) = context.create_buffer() {
+            let ebo = ElementBufferObject {
+                ebo: buffer,
+                size,
+                data_storage_type,
+                index_type: std::marker::PhantomData,
+                context: context.clone(),
+            };
+            ebo.bind_for_upload(context);
+            context.buffer_data_with_size(
                 crate::AppContext::ELEMENT_ARRAY_BUFFER,
-                &vert_array,
-                crate::AppContext::STATIC_DRAW,
+                (size * std::mem::size_of::<I>()) as i32,
+                data_storage_type.get_gl_property_value(),
             );
+
+            return Ok(ebo);
         }
 
-        Ok(ebo)
+        Err(SWGLRuntimeError::new("ElementBufferObject::CreationError"))
     }
 
-    pub fn bind(&self, context: &crate::AppContext) {
+    /// This method deletes the buffer immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    pub fn bind(&self, context: &B) {
         context.bind_buffer(crate::AppContext::ELEMENT_ARRAY_BUFFER, Some(&self.ebo));
     }
 
-    /// This method returns size in elements (not in bytes). 
+    /// This method returns size in elements (not in bytes).
     pub fn get_size(&self) -> usize {
         self.size
     }
 
+    /// This method returns GL type of indices (UNSIGNED_BYTE, UNSIGNED_SHORT or UNSIGNED_INT).
+    pub fn get_index_type(&self) -> u32 {
+        I::get_gl_property_value()
+    }
+
+    /// This method returns byte offset of n-th index.
+    pub fn get_byte_offset(&self, index: u32) -> usize {
+        index as usize * std::mem::size_of::<I>()
+    }
+
+    /// EBO binding is a part of the VAO state, so VAO is unbound first to not attach this buffer to it.
+    fn bind_for_upload(&self, context: &B) {
+        context.bind_vertex_array(None);
+        self.bind(context);
+    }
+
+    /// This method gets offset parameter, that reffers to n-element (index), not to n-byte.
+    /// It unbinds the current VAO.
+    pub fn update_data(&self, context: &B, indices: &[I], offset: u32) {
+        self.bind_for_upload(context);
+
+        context.buffer_sub_data_with_u8_array(
+            crate::AppContext::ELEMENT_ARRAY_BUFFER,
+            self.get_byte_offset(offset) as i32,
+            interface::as_byte_slice(indices),
+        );
+    }
+
+    pub fn update_data_safe(&self, context: &B, indices: &[I], offset: u32) -> SWGLResult<()> {
+        if offset as usize + indices.len() > self.size {
+            return Err(SWGLRuntimeError::new(
+                "ElementBufferObject::UpdateDataOverflow",
+            ));
+        }
+        self.update_data(context, indices, offset);
+        Ok(())
+    }
+
     /// This method returns object that can be used directly inside WebGL low level calls.
-    pub fn get_raw_id(&self) -> &WebGlBuffer {
+    pub fn get_raw_id(&self) -> &B::Buffer {
         &self.ebo
     }
 }
+
+impl<B: GlBackend, I: IndexType> Drop for ElementBufferObject<B, I> {
+    fn drop(&mut self) {
+        self.context.delete_buffer(Some(&self.ebo));
+    }
+}
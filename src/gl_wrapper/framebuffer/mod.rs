@@ -0,0 +1,239 @@
+//! WebGL Framebuffer Object abstraction.
+
+use super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::backend::interface::GlBackend;
+use super::texture::texture_2d::Texture2D;
+
+pub mod renderbuffer;
+pub mod render_target;
+pub mod multisample_render_target;
+use renderbuffer::Renderbuffer;
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FramebufferAttachment {
+    /// Color attachment with given index (COLOR_ATTACHMENT0 + index).
+    Color(u32),
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+impl FramebufferAttachment {
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Color(index) => crate::AppContext::COLOR_ATTACHMENT0 + index,
+            Self::Depth => crate::AppContext::DEPTH_ATTACHMENT,
+            Self::Stencil => crate::AppContext::STENCIL_ATTACHMENT,
+            Self::DepthStencil => crate::AppContext::DEPTH_STENCIL_ATTACHMENT,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Framebuffer doesn't own attached textures and renderbuffers (see RenderTarget), they have to live as long as
+/// the framebuffer is used. GL framebuffer is deleted when this object is dropped.
+pub struct Framebuffer<B: GlBackend = crate::AppContext> {
+    framebuffer: B::Framebuffer,
+    context: B,
+}
+
+impl<B: GlBackend> Framebuffer<B> {
+    pub fn new(context: &B) -> SWGLResult<Self> {
+        if let Some(framebuffer) = context.create_framebuffer() {
+            return Ok(Self {
+                framebuffer,
+                context: context.clone(),
+            });
+        }
+
+        Err(SWGLRuntimeError::new("Framebuffer::CreationError"))
+    }
+
+    /// This method deletes the framebuffer immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    /// Next draw calls write into this framebuffer.
+    pub fn bind(&self, context: &B) {
+        context.bind_framebuffer(crate::AppContext::FRAMEBUFFER, Some(&self.framebuffer));
+    }
+
+    /// This method binds the framebuffer as source of blit_framebuffer and read operations.
+    pub fn bind_read(&self, context: &B) {
+        context.bind_framebuffer(crate::AppContext::READ_FRAMEBUFFER, Some(&self.framebuffer));
+    }
+
+    /// This method binds the framebuffer as destination of draw calls and blit_framebuffer.
+    pub fn bind_draw(&self, context: &B) {
+        context.bind_framebuffer(crate::AppContext::DRAW_FRAMEBUFFER, Some(&self.framebuffer));
+    }
+
+    /// This method binds the default framebuffer (canvas).
+    pub fn unbind(&self, context: &B) {
+        context.bind_framebuffer(crate::AppContext::FRAMEBUFFER, None);
+    }
+
+    /// This method binds the framebuffer and attaches base level of the texture. Only TEXTURE_2D textures can be attached.
+    pub fn attach_texture(&self, context: &B, attachment: FramebufferAttachment, texture: &Texture2D<B>) -> SWGLResult<()> {
+        if texture.get_texture_type() != crate::AppContext::TEXTURE_2D {
+            return Err(SWGLRuntimeError::new("Framebuffer::UnsupportedTextureType"));
+        }
+
+        self.bind(context);
+        context.framebuffer_texture_2d(
+            crate::AppContext::FRAMEBUFFER,
+            attachment.get_gl_property_value(),
+            crate::AppContext::TEXTURE_2D,
+            texture.get_raw_id().as_ref(),
+            0,
+        );
+        Ok(())
+    }
+
+    /// This method binds the framebuffer and attaches the renderbuffer.
+    pub fn attach_renderbuffer(&self, context: &B, attachment: FramebufferAttachment, renderbuffer: &Renderbuffer<B>) {
+        self.bind(context);
+        context.framebuffer_renderbuffer(
+            crate::AppContext::FRAMEBUFFER,
+            attachment.get_gl_property_value(),
+            crate::AppContext::RENDERBUFFER,
+            Some(renderbuffer.get_raw_id()),
+        );
+    }
+
+    /// This method binds the framebuffer and checks if it can be used for drawing (attachments have renderable
+    /// formats and the same size).
+    pub fn check_status(&self, context: &B) -> SWGLResult<()> {
+        self.bind(context);
+        match context.check_framebuffer_status(crate::AppContext::FRAMEBUFFER) {
+            crate::AppContext::FRAMEBUFFER_COMPLETE => Ok(()),
+            crate::AppContext::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => {
+                Err(SWGLRuntimeError::new("Framebuffer::IncompleteAttachment"))
+            }
+            crate::AppContext::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
+                Err(SWGLRuntimeError::new("Framebuffer::MissingAttachment"))
+            }
+            crate::AppContext::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => {
+                Err(SWGLRuntimeError::new("Framebuffer::IncompleteDimensions"))
+            }
+            crate::AppContext::FRAMEBUFFER_UNSUPPORTED => Err(SWGLRuntimeError::new("Framebuffer::Unsupported")),
+            crate::AppContext::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => {
+                Err(SWGLRuntimeError::new("Framebuffer::IncompleteMultisample"))
+            }
+            status => Err(SWGLRuntimeError::new(&format!("Framebuffer::UnknownStatus: {}", status))),
+        }
+    }
+
+    /// This method returns object that can be used directly inside WebGL low level calls.
+    pub fn get_raw_id(&self) -> &B::Framebuffer {
+        &self.framebuffer
+    }
+}
+
+impl<B: GlBackend> Drop for Framebuffer<B> {
+    fn drop(&mut self) {
+        self.context.delete_framebuffer(Some(&self.framebuffer));
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::renderbuffer::RenderbufferFormat;
+    use super::super::backend::recording_backend::{GlCall, RecordingBackend};
+    use super::super::texture::texture_config::{TextureConfiguration, TextureFilter, TextureFormat, TextureWrap};
+
+    fn texture_configuration() -> TextureConfiguration {
+        TextureConfiguration::new(
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::Nearest,
+            TextureFilter::Nearest,
+            TextureFormat::Rgba8,
+        )
+    }
+
+    #[test]
+    fn attaches_textures_and_renderbuffers() {
+        let context = RecordingBackend::new();
+        let framebuffer = Framebuffer::new(&context).unwrap();
+        let texture = Texture2D::new_empty(&context, 4, 4, texture_configuration()).unwrap();
+        let depth = Renderbuffer::new(&context, RenderbufferFormat::Depth24Stencil8, 4, 4).unwrap();
+        context.clear_calls();
+
+        framebuffer.attach_texture(&context, FramebufferAttachment::Color(1), &texture).unwrap();
+        framebuffer.attach_renderbuffer(&context, RenderbufferFormat::Depth24Stencil8.get_attachment(), &depth);
+        assert_eq!(
+            context.calls(),
+            vec![
+                GlCall::BindFramebuffer { target: crate::AppContext::FRAMEBUFFER, framebuffer: Some(*framebuffer.get_raw_id()) },
+                GlCall::FramebufferTexture2D {
+                    target: crate::AppContext::FRAMEBUFFER,
+                    attachment: crate::AppContext::COLOR_ATTACHMENT0 + 1,
+                    tex_target: crate::AppContext::TEXTURE_2D,
+                    texture: *texture.get_raw_id(),
+                    level: 0,
+                },
+                GlCall::BindFramebuffer { target: crate::AppContext::FRAMEBUFFER, framebuffer: Some(*framebuffer.get_raw_id()) },
+                GlCall::FramebufferRenderbuffer {
+                    target: crate::AppContext::FRAMEBUFFER,
+                    attachment: crate::AppContext::DEPTH_STENCIL_ATTACHMENT,
+                    renderbuffer: Some(*depth.get_raw_id()),
+                },
+            ]
+        );
+        framebuffer.check_status(&context).unwrap();
+        assert!(context.errors().is_empty());
+    }
+
+    #[test]
+    fn rejects_texture_arrays() {
+        let context = RecordingBackend::new();
+        let framebuffer = Framebuffer::new(&context).unwrap();
+        let array = Texture2D::new_texture_array_from_data(&context, 1, 1, 2, &[0u8; 8], texture_configuration()).unwrap();
+        context.clear_calls();
+
+        let error = framebuffer.attach_texture(&context, FramebufferAttachment::Color(0), &array).unwrap_err();
+        assert_eq!(error.message, "Framebuffer::UnsupportedTextureType");
+        assert!(context.calls().is_empty());
+    }
+
+    #[test]
+    fn maps_incomplete_status_to_errors() {
+        let context = RecordingBackend::new();
+        let framebuffer = Framebuffer::new(&context).unwrap();
+        let error = framebuffer.check_status(&context).unwrap_err();
+        assert_eq!(error.message, "Framebuffer::MissingAttachment");
+
+        let statuses = [
+            (crate::AppContext::FRAMEBUFFER_INCOMPLETE_ATTACHMENT, "Framebuffer::IncompleteAttachment"),
+            (crate::AppContext::FRAMEBUFFER_INCOMPLETE_DIMENSIONS, "Framebuffer::IncompleteDimensions"),
+            (crate::AppContext::FRAMEBUFFER_UNSUPPORTED, "Framebuffer::Unsupported"),
+            (crate::AppContext::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE, "Framebuffer::IncompleteMultisample"),
+            (0x1234, "Framebuffer::UnknownStatus: 4660"),
+        ];
+        for (status, message) in statuses.iter() {
+            context.set_framebuffer_status(Some(*status));
+            assert_eq!(framebuffer.check_status(&context).unwrap_err().message, *message);
+        }
+    }
+
+    #[test]
+    fn drop_deletes_framebuffer_and_renderbuffer() {
+        let context = RecordingBackend::new();
+        let framebuffer = Framebuffer::new(&context).unwrap();
+        let renderbuffer = Renderbuffer::new(&context, RenderbufferFormat::Depth16, 4, 4).unwrap();
+        let ids = (*framebuffer.get_raw_id(), *renderbuffer.get_raw_id());
+        assert_eq!(context.live_object_count(), 2);
+
+        framebuffer.destroy();
+        drop(renderbuffer);
+        assert_eq!(context.live_object_count(), 0);
+        assert!(context.calls().contains(&GlCall::DeleteFramebuffer { framebuffer: Some(ids.0) }));
+        assert!(context.calls().contains(&GlCall::DeleteRenderbuffer { renderbuffer: Some(ids.1) }));
+    }
+}
@@ -0,0 +1,229 @@
+//! Multisampled (MSAA) render target, its content has to be resolved into RenderTarget before it can be sampled.
+
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::super::backend::interface::GlBackend;
+use super::super::texture::texture_config::TextureFormat;
+use super::render_target::RenderTarget;
+use super::renderbuffer::{Renderbuffer, RenderbufferFormat};
+use super::{Framebuffer, FramebufferAttachment};
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Framebuffer with multisampled color and optional depth/stencil renderbuffers. It smooths edges of drawn
+/// geometry (e.g. rotated sprites), which is not possible with texture attachments of RenderTarget.
+///
+/// ```text
+/// let msaa = MultisampleRenderTarget::new(&context, 512, 512, 4, TextureFormat::Rgba8, None)?;
+/// let target = RenderTarget::new(&context, 512, 512, None)?;
+/// msaa.bind(&context);
+/// renderer.flush(&context, &camera, None)?;
+/// msaa.resolve_into(&context, &target)?;
+/// // target.get_texture() contains antialiased image
+/// ```
+pub struct MultisampleRenderTarget<B: GlBackend = crate::AppContext> {
+    framebuffer: Framebuffer<B>,
+    color: Renderbuffer<B>,
+    color_format: TextureFormat,
+    depth_stencil: Option<Renderbuffer<B>>,
+    width: u32,
+    height: u32,
+}
+
+impl<B: GlBackend> MultisampleRenderTarget<B> {
+    /// This method gets size in pixels. Sample count is clamped to MAX_SAMPLES (see get_samples). Color format has
+    /// to be the same as format of RenderTarget the content is resolved into, float formats require
+    /// EXT_color_buffer_float extension.
+    pub fn new(
+        context: &B,
+        width: u32,
+        height: u32,
+        samples: u32,
+        color_format: TextureFormat,
+        depth_stencil: Option<RenderbufferFormat>,
+    ) -> SWGLResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(SWGLRuntimeError::new("MultisampleRenderTarget::ZeroSize"));
+        }
+        if let Some(FramebufferAttachment::Color(_)) = depth_stencil.map(|format| format.get_attachment()) {
+            return Err(SWGLRuntimeError::new("MultisampleRenderTarget::InvalidDepthStencilFormat"));
+        }
+        if color_format.is_depth() {
+            return Err(SWGLRuntimeError::new("MultisampleRenderTarget::InvalidColorFormat"));
+        }
+        if let Some(extension) = color_format.get_render_extension() {
+            if !context.enable_extension(extension) {
+                return Err(SWGLRuntimeError::new(&format!("MultisampleRenderTarget::MissingExtension: {}", extension)));
+            }
+        }
+
+        let framebuffer = Framebuffer::new(context)?;
+        let color = Renderbuffer::new_multisample(context, RenderbufferFormat::Color(color_format), width, height, samples)?;
+        framebuffer.attach_renderbuffer(context, FramebufferAttachment::Color(0), &color);
+
+        let depth_stencil = match depth_stencil {
+            Some(format) => {
+                let renderbuffer = Renderbuffer::new_multisample(context, format, width, height, color.get_samples())?;
+                framebuffer.attach_renderbuffer(context, format.get_attachment(), &renderbuffer);
+                Some(renderbuffer)
+            }
+            None => None,
+        };
+
+        let status = framebuffer.check_status(context);
+        framebuffer.unbind(context);
+        status?;
+
+        Ok(Self {
+            framebuffer,
+            color,
+            color_format,
+            depth_stencil,
+            width,
+            height,
+        })
+    }
+
+    /// This method deletes the framebuffer and renderbuffers immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    /// This method binds the framebuffer and sets viewport to the whole target.
+    pub fn bind(&self, context: &B) {
+        self.framebuffer.bind(context);
+        context.viewport(0, 0, self.width as i32, self.height as i32);
+    }
+
+    /// This method binds the default framebuffer (canvas). Viewport is not restored.
+    pub fn unbind(&self, context: &B) {
+        self.framebuffer.unbind(context);
+    }
+
+    /// This method copies averaged samples into the texture of the target (with blitFramebuffer). Only color is
+    /// resolved and both targets must have the same size and color format. The default framebuffer is bound
+    /// afterwards.
+    pub fn resolve_into(&self, context: &B, target: &RenderTarget<B>) -> SWGLResult<()> {
+        if target.get_size() != self.get_size() {
+            return Err(SWGLRuntimeError::new("MultisampleRenderTarget::ResolveSizeMismatch"));
+        }
+        if target.get_texture().get_format() != self.color_format {
+            return Err(SWGLRuntimeError::new("MultisampleRenderTarget::ResolveFormatMismatch"));
+        }
+
+        self.framebuffer.bind_read(context);
+        target.get_framebuffer().bind_draw(context);
+        context.blit_framebuffer(
+            0,
+            0,
+            self.width as i32,
+            self.height as i32,
+            0,
+            0,
+            self.width as i32,
+            self.height as i32,
+            crate::AppContext::COLOR_BUFFER_BIT,
+            crate::AppContext::NEAREST,
+        );
+        self.framebuffer.unbind(context);
+        Ok(())
+    }
+
+    pub fn get_color_format(&self) -> TextureFormat {
+        self.color_format
+    }
+
+    /// This method returns number of samples per pixel used by the device.
+    pub fn get_samples(&self) -> u32 {
+        self.color.get_samples()
+    }
+
+    pub fn get_framebuffer(&self) -> &Framebuffer<B> {
+        &self.framebuffer
+    }
+
+    pub fn get_depth_stencil(&self) -> Option<&Renderbuffer<B>> {
+        self.depth_stencil.as_ref()
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::backend::recording_backend::{GlCall, RecordingBackend};
+    use super::super::super::texture::texture_config::{TextureConfiguration, TextureFilter, TextureWrap};
+
+    fn render_target(context: &RecordingBackend, format: TextureFormat) -> RenderTarget<RecordingBackend> {
+        let conf = TextureConfiguration::new(
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::Linear,
+            TextureFilter::Linear,
+            format,
+        );
+        RenderTarget::new_with_configuration(context, 64, 32, conf, None).unwrap()
+    }
+
+    #[test]
+    fn resolve_blits_color_into_target() {
+        let context = RecordingBackend::new();
+        let msaa = MultisampleRenderTarget::new(&context, 64, 32, 16, TextureFormat::Rgba16F, Some(RenderbufferFormat::Depth24Stencil8)).unwrap();
+        let target = render_target(&context, TextureFormat::Rgba16F);
+        assert_eq!(msaa.get_samples(), 4);
+        assert!(context.calls().contains(&GlCall::RenderbufferStorageMultisample {
+            target: crate::AppContext::RENDERBUFFER,
+            samples: 4,
+            internal_format: crate::AppContext::RGBA16F,
+            width: 64,
+            height: 32,
+        }));
+
+        context.clear_calls();
+        msaa.resolve_into(&context, &target).unwrap();
+        let blits: Vec<_> = context.calls().into_iter().filter(|c| matches!(c, GlCall::BlitFramebuffer { .. })).collect();
+        assert_eq!(
+            blits,
+            vec![GlCall::BlitFramebuffer {
+                read_framebuffer: Some(*msaa.get_framebuffer().get_raw_id()),
+                draw_framebuffer: Some(*target.get_framebuffer().get_raw_id()),
+                src: [0, 0, 64, 32],
+                dst: [0, 0, 64, 32],
+                mask: crate::AppContext::COLOR_BUFFER_BIT,
+                filter: crate::AppContext::NEAREST,
+            }]
+        );
+        assert_eq!(
+            context.calls().last(),
+            Some(&GlCall::BindFramebuffer { target: crate::AppContext::FRAMEBUFFER, framebuffer: None })
+        );
+    }
+
+    #[test]
+    fn resolve_rejects_target_of_other_format() {
+        let context = RecordingBackend::new();
+        let msaa = MultisampleRenderTarget::new(&context, 64, 32, 4, TextureFormat::Rgba8, None).unwrap();
+
+        for format in [TextureFormat::Rgba16F, TextureFormat::Srgb8Alpha8].iter() {
+            let target = render_target(&context, *format);
+            context.clear_calls();
+            let error = msaa.resolve_into(&context, &target).unwrap_err();
+            assert_eq!(error.message, "MultisampleRenderTarget::ResolveFormatMismatch");
+            assert!(context.calls().is_empty());
+        }
+    }
+
+    #[test]
+    fn float_color_requires_extension() {
+        let context = RecordingBackend::new();
+        context.set_extension_supported("EXT_color_buffer_float", false);
+
+        let error = MultisampleRenderTarget::new(&context, 64, 32, 4, TextureFormat::Rgba16F, None).err().unwrap();
+        assert_eq!(error.message, "MultisampleRenderTarget::MissingExtension: EXT_color_buffer_float");
+        let error = MultisampleRenderTarget::new(&context, 64, 32, 4, TextureFormat::Depth24Stencil8, None).err().unwrap();
+        assert_eq!(error.message, "MultisampleRenderTarget::InvalidColorFormat");
+        assert_eq!(context.live_object_count(), 0);
+    }
+}
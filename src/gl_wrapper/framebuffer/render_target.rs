@@ -0,0 +1,229 @@
+//! Render-to-texture target (e.g. minimap, post-processing or cached static layer).
+
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::super::backend::interface::GlBackend;
+use super::super::texture::texture_2d::Texture2D;
+use super::super::texture::texture_config::{TextureChannels, TextureConfiguration, TextureFilter, TextureWrap};
+use super::renderbuffer::{Renderbuffer, RenderbufferFormat};
+use super::{Framebuffer, FramebufferAttachment};
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Framebuffer with color texture and optional depth/stencil renderbuffer. Renderers draw into it when it is bound,
+/// then its texture can be used like any other Texture2D.
+/// Texture rows are stored from the bottom, so it is upside down compared to textures loaded from images.
+///
+/// ```text
+/// minimap.bind(&context);
+/// clear(&context, &ClearValues::default());
+/// renderer.flush(&context, &minimap_camera, None)?;
+/// minimap.unbind(&context);
+/// RenderState { viewport: Some(canvas_rect), ..RenderState::default() }.apply(&context);
+/// sprite_renderer.flush(&context, &camera, Some(minimap.get_texture()))?;
+/// ```
+pub struct RenderTarget<B: GlBackend = crate::AppContext> {
+    framebuffer: Framebuffer<B>,
+    texture: Texture2D<B>,
+    depth_stencil: Option<Renderbuffer<B>>,
+    width: u32,
+    height: u32,
+}
+
+impl<B: GlBackend> RenderTarget<B> {
+    /// This method creates target with RGBA texture with linear filtering, size is given in pixels.
+    pub fn new(context: &B, width: u32, height: u32, depth_stencil: Option<RenderbufferFormat>) -> SWGLResult<Self> {
+        let conf = TextureConfiguration::new(
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::Linear,
+            TextureFilter::Linear,
+            TextureChannels::Rgba,
+        );
+        Self::new_with_configuration(context, width, height, conf, depth_stencil)
+    }
+
+    /// If the configuration generates mipmaps, they have to be updated with Texture2D::generate_mipmaps after
+    /// rendering. Format of the configuration has to be a color format, float formats require
+    /// EXT_color_buffer_float extension (e.g. HDR targets).
+    pub fn new_with_configuration(
+        context: &B,
+        width: u32,
+        height: u32,
+        conf: TextureConfiguration,
+        depth_stencil: Option<RenderbufferFormat>,
+    ) -> SWGLResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(SWGLRuntimeError::new("RenderTarget::ZeroSize"));
+        }
+        if let Some(FramebufferAttachment::Color(_)) = depth_stencil.map(|format| format.get_attachment()) {
+            return Err(SWGLRuntimeError::new("RenderTarget::InvalidDepthStencilFormat"));
+        }
+        if conf.format.is_depth() {
+            return Err(SWGLRuntimeError::new("RenderTarget::InvalidColorFormat"));
+        }
+        conf.check_support(context, true)?;
+
+        let framebuffer = Framebuffer::new(context)?;
+        let texture = Texture2D::new_empty(context, width, height, conf)?;
+        framebuffer.attach_texture(context, FramebufferAttachment::Color(0), &texture)?;
+
+        let depth_stencil = match depth_stencil {
+            Some(format) => {
+                let renderbuffer = Renderbuffer::new(context, format, width, height)?;
+                framebuffer.attach_renderbuffer(context, format.get_attachment(), &renderbuffer);
+                Some(renderbuffer)
+            }
+            None => None,
+        };
+
+        let status = framebuffer.check_status(context);
+        framebuffer.unbind(context);
+        status?;
+
+        Ok(Self {
+            framebuffer,
+            texture,
+            depth_stencil,
+            width,
+            height,
+        })
+    }
+
+    /// This method deletes the framebuffer, texture and renderbuffer immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    /// This method binds the framebuffer and sets viewport to the whole target.
+    pub fn bind(&self, context: &B) {
+        self.framebuffer.bind(context);
+        context.viewport(0, 0, self.width as i32, self.height as i32);
+    }
+
+    /// This method binds the default framebuffer (canvas). Viewport is not restored.
+    pub fn unbind(&self, context: &B) {
+        self.framebuffer.unbind(context);
+    }
+
+    /// This method returns texture with the rendered image.
+    pub fn get_texture(&self) -> &Texture2D<B> {
+        &self.texture
+    }
+
+    pub fn get_framebuffer(&self) -> &Framebuffer<B> {
+        &self.framebuffer
+    }
+
+    pub fn get_depth_stencil(&self) -> Option<&Renderbuffer<B>> {
+        self.depth_stencil.as_ref()
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::backend::recording_backend::{GlCall, RecordingBackend};
+    use super::super::super::texture::texture_config::TextureFormat;
+
+    fn error_of(result: SWGLResult<RenderTarget<RecordingBackend>>) -> String {
+        result.err().unwrap().message
+    }
+
+    #[test]
+    fn new_attaches_texture_and_depth_stencil() {
+        let context = RecordingBackend::new();
+        let target = RenderTarget::new(&context, 64, 32, Some(RenderbufferFormat::Depth24Stencil8)).unwrap();
+        let framebuffer = *target.get_framebuffer().get_raw_id();
+        let depth_stencil = target.get_depth_stencil().unwrap();
+
+        assert_eq!(target.get_size(), (64, 32));
+        assert_eq!(depth_stencil.get_size(), (64, 32));
+        assert!(context.calls().contains(&GlCall::FramebufferTexture2D {
+            target: crate::AppContext::FRAMEBUFFER,
+            attachment: crate::AppContext::COLOR_ATTACHMENT0,
+            tex_target: crate::AppContext::TEXTURE_2D,
+            texture: *target.get_texture().get_raw_id(),
+            level: 0,
+        }));
+        assert!(context.calls().contains(&GlCall::FramebufferRenderbuffer {
+            target: crate::AppContext::FRAMEBUFFER,
+            attachment: crate::AppContext::DEPTH_STENCIL_ATTACHMENT,
+            renderbuffer: Some(*depth_stencil.get_raw_id()),
+        }));
+        assert!(context.calls().contains(&GlCall::RenderbufferStorage {
+            target: crate::AppContext::RENDERBUFFER,
+            internal_format: crate::AppContext::DEPTH24_STENCIL8,
+            width: 64,
+            height: 32,
+        }));
+        // canvas is bound after creation
+        assert_eq!(context.bound_framebuffer(crate::AppContext::DRAW_FRAMEBUFFER), None);
+
+        target.bind(&context);
+        assert_eq!(context.bound_framebuffer(crate::AppContext::DRAW_FRAMEBUFFER), Some(framebuffer));
+        assert_eq!(context.calls().last(), Some(&GlCall::Viewport { x: 0, y: 0, width: 64, height: 32 }));
+        target.unbind(&context);
+        assert_eq!(context.bound_framebuffer(crate::AppContext::DRAW_FRAMEBUFFER), None);
+        assert!(context.errors().is_empty());
+    }
+
+    #[test]
+    fn new_rejects_invalid_sizes_and_formats() {
+        let context = RecordingBackend::new();
+        assert_eq!(error_of(RenderTarget::new(&context, 0, 32, None)), "RenderTarget::ZeroSize");
+        assert_eq!(
+            error_of(RenderTarget::new(&context, 64, 32, Some(RenderbufferFormat::Color(TextureFormat::Rgba8)))),
+            "RenderTarget::InvalidDepthStencilFormat"
+        );
+
+        let mut conf = TextureConfiguration::default();
+        conf.format = TextureFormat::DepthComponent32F;
+        assert_eq!(
+            error_of(RenderTarget::new_with_configuration(&context, 64, 32, conf, None)),
+            "RenderTarget::InvalidColorFormat"
+        );
+
+        context.set_extension_supported("EXT_color_buffer_float", false);
+        let mut conf = TextureConfiguration::default();
+        conf.format = TextureFormat::Rgba16F;
+        assert_eq!(
+            error_of(RenderTarget::new_with_configuration(&context, 64, 32, conf, None)),
+            "TextureConfiguration::MissingExtension: EXT_color_buffer_float"
+        );
+        assert_eq!(context.live_object_count(), 0);
+    }
+
+    #[test]
+    fn incomplete_framebuffer_releases_created_objects() {
+        let context = RecordingBackend::new();
+        context.set_framebuffer_status(Some(crate::AppContext::FRAMEBUFFER_INCOMPLETE_DIMENSIONS));
+
+        let error = error_of(RenderTarget::new(&context, 64, 32, Some(RenderbufferFormat::Depth16)));
+        assert_eq!(error, "Framebuffer::IncompleteDimensions");
+        assert_eq!(context.live_object_count(), 0);
+        assert_eq!(context.bound_framebuffer(crate::AppContext::DRAW_FRAMEBUFFER), None);
+    }
+
+    #[test]
+    fn drop_deletes_framebuffer_texture_and_renderbuffer() {
+        let context = RecordingBackend::new();
+        let target = RenderTarget::new(&context, 64, 32, Some(RenderbufferFormat::Depth16)).unwrap();
+        let framebuffer = *target.get_framebuffer().get_raw_id();
+        let texture = target.get_texture().get_raw_id().unwrap();
+        let renderbuffer = *target.get_depth_stencil().unwrap().get_raw_id();
+        assert_eq!(context.live_object_count(), 3);
+
+        drop(target);
+        assert_eq!(context.live_object_count(), 0);
+        for id in [framebuffer, texture, renderbuffer].iter() {
+            assert!(!context.is_live_object(*id));
+        }
+        assert!(context.calls().contains(&GlCall::DeleteFramebuffer { framebuffer: Some(framebuffer) }));
+        assert!(context.calls().contains(&GlCall::DeleteTexture { texture: Some(texture) }));
+        assert!(context.calls().contains(&GlCall::DeleteRenderbuffer { renderbuffer: Some(renderbuffer) }));
+    }
+}
@@ -0,0 +1,138 @@
+//! WebGL Renderbuffer Object abstraction.
+
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::super::backend::interface::GlBackend;
+use super::super::texture::texture_config::TextureFormat;
+use super::FramebufferAttachment;
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderbufferFormat {
+    Depth16,
+    Depth24,
+    Depth32F,
+    Depth24Stencil8,
+    Depth32FStencil8,
+    Stencil8,
+    /// Color image of the texture format (e.g. color of MultisampleRenderTarget), depth formats of TextureFormat
+    /// aren't color formats.
+    Color(TextureFormat),
+}
+
+impl RenderbufferFormat {
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Depth16 => crate::AppContext::DEPTH_COMPONENT16,
+            Self::Depth24 => crate::AppContext::DEPTH_COMPONENT24,
+            Self::Depth32F => crate::AppContext::DEPTH_COMPONENT32F,
+            Self::Depth24Stencil8 => crate::AppContext::DEPTH24_STENCIL8,
+            Self::Depth32FStencil8 => crate::AppContext::DEPTH32F_STENCIL8,
+            Self::Stencil8 => crate::AppContext::STENCIL_INDEX8,
+            Self::Color(format) => format.get_internal_format(),
+        }
+    }
+
+    /// This method returns framebuffer attachment point matching the format.
+    pub fn get_attachment(&self) -> FramebufferAttachment {
+        match self {
+            Self::Depth16 | Self::Depth24 | Self::Depth32F => FramebufferAttachment::Depth,
+            Self::Depth24Stencil8 | Self::Depth32FStencil8 => FramebufferAttachment::DepthStencil,
+            Self::Stencil8 => FramebufferAttachment::Stencil,
+            Self::Color(_) => FramebufferAttachment::Color(0),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Image which can be used only as framebuffer attachment (it can't be sampled, e.g. depth buffer of RenderTarget).
+/// GL renderbuffer is deleted when this object is dropped.
+pub struct Renderbuffer<B: GlBackend = crate::AppContext> {
+    renderbuffer: B::Renderbuffer,
+    format: RenderbufferFormat,
+    width: u32,
+    height: u32,
+    samples: u32,
+    context: B,
+}
+
+impl<B: GlBackend> Renderbuffer<B> {
+    /// This method gets width and height parameters in pixels.
+    pub fn new(context: &B, format: RenderbufferFormat, width: u32, height: u32) -> SWGLResult<Self> {
+        let renderbuffer = Self::create(context, format, width, height, 0)?;
+        context.renderbuffer_storage(
+            crate::AppContext::RENDERBUFFER,
+            format.get_gl_property_value(),
+            width as i32,
+            height as i32,
+        );
+        Ok(renderbuffer)
+    }
+
+    /// This method creates multisampled renderbuffer (see MultisampleRenderTarget). Sample count is clamped to
+    /// MAX_SAMPLES of the device, the used value can be read with get_samples.
+    pub fn new_multisample(context: &B, format: RenderbufferFormat, width: u32, height: u32, samples: u32) -> SWGLResult<Self> {
+        let max_samples = context.get_parameter_i32(crate::AppContext::MAX_SAMPLES).max(0) as u32;
+        let samples = samples.min(max_samples);
+
+        let renderbuffer = Self::create(context, format, width, height, samples)?;
+        context.renderbuffer_storage_multisample(
+            crate::AppContext::RENDERBUFFER,
+            samples as i32,
+            format.get_gl_property_value(),
+            width as i32,
+            height as i32,
+        );
+        Ok(renderbuffer)
+    }
+
+    /// This method creates and binds renderbuffer without storage.
+    fn create(context: &B, format: RenderbufferFormat, width: u32, height: u32, samples: u32) -> SWGLResult<Self> {
+        if let Some(renderbuffer) = context.create_renderbuffer() {
+            let renderbuffer = Self {
+                renderbuffer,
+                format,
+                width,
+                height,
+                samples,
+                context: context.clone(),
+            };
+            renderbuffer.bind(context);
+            return Ok(renderbuffer);
+        }
+
+        Err(SWGLRuntimeError::new("Renderbuffer::CreationError"))
+    }
+
+    /// This method deletes the renderbuffer immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    pub fn bind(&self, context: &B) {
+        context.bind_renderbuffer(crate::AppContext::RENDERBUFFER, Some(&self.renderbuffer));
+    }
+
+    pub fn get_format(&self) -> RenderbufferFormat {
+        self.format
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// This method returns number of samples per pixel (0 for not multisampled renderbuffer).
+    pub fn get_samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// This method returns object that can be used directly inside WebGL low level calls.
+    pub fn get_raw_id(&self) -> &B::Renderbuffer {
+        &self.renderbuffer
+    }
+}
+
+impl<B: GlBackend> Drop for Renderbuffer<B> {
+    fn drop(&mut self) {
+        self.context.delete_renderbuffer(Some(&self.renderbuffer));
+    }
+}
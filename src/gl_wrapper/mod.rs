@@ -4,5 +4,8 @@ pub mod shader;
 pub mod vertex_buffer_object;
 pub mod vertex_array_object;
 pub mod element_buffer_object;
+pub mod uniform_buffer_object;
 pub mod texture;
-pub mod basics;
\ No newline at end of file
+pub mod framebuffer;
+pub mod basics;
+pub mod backend;
\ No newline at end of file
@@ -0,0 +1,329 @@
+//! Structured shader compilation / link errors.
+
+use std::fmt;
+
+use super::super::super::runtime_error::SWGLRuntimeError;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Place in the edited file where a line of the compiled shader code comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineOrigin {
+    /// Line number (starting from 1) in the combined source file.
+    Source(usize),
+    /// Line of #include'd snippet. `include_line` is the line of #include directive in the combined source file.
+    Snippet {
+        name: String,
+        line: usize,
+        include_line: usize,
+    },
+    /// Line added by the preprocessor (e.g. #define).
+    Generated,
+}
+
+impl fmt::Display for LineOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Source(line) => write!(f, "line {}", line),
+            Self::Snippet { name, line, include_line } => {
+                write!(f, "line {} (snippet \"{}\" line {})", include_line, name, line)
+            }
+            Self::Generated => write!(f, "generated line"),
+        }
+    }
+}
+
+/// This type maps lines of split shaders back to the combined source file (see split_vfshader_to_shader_source).
+/// N-th element describes line N + 1 of the shader code.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SourceMap {
+    pub vertex: Vec<LineOrigin>,
+    pub fragment: Vec<LineOrigin>,
+}
+
+impl SourceMap {
+    /// This method returns line origins of given stage (empty for ShaderStage::Link).
+    pub fn get_lines(&self, stage: ShaderStage) -> &[LineOrigin] {
+        match stage {
+            ShaderStage::Vertex => &self.vertex,
+            ShaderStage::Fragment => &self.fragment,
+            ShaderStage::Link => &[],
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+    Link,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+/// Single message parsed from the info log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderDiagnostic {
+    pub severity: DiagnosticSeverity,
+    /// Line number in the compiled shader code (as reported by the driver).
+    pub line: Option<usize>,
+    /// Line remapped to the combined source file (if the source map is available).
+    pub origin: Option<LineOrigin>,
+    pub message: String,
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// private:
+
+/// This function parses lines like "ERROR: 0:12: 'x' : undeclared identifier".
+fn parse_log_line(line: &str) -> Option<ShaderDiagnostic> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+
+    let (severity, rest) = if let Some(rest) = line.strip_prefix("ERROR:") {
+        (DiagnosticSeverity::Error, rest)
+    } else if let Some(rest) = line.strip_prefix("WARNING:") {
+        (DiagnosticSeverity::Warning, rest)
+    } else {
+        return Some(ShaderDiagnostic {
+            severity: DiagnosticSeverity::Error,
+            line: None,
+            origin: None,
+            message: String::from(line),
+        });
+    };
+
+    let mut parts = rest.splitn(3, ':');
+    let location = (parts.next(), parts.next(), parts.next());
+    if let (Some(_), Some(line_number), Some(message)) = location {
+        if let Ok(line_number) = line_number.trim().parse::<usize>() {
+            return Some(ShaderDiagnostic {
+                severity,
+                line: Some(line_number),
+                origin: None,
+                message: String::from(message.trim()),
+            });
+        }
+    }
+
+    Some(ShaderDiagnostic {
+        severity,
+        line: None,
+        origin: None,
+        message: String::from(rest.trim()),
+    })
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Error returned by Program::compile. Converts into SWGLRuntimeError (Program::new) with rendered snippet as message.
+#[derive(Debug, Clone)]
+pub struct ShaderCompileError {
+    pub stage: ShaderStage,
+    pub diagnostics: Vec<ShaderDiagnostic>,
+    /// Raw info log.
+    pub log: String,
+    /// Compiled code of the stage (empty for link errors).
+    pub code: String,
+    /// Origins of the code lines (empty if the source map is not available).
+    pub origins: Vec<LineOrigin>,
+}
+
+impl ShaderCompileError {
+    pub fn new(stage: ShaderStage, log: &str, code: &str, origins: &[LineOrigin]) -> Self {
+        let diagnostics = log
+            .lines()
+            .filter_map(parse_log_line)
+            .map(|mut diagnostic| {
+                diagnostic.origin = diagnostic
+                    .line
+                    .and_then(|line| origins.get(line.checked_sub(1)?))
+                    .cloned();
+                diagnostic
+            })
+            .collect();
+
+        Self {
+            stage,
+            diagnostics,
+            log: String::from(log),
+            code: String::from(code),
+            origins: origins.to_vec(),
+        }
+    }
+
+    /// This method returns error name used in SWGLRuntimeError message.
+    pub fn get_error_name(&self) -> &'static str {
+        match self.stage {
+            ShaderStage::Vertex => "Program::VertexCompilationError",
+            ShaderStage::Fragment => "Program::FragmentCompilationError",
+            ShaderStage::Link => "Program::LinkError",
+        }
+    }
+
+    /// This method renders offending lines with `context_lines` lines around them. Lines are labeled with
+    /// numbers of the combined source file when they are known ("+N" is N-th line of a snippet, "*" is generated line).
+    pub fn render_snippet(&self, context_lines: usize) -> String {
+        let code_lines: Vec<&str> = self.code.lines().collect();
+        let mut result = String::new();
+
+        for diagnostic in &self.diagnostics {
+            let location = match (&diagnostic.origin, diagnostic.line) {
+                (Some(origin), _) => format!("{}", origin),
+                (None, Some(line)) => format!("line {}", line),
+                (None, None) => String::from("unknown line"),
+            };
+            result.push_str(&format!("{}: {}\n", location, diagnostic.message));
+
+            let line = match diagnostic.line {
+                Some(line) if line >= 1 && line <= code_lines.len() => line,
+                _ => continue,
+            };
+
+            let first = line.saturating_sub(context_lines).max(1);
+            let last = (line + context_lines).min(code_lines.len());
+            for current in first..=last {
+                let marker = if current == line { '>' } else { ' ' };
+                let label = match self.origins.get(current - 1) {
+                    Some(LineOrigin::Source(source_line)) => format!("{}", source_line),
+                    Some(LineOrigin::Snippet { line, .. }) => format!("+{}", line),
+                    Some(LineOrigin::Generated) => String::from("*"),
+                    None => format!("{}", current),
+                };
+                result.push_str(&format!("{} {:>5} | {}\n", marker, label, code_lines[current - 1]));
+            }
+        }
+
+        result
+    }
+}
+
+impl fmt::Display for ShaderCompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if self.diagnostics.is_empty() {
+            return write!(f, "{}: {}", self.get_error_name(), self.log);
+        }
+        write!(f, "{}:\n{}", self.get_error_name(), self.render_snippet(2))
+    }
+}
+
+impl std::error::Error for ShaderCompileError {}
+
+impl From<ShaderCompileError> for SWGLRuntimeError {
+    fn from(error: ShaderCompileError) -> Self {
+        SWGLRuntimeError::new(&format!("{}", error))
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::backend::recording_backend::RecordingBackend;
+    use super::super::preprocessor::{ShaderDefine, ShaderPreprocessor};
+    use super::super::Program;
+
+    fn diagnostic(severity: DiagnosticSeverity, line: Option<usize>, message: &str) -> ShaderDiagnostic {
+        ShaderDiagnostic {
+            severity,
+            line,
+            origin: None,
+            message: String::from(message),
+        }
+    }
+
+    #[test]
+    fn parses_log_lines() {
+        assert_eq!(
+            parse_log_line("ERROR: 0:12: 'x' : undeclared identifier"),
+            Some(diagnostic(DiagnosticSeverity::Error, Some(12), "'x' : undeclared identifier"))
+        );
+        assert_eq!(
+            parse_log_line("  WARNING: 0:3: implicit conversion  "),
+            Some(diagnostic(DiagnosticSeverity::Warning, Some(3), "implicit conversion"))
+        );
+        assert_eq!(parse_log_line("   "), None);
+    }
+
+    #[test]
+    fn keeps_malformed_log_lines_as_messages() {
+        assert_eq!(
+            parse_log_line("ERROR: 0:x: bad line number"),
+            Some(diagnostic(DiagnosticSeverity::Error, None, "0:x: bad line number"))
+        );
+        assert_eq!(
+            parse_log_line("ERROR: unexpected end"),
+            Some(diagnostic(DiagnosticSeverity::Error, None, "unexpected end"))
+        );
+        assert_eq!(
+            parse_log_line("Link failed: too many attributes"),
+            Some(diagnostic(DiagnosticSeverity::Error, None, "Link failed: too many attributes"))
+        );
+    }
+
+    #[test]
+    fn maps_lines_to_origins() {
+        let origins = [
+            LineOrigin::Source(2),
+            LineOrigin::Generated,
+            LineOrigin::Snippet { name: String::from("light"), line: 4, include_line: 3 },
+            LineOrigin::Source(4),
+        ];
+        let log = "ERROR: 0:2: 'A' : redefinition\nERROR: 0:3: 'x' : undeclared identifier\nWARNING: 0:9: out of code\n";
+        let code = "#version 300 es\n#define A\nfloat y = x;\nvoid main() {}\n";
+        let error = ShaderCompileError::new(ShaderStage::Fragment, log, code, &origins);
+
+        let mapped: Vec<_> = error.diagnostics.iter().map(|d| d.origin.clone()).collect();
+        assert_eq!(mapped, vec![Some(origins[1].clone()), Some(origins[2].clone()), None]);
+        let expected = [
+            "generated line: 'A' : redefinition",
+            "      2 | #version 300 es",
+            ">     * | #define A",
+            "     +4 | float y = x;",
+            "line 3 (snippet \"light\" line 4): 'x' : undeclared identifier",
+            "      * | #define A",
+            ">    +4 | float y = x;",
+            "      4 | void main() {}",
+            "line 9: out of code",
+        ];
+        assert_eq!(error.render_snippet(1).lines().collect::<Vec<_>>(), expected);
+        assert_eq!(error.get_error_name(), "Program::FragmentCompilationError");
+    }
+
+    #[test]
+    fn reports_lines_as_they_are_without_source_map() {
+        let error = ShaderCompileError::new(ShaderStage::Vertex, "ERROR: 0:1: syntax error\nsomething else", "void main(\n", &[]);
+        assert_eq!(error.diagnostics[0].origin, None);
+        assert_eq!(error.render_snippet(0), "line 1: syntax error\n>     1 | void main(\nunknown line: something else\n");
+    }
+
+    #[test]
+    fn program_errors_point_to_combined_source() {
+        let context = RecordingBackend::new();
+        let mut preprocessor = ShaderPreprocessor::new();
+        preprocessor.register_snippet("checks", "// checks\n#ifdef BROKEN\n#error broken snippet\n#endif");
+        let source = preprocessor
+            .process(
+                "#shader vertex\n#version 300 es\nvoid main() {}\n#shader fragment\n#version 300 es\n#include \"checks\"\nvoid main() {}",
+                &[ShaderDefine::flag("BROKEN")],
+            )
+            .unwrap();
+
+        let error = Program::compile(&context, &source).err().unwrap();
+        assert_eq!(error.stage, ShaderStage::Fragment);
+        assert_eq!(
+            error.diagnostics[0].origin,
+            Some(LineOrigin::Snippet { name: String::from("checks"), line: 3, include_line: 6 })
+        );
+        assert!(SWGLRuntimeError::from(error).message.starts_with("Program::FragmentCompilationError:\nline 6 (snippet"));
+    }
+}
@@ -1,10 +1,20 @@
+use std::collections::HashMap;
+
 use super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
-use web_sys::{WebGlProgram, WebGlShader, WebGlUniformLocation};
+use super::backend::interface::GlBackend;
+
+pub mod uniform_value;
+pub mod reflection;
+pub mod preprocessor;
+pub mod compile_error;
+use uniform_value::UniformValue;
+use reflection::ProgramReflection;
+use compile_error::{LineOrigin, ShaderCompileError, ShaderStage, SourceMap};
 
 // -----------------------------------------------------------------------------------------------------------
 // private:
 
-fn compile_vertex_shader(context: &crate::AppContext, shader: &str) -> SWGLResult<WebGlShader> {
+fn compile_vertex_shader<B: GlBackend>(context: &B, shader: &str) -> SWGLResult<B::Shader> {
     if let Some(shader_id) = context.create_shader(crate::AppContext::VERTEX_SHADER) {
         context.shader_source(&shader_id, shader);
         context.compile_shader(&shader_id);
@@ -14,7 +24,7 @@ fn compile_vertex_shader(context: &crate::AppContext, shader: &str) -> SWGLResul
     Err(SWGLRuntimeError::new("Program::VertexCreationError"))
 }
 
-fn compile_fragment_shader(context: &crate::AppContext, shader: &str) -> SWGLResult<WebGlShader> {
+fn compile_fragment_shader<B: GlBackend>(context: &B, shader: &str) -> SWGLResult<B::Shader> {
     if let Some(shader_id) = context.create_shader(crate::AppContext::FRAGMENT_SHADER) {
         context.shader_source(&shader_id, shader);
         context.compile_shader(&shader_id);
@@ -24,14 +34,14 @@ fn compile_fragment_shader(context: &crate::AppContext, shader: &str) -> SWGLRes
     Err(SWGLRuntimeError::new("Program::FragmentCreationError"))
 }
 
-fn link_program(
-    context: &crate::AppContext,
-    vertex: &WebGlShader,
-    fragment: &WebGlShader,
-) -> SWGLResult<WebGlProgram> {
+fn link_program<B: GlBackend>(
+    context: &B,
+    vertex: &B::Shader,
+    fragment: &B::Shader,
+) -> SWGLResult<B::Program> {
     if let Some(shader_program) = context.create_program() {
-        context.attach_shader(&shader_program, &vertex);
-        context.attach_shader(&shader_program, &fragment);
+        context.attach_shader(&shader_program, vertex);
+        context.attach_shader(&shader_program, fragment);
         context.link_program(&shader_program);
         return Ok(shader_program);
     }
@@ -45,6 +55,18 @@ fn link_program(
 pub struct ShaderSource {
     pub vertex: String,
     pub fragment: String,
+    /// Used for mapping compilation errors back to the combined source file (None means lines are reported as they are).
+    pub source_map: Option<SourceMap>,
+}
+
+impl ShaderSource {
+    pub fn new(vertex: &str, fragment: &str) -> Self {
+        Self {
+            vertex: String::from(vertex),
+            fragment: String::from(fragment),
+            source_map: None,
+        }
+    }
 }
 
 // -----------------------------------------------------------------------------------------------------------
@@ -60,13 +82,25 @@ enum ShaderType {
 // -----------------------------------------------------------------------------------------------------------
 
 /// You can have both vertex and fragment shader in one file, this function can split it. Vertex Shader content must be after: "#shader vertex" line, and similar Fragment Shader need to be below "#shader fragment".
+/// See preprocessor::ShaderPreprocessor if you need #include or #define support.
 pub fn split_vfshader_to_shader_source(source: &str) -> ShaderSource {
+    split_lines_to_shader_source(
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (line, LineOrigin::Source(i + 1))),
+    )
+}
+
+/// This function splits lines with known origins (used also by the preprocessor, after resolving includes).
+pub(crate) fn split_lines_to_shader_source<'a>(lines: impl Iterator<Item = (&'a str, LineOrigin)>) -> ShaderSource {
     let mut stype = ShaderType::Null;
 
     let mut vertex_code = String::from("");
     let mut fragment_code = String::from("");
+    let mut source_map = SourceMap::default();
 
-    for line in source.lines() {
+    for (line, origin) in lines {
         let line = format!("{}{}", line, "\n");
 
         if line.contains("#shader vertex") {
@@ -75,8 +109,14 @@ pub fn split_vfshader_to_shader_source(source: &str) -> ShaderSource {
             stype = ShaderType::Fragment;
         } else {
             match stype {
-                ShaderType::Vertex => vertex_code.push_str(&line),
-                ShaderType::Fragment => fragment_code.push_str(&line),
+                ShaderType::Vertex => {
+                    vertex_code.push_str(&line);
+                    source_map.vertex.push(origin);
+                }
+                ShaderType::Fragment => {
+                    fragment_code.push_str(&line);
+                    source_map.fragment.push(origin);
+                }
                 _ => (),
             };
         }
@@ -85,83 +125,174 @@ pub fn split_vfshader_to_shader_source(source: &str) -> ShaderSource {
     ShaderSource {
         vertex: vertex_code,
         fragment: fragment_code,
+        source_map: Some(source_map),
     }
 }
 
 // -----------------------------------------------------------------------------------------------------------
 // private:
 
-fn create_program(context: &crate::AppContext, source: &ShaderSource) -> SWGLResult<WebGlProgram> {
-    let vertex = compile_vertex_shader(context, &source.vertex)?;
+/// Errors of GL object creation (e.g. lost context) don't have info log, so their message is used instead.
+fn stage_error(stage: ShaderStage, source: &ShaderSource, log: &str) -> ShaderCompileError {
+    let code = match stage {
+        ShaderStage::Vertex => source.vertex.as_str(),
+        ShaderStage::Fragment => source.fragment.as_str(),
+        ShaderStage::Link => "",
+    };
+    let origins = source.source_map.as_ref().map(|map| map.get_lines(stage)).unwrap_or(&[]);
+    ShaderCompileError::new(stage, log, code, origins)
+}
+
+/// Shaders are not needed after linking, so they are detached and deleted (also when an error occurs).
+fn create_program<B: GlBackend>(context: &B, source: &ShaderSource) -> Result<B::Program, ShaderCompileError> {
+    let vertex = compile_vertex_shader(context, &source.vertex)
+        .map_err(|e| stage_error(ShaderStage::Vertex, source, &e.message))?;
 
     let err_msg = context
         .get_shader_info_log(&vertex)
         .unwrap_or("No info".to_string());
     if err_msg.len() != 0 {
-        return Err(SWGLRuntimeError::new(&format!(
-            "Program::VertexCompilationError: {}",
-            err_msg
-        )));
+        context.delete_shader(Some(&vertex));
+        return Err(stage_error(ShaderStage::Vertex, source, &err_msg));
     }
 
     // ----------------------------------------------------------
 
-    let fragment = compile_fragment_shader(context, &source.fragment)?;
+    let fragment = match compile_fragment_shader(context, &source.fragment) {
+        Ok(fragment) => fragment,
+        Err(e) => {
+            context.delete_shader(Some(&vertex));
+            return Err(stage_error(ShaderStage::Fragment, source, &e.message));
+        }
+    };
 
     let err_msg = context
         .get_shader_info_log(&fragment)
         .unwrap_or("No info".to_string());
     if err_msg.len() != 0 {
-        return Err(SWGLRuntimeError::new(&format!(
-            "Program::FragmentCompilationError: {}",
-            err_msg
-        )));
+        context.delete_shader(Some(&vertex));
+        context.delete_shader(Some(&fragment));
+        return Err(stage_error(ShaderStage::Fragment, source, &err_msg));
     }
 
     // ----------------------------------------------------------
 
-    let program = link_program(context, &vertex, &fragment)?;
+    let program = link_program(context, &vertex, &fragment);
+    if let Ok(program) = &program {
+        context.detach_shader(program, &vertex);
+        context.detach_shader(program, &fragment);
+    }
+    context.delete_shader(Some(&vertex));
+    context.delete_shader(Some(&fragment));
+    let program = program.map_err(|e| stage_error(ShaderStage::Link, source, &e.message))?;
 
     let err_msg = context
         .get_program_info_log(&program)
         .unwrap_or("No info".to_string());
     if err_msg.len() != 0 {
-        return Err(SWGLRuntimeError::new(&format!(
-            "Program::LinkError: {}",
-            err_msg
-        )));
+        context.delete_program(Some(&program));
+        return Err(stage_error(ShaderStage::Link, source, &err_msg));
     }
 
     Ok(program)
 }
 
+/// This function resolves locations of all active uniforms. Elements of array uniforms are stored separately ("name[i]"),
+/// the first element is also available under the array name.
+fn resolve_uniform_locations<B: GlBackend>(context: &B, program: &B::Program) -> HashMap<String, B::UniformLocation> {
+    let mut locations = HashMap::new();
+    let count = context.get_program_parameter(program, crate::AppContext::ACTIVE_UNIFORMS);
+
+    for index in 0..count.max(0) as u32 {
+        let info = match context.get_active_uniform(program, index) {
+            Some(info) => info,
+            None => continue,
+        };
+
+        let base_name = info.name.trim_end_matches("[0]");
+        let mut names = vec![String::from(base_name)];
+        if info.name.ends_with("[0]") {
+            names.extend((0..info.size).map(|i| format!("{}[{}]", base_name, i)));
+        }
+
+        for name in names {
+            if let Some(location) = context.get_uniform_location(program, &name) {
+                locations.insert(name, location);
+            }
+        }
+    }
+
+    locations
+}
+
 // -----------------------------------------------------------------------------------------------------------
 
-pub struct Program {
-    program: WebGlProgram,
+/// GL program is deleted when this object is dropped.
+pub struct Program<B: GlBackend = crate::AppContext> {
+    program: B::Program,
+    uniform_locations: HashMap<String, B::UniformLocation>,
+    context: B,
 }
 
-impl Program {
-    pub fn new(context: &crate::AppContext, source: &ShaderSource) -> SWGLResult<Self> {
+impl<B: GlBackend> Program<B> {
+    /// Compilation errors are converted into SWGLRuntimeError with rendered snippet of offending lines (see Program::compile).
+    pub fn new(context: &B, source: &ShaderSource) -> SWGLResult<Self> {
+        Ok(Self::compile(context, source)?)
+    }
+
+    /// This method works like Program::new, but returns structured error with parsed diagnostics.
+    pub fn compile(context: &B, source: &ShaderSource) -> Result<Self, ShaderCompileError> {
         let program = create_program(context, source)?;
-        Ok(Program { program })
+        let uniform_locations = resolve_uniform_locations(context, &program);
+        Ok(Program {
+            program,
+            uniform_locations,
+            context: context.clone(),
+        })
     }
 
+    /// This method deletes the program immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
     /// This method returns object that can be used directly inside WebGL low level calls.
-    pub fn get_program(&self) -> &WebGlProgram {
+    pub fn get_program(&self) -> &B::Program {
         &self.program
     }
 
-    pub fn use_program(&self, context: &crate::AppContext) {
+    pub fn use_program(&self, context: &B) {
         context.use_program(Some(&self.program));
     }
 
-    pub fn unuse_program(&self, context: &crate::AppContext) {
+    pub fn unuse_program(&self, context: &B) {
         context.use_program(None);
     }
 
-    fn get_uniform_location(&self, context: &crate::AppContext, name: &str) -> SWGLResult<WebGlUniformLocation> {
-        if let Some(location) = context.get_uniform_location(&self.program, name) {
+    /// This method queries active uniforms and attributes of the program (with their types and locations).
+    pub fn reflect(&self, context: &B) -> ProgramReflection {
+        ProgramReflection::create(context, &self.program)
+    }
+
+    /// This method checks if the uniform is active (it was not removed by the shader compiler).
+    pub fn has_uniform(&self, name: &str) -> bool {
+        self.uniform_locations.contains_key(name)
+    }
+
+    /// This method connects uniform block of the program with binding point used by UniformBufferObject::bind_base.
+    pub fn bind_uniform_block(&self, context: &B, name: &str, binding: u32) -> SWGLResult<()> {
+        let block_index = context.get_uniform_block_index(&self.program, name);
+        if block_index == crate::AppContext::INVALID_INDEX {
+            return Err(SWGLRuntimeError::new(&format!(
+                "Program::CannotGetUniformBlockIndex: {}",
+                name
+            )));
+        }
+        context.uniform_block_binding(&self.program, block_index, binding);
+        Ok(())
+    }
+
+    /// Uniform locations are resolved once, when the program is created.
+    fn get_uniform_location(&self, name: &str) -> SWGLResult<&B::UniformLocation> {
+        if let Some(location) = self.uniform_locations.get(name) {
             return Ok(location);
         }
         Err(SWGLRuntimeError::new(&format!(
@@ -172,29 +303,36 @@ impl Program {
 
     // bind uniform params
 
+    /// This method uploads any value implementing UniformValue (scalars, vectors, matrices, colors and slices of them).
+    pub fn set_uniform(&self, context: &B, name: &str, value: impl UniformValue) -> SWGLResult<()> {
+        self.use_program(context);
+        value.upload(context, self.get_uniform_location(name)?);
+        Ok(())
+    }
+
     // setnf
 
-    pub fn set1f(&self, context: &crate::AppContext, name: &str, v1: f32) -> SWGLResult<()> {
+    pub fn set1f(&self, context: &B, name: &str, v1: f32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform1f(Some(&self.get_uniform_location(context, name)?), v1);
+        context.uniform1f(Some(self.get_uniform_location(name)?), v1);
         Ok(())
     }
 
-    pub fn set2f(&self, context: &crate::AppContext, name: &str, v1: f32, v2: f32) -> SWGLResult<()> {
+    pub fn set2f(&self, context: &B, name: &str, v1: f32, v2: f32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform2f(Some(&self.get_uniform_location(context, name)?), v1, v2);
+        context.uniform2f(Some(self.get_uniform_location(name)?), v1, v2);
         Ok(())
     }
 
-    pub fn set3f(&self, context: &crate::AppContext, name: &str, v1: f32, v2: f32, v3: f32) -> SWGLResult<()> {
+    pub fn set3f(&self, context: &B, name: &str, v1: f32, v2: f32, v3: f32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform3f(Some(&self.get_uniform_location(context, name)?), v1, v2, v3);
+        context.uniform3f(Some(self.get_uniform_location(name)?), v1, v2, v3);
         Ok(())
     }
 
     pub fn set4f(
         &self,
-        context: &crate::AppContext,
+        context: &B,
         name: &str,
         v1: f32,
         v2: f32,
@@ -203,7 +341,7 @@ impl Program {
     ) -> SWGLResult<()> {
         self.use_program(context);
         context.uniform4f(
-            Some(&self.get_uniform_location(context, name)?),
+            Some(self.get_uniform_location(name)?),
             v1,
             v2,
             v3,
@@ -214,27 +352,27 @@ impl Program {
 
     // setni
 
-    pub fn set1i(&self, context: &crate::AppContext, name: &str, v1: i32) -> SWGLResult<()> {
+    pub fn set1i(&self, context: &B, name: &str, v1: i32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform1i(Some(&self.get_uniform_location(context, name)?), v1);
+        context.uniform1i(Some(self.get_uniform_location(name)?), v1);
         Ok(())
     }
 
-    pub fn set2i(&self, context: &crate::AppContext, name: &str, v1: i32, v2: i32) -> SWGLResult<()> {
+    pub fn set2i(&self, context: &B, name: &str, v1: i32, v2: i32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform2i(Some(&self.get_uniform_location(context, name)?), v1, v2);
+        context.uniform2i(Some(self.get_uniform_location(name)?), v1, v2);
         Ok(())
     }
 
-    pub fn set3i(&self, context: &crate::AppContext, name: &str, v1: i32, v2: i32, v3: i32) -> SWGLResult<()> {
+    pub fn set3i(&self, context: &B, name: &str, v1: i32, v2: i32, v3: i32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform3i(Some(&self.get_uniform_location(context, name)?), v1, v2, v3);
+        context.uniform3i(Some(self.get_uniform_location(name)?), v1, v2, v3);
         Ok(())
     }
 
     pub fn set4i(
         &self,
-        context: &crate::AppContext,
+        context: &B,
         name: &str,
         v1: i32,
         v2: i32,
@@ -243,7 +381,7 @@ impl Program {
     ) -> SWGLResult<()> {
         self.use_program(context);
         context.uniform4i(
-            Some(&self.get_uniform_location(context, name)?),
+            Some(self.get_uniform_location(name)?),
             v1,
             v2,
             v3,
@@ -254,27 +392,27 @@ impl Program {
 
     // setnu
 
-    pub fn set1u(&self, context: &crate::AppContext, name: &str, v1: u32) -> SWGLResult<()> {
+    pub fn set1u(&self, context: &B, name: &str, v1: u32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform1ui(Some(&self.get_uniform_location(context, name)?), v1);
+        context.uniform1ui(Some(self.get_uniform_location(name)?), v1);
         Ok(())
     }
 
-    pub fn set2u(&self, context: &crate::AppContext, name: &str, v1: u32, v2: u32) -> SWGLResult<()> {
+    pub fn set2u(&self, context: &B, name: &str, v1: u32, v2: u32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform2ui(Some(&self.get_uniform_location(context, name)?), v1, v2);
+        context.uniform2ui(Some(self.get_uniform_location(name)?), v1, v2);
         Ok(())
     }
 
-    pub fn set3u(&self, context: &crate::AppContext, name: &str, v1: u32, v2: u32, v3: u32) -> SWGLResult<()> {
+    pub fn set3u(&self, context: &B, name: &str, v1: u32, v2: u32, v3: u32) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform3ui(Some(&self.get_uniform_location(context, name)?), v1, v2, v3);
+        context.uniform3ui(Some(self.get_uniform_location(name)?), v1, v2, v3);
         Ok(())
     }
 
     pub fn set4u(
         &self,
-        context: &crate::AppContext,
+        context: &B,
         name: &str,
         v1: u32,
         v2: u32,
@@ -283,7 +421,7 @@ impl Program {
     ) -> SWGLResult<()> {
         self.use_program(context);
         context.uniform4ui(
-            Some(&self.get_uniform_location(context, name)?),
+            Some(self.get_uniform_location(name)?),
             v1,
             v2,
             v3,
@@ -294,15 +432,15 @@ impl Program {
 
     // setmat
 
-    pub fn set_mat_4x4f(&self, context: &crate::AppContext, name: &str, v: &[f32]) -> SWGLResult<()> {
+    pub fn set_mat_4x4f(&self, context: &B, name: &str, v: &[f32]) -> SWGLResult<()> {
         self.use_program(context);
-        context.uniform_matrix4fv_with_f32_array_and_src_offset_and_src_length(
-            Some(&self.get_uniform_location(context, name)?),
-            false,
-            v,
-            0,
-            v.len() as u32,
-        );
+        context.uniform_matrix4fv(Some(self.get_uniform_location(name)?), false, v);
         Ok(())
     }
 }
+
+impl<B: GlBackend> Drop for Program<B> {
+    fn drop(&mut self) {
+        self.context.delete_program(Some(&self.program));
+    }
+}
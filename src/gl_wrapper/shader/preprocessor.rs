@@ -0,0 +1,351 @@
+//! Simple GLSL preprocessor (#include of named snippets, #define injection and cached program variants).
+
+use std::collections::HashMap;
+
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::super::backend::interface::GlBackend;
+use super::compile_error::LineOrigin;
+use super::{split_lines_to_shader_source, Program, ShaderSource};
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Single "#define name value" line, value can be empty.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShaderDefine {
+    pub name: String,
+    pub value: String,
+}
+
+impl ShaderDefine {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: String::from(name),
+            value: String::from(value),
+        }
+    }
+
+    pub fn flag(name: &str) -> Self {
+        Self::new(name, "")
+    }
+
+    fn to_glsl(&self) -> String {
+        if self.value.is_empty() {
+            format!("#define {}\n", self.name)
+        } else {
+            format!("#define {} {}\n", self.name, self.value)
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// private:
+
+/// This function returns included snippet name if the line is: #include "name".
+fn parse_include(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix('#')?.trim_start().strip_prefix("include")?.trim();
+    rest.strip_prefix('"')?.strip_suffix('"')
+}
+
+/// "#version" directive must be the first line of GLSL code, so defines are placed just after it.
+fn is_version_line(line: &str) -> bool {
+    line.trim()
+        .strip_prefix('#')
+        .map(|rest| rest.trim_start().starts_with("version"))
+        .unwrap_or(false)
+}
+
+/// This function also inserts Generated entries into line origins of the code.
+fn inject_defines(code: &str, origins: &mut Vec<LineOrigin>, defines: &[ShaderDefine]) -> String {
+    if defines.is_empty() {
+        return String::from(code);
+    }
+
+    let defines_code: String = defines.iter().map(|d| d.to_glsl()).collect();
+    let generated = std::iter::repeat(LineOrigin::Generated).take(defines.len());
+
+    let mut result = String::with_capacity(code.len() + defines_code.len());
+    let mut injected = false;
+    for (i, line) in code.lines().enumerate() {
+        result.push_str(line);
+        result.push('\n');
+        if !injected && is_version_line(line) {
+            result.push_str(&defines_code);
+            origins.splice(i + 1..i + 1, generated.clone());
+            injected = true;
+        }
+    }
+
+    if !injected {
+        result.insert_str(0, &defines_code);
+        origins.splice(0..0, generated);
+    }
+    result
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This type keeps named GLSL snippets which can be used in shaders with: #include "name".
+/// Snippets can include other snippets.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderPreprocessor {
+    snippets: HashMap<String, String>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This method adds (or replaces) snippet of given name.
+    pub fn register_snippet(&mut self, name: &str, code: &str) {
+        self.snippets.insert(String::from(name), String::from(code));
+    }
+
+    pub fn has_snippet(&self, name: &str) -> bool {
+        self.snippets.contains_key(name)
+    }
+
+    /// This method replaces every #include line with the snippet code.
+    pub fn resolve_includes(&self, source: &str) -> SWGLResult<String> {
+        let mut result = String::with_capacity(source.len());
+        for (line, _) in self.resolve_include_lines(source)? {
+            result.push_str(line);
+            result.push('\n');
+        }
+        Ok(result)
+    }
+
+    /// This method returns lines with resolved includes together with their origins.
+    fn resolve_include_lines<'a>(&'a self, source: &'a str) -> SWGLResult<Vec<(&'a str, LineOrigin)>> {
+        let mut result = vec![];
+        for (i, line) in source.lines().enumerate() {
+            match parse_include(line) {
+                Some(name) => self.resolve_snippet_into(name, i + 1, &mut vec![], &mut result)?,
+                None => result.push((line, LineOrigin::Source(i + 1))),
+            }
+        }
+        Ok(result)
+    }
+
+    fn resolve_snippet_into<'a>(
+        &'a self,
+        name: &str,
+        include_line: usize,
+        stack: &mut Vec<&'a str>,
+        result: &mut Vec<(&'a str, LineOrigin)>,
+    ) -> SWGLResult<()> {
+        let (name, code) = match self.snippets.get_key_value(name) {
+            Some((name, code)) => (name.as_str(), code),
+            None => {
+                return Err(SWGLRuntimeError::new(&format!(
+                    "ShaderPreprocessor::UnknownSnippet: {}",
+                    name
+                )))
+            }
+        };
+
+        if stack.contains(&name) {
+            return Err(SWGLRuntimeError::new(&format!(
+                "ShaderPreprocessor::IncludeCycle: {}",
+                name
+            )));
+        }
+
+        stack.push(name);
+        for (i, line) in code.lines().enumerate() {
+            match parse_include(line) {
+                Some(nested) => self.resolve_snippet_into(nested, include_line, stack, result)?,
+                None => result.push((
+                    line,
+                    LineOrigin::Snippet {
+                        name: String::from(name),
+                        line: i + 1,
+                        include_line,
+                    },
+                )),
+            }
+        }
+        stack.pop();
+        Ok(())
+    }
+
+    /// This method resolves includes, splits the source (see split_vfshader_to_shader_source) and inserts given defines
+    /// into both shaders (after "#version" line).
+    /// Returned source contains map of lines back to the original source (used by compilation errors).
+    pub fn process(&self, source: &str, defines: &[ShaderDefine]) -> SWGLResult<ShaderSource> {
+        let source = split_lines_to_shader_source(self.resolve_include_lines(source)?.into_iter());
+        let mut source_map = source.source_map.unwrap_or_default();
+        Ok(ShaderSource {
+            vertex: inject_defines(&source.vertex, &mut source_map.vertex, defines),
+            fragment: inject_defines(&source.fragment, &mut source_map.fragment, defines),
+            source_map: Some(source_map),
+        })
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This type builds permutations of one shader source with different defines. Every permutation is compiled once
+/// and cached as a separate Program.
+pub struct ShaderVariants<B: GlBackend = crate::AppContext> {
+    source: String,
+    preprocessor: ShaderPreprocessor,
+    programs: HashMap<Vec<ShaderDefine>, Program<B>>,
+}
+
+impl<B: GlBackend> ShaderVariants<B> {
+    /// Source can contain both shaders (with "#shader vertex" and "#shader fragment" lines), but no #include lines
+    /// (see new_with_preprocessor).
+    pub fn new(source: &str) -> Self {
+        Self::new_with_preprocessor(source, ShaderPreprocessor::new())
+    }
+
+    /// This method creates variants whose #include lines are resolved with snippets of the preprocessor. Snippets
+    /// are copied, so snippets registered later don't change already created variants.
+    pub fn new_with_preprocessor(source: &str, preprocessor: ShaderPreprocessor) -> Self {
+        Self {
+            source: String::from(source),
+            preprocessor,
+            programs: HashMap::new(),
+        }
+    }
+
+    /// This method returns program compiled with given defines (order of defines doesn't matter).
+    pub fn get(&mut self, context: &B, defines: &[ShaderDefine]) -> SWGLResult<&Program<B>> {
+        let mut key = defines.to_vec();
+        key.sort();
+
+        if !self.programs.contains_key(&key) {
+            let program = Program::new(context, &self.preprocessor.process(&self.source, &key)?)?;
+            self.programs.insert(key.clone(), program);
+        }
+
+        Ok(&self.programs[&key])
+    }
+
+    /// This method returns already compiled program without compiling a new one.
+    pub fn get_cached(&self, defines: &[ShaderDefine]) -> Option<&Program<B>> {
+        let mut key = defines.to_vec();
+        key.sort();
+        self.programs.get(&key)
+    }
+
+    pub fn get_variant_count(&self) -> usize {
+        self.programs.len()
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::backend::recording_backend::{GlCall, RecordingBackend};
+
+    const SOURCE: &str = "#shader vertex\n#version 300 es\n#include \"light\"\nvoid main() {}\n#shader fragment\n#version 300 es\nvoid main() {}";
+
+    fn preprocessor() -> ShaderPreprocessor {
+        let mut preprocessor = ShaderPreprocessor::new();
+        preprocessor.register_snippet("math", "float square(float x) { return x * x; }");
+        preprocessor.register_snippet("light", "#include \"math\"\n  #  include \"math\"  \nuniform vec3 u_light;");
+        preprocessor
+    }
+
+    fn snippet(name: &str, line: usize, include_line: usize) -> LineOrigin {
+        LineOrigin::Snippet { name: String::from(name), line, include_line }
+    }
+
+    #[test]
+    fn resolves_nested_includes() {
+        let code = preprocessor().resolve_includes("#version 300 es\n#include \"light\"\nvoid main() {}").unwrap();
+        assert_eq!(
+            code,
+            "#version 300 es\nfloat square(float x) { return x * x; }\nfloat square(float x) { return x * x; }\n\
+             uniform vec3 u_light;\nvoid main() {}\n"
+        );
+
+        let source = preprocessor().process(SOURCE, &[]).unwrap();
+        assert_eq!(
+            source.source_map.unwrap().vertex,
+            vec![
+                LineOrigin::Source(2),
+                snippet("math", 1, 3),
+                snippet("math", 1, 3),
+                snippet("light", 3, 3),
+                LineOrigin::Source(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_snippets_and_include_cycles() {
+        let mut preprocessor = preprocessor();
+        let error = preprocessor.resolve_includes("#include \"shadow\"").unwrap_err();
+        assert_eq!(error.message, "ShaderPreprocessor::UnknownSnippet: shadow");
+
+        preprocessor.register_snippet("math", "#include \"light\"");
+        let error = preprocessor.resolve_includes("#include \"light\"").unwrap_err();
+        assert_eq!(error.message, "ShaderPreprocessor::IncludeCycle: light");
+
+        preprocessor.register_snippet("self", "#include \"self\"");
+        let error = preprocessor.resolve_includes("#include \"self\"").unwrap_err();
+        assert_eq!(error.message, "ShaderPreprocessor::IncludeCycle: self");
+    }
+
+    #[test]
+    fn injects_defines_after_version_line() {
+        let defines = [ShaderDefine::flag("SHADOWS"), ShaderDefine::new("LIGHT_COUNT", "4")];
+        let source = preprocessor().process(SOURCE, &defines).unwrap();
+
+        assert!(source.vertex.starts_with("#version 300 es\n#define SHADOWS\n#define LIGHT_COUNT 4\nfloat square"));
+        assert_eq!(source.fragment, "#version 300 es\n#define SHADOWS\n#define LIGHT_COUNT 4\nvoid main() {}\n");
+        let source_map = source.source_map.unwrap();
+        assert_eq!(source_map.vertex.len(), source.vertex.lines().count());
+        assert_eq!(
+            source_map.fragment,
+            vec![LineOrigin::Source(6), LineOrigin::Generated, LineOrigin::Generated, LineOrigin::Source(7)]
+        );
+    }
+
+    #[test]
+    fn injects_defines_at_start_without_version_line() {
+        let source = ShaderPreprocessor::new()
+            .process("#shader vertex\nvoid main() {}\n#shader fragment\nvoid main() {}", &[ShaderDefine::flag("A")])
+            .unwrap();
+        assert_eq!(source.vertex, "#define A\nvoid main() {}\n");
+        assert_eq!(source.source_map.unwrap().vertex, vec![LineOrigin::Generated, LineOrigin::Source(2)]);
+    }
+
+    #[test]
+    fn variants_compile_every_define_set_once() {
+        let context = RecordingBackend::new();
+        let mut variants = ShaderVariants::new_with_preprocessor(SOURCE, preprocessor());
+        let a = ShaderDefine::flag("A");
+        let b = ShaderDefine::new("B", "2");
+
+        let first = *variants.get(&context, &[a.clone(), b.clone()]).unwrap().get_program();
+        let second = *variants.get(&context, &[b.clone(), a.clone()]).unwrap().get_program();
+        assert_eq!(first, second);
+        assert!(variants.get_cached(std::slice::from_ref(&a)).is_none());
+        variants.get(&context, std::slice::from_ref(&a)).unwrap();
+        variants.get(&context, &[]).unwrap();
+        assert_eq!(variants.get_variant_count(), 3);
+
+        let created = context.calls().iter().filter(|c| matches!(c, GlCall::CreateProgram { .. })).count();
+        assert_eq!(created, 3);
+        // includes are resolved with snippets given at construction
+        assert!(context.calls().iter().any(|c| matches!(
+            c,
+            GlCall::ShaderSource { source, .. } if source.contains("uniform vec3 u_light;") && source.contains("#define B 2")
+        )));
+    }
+
+    #[test]
+    fn variants_without_preprocessor_reject_includes() {
+        let context = RecordingBackend::new();
+        let mut variants: ShaderVariants<RecordingBackend> = ShaderVariants::new(SOURCE);
+        let error = variants.get(&context, &[]).err().unwrap();
+        assert_eq!(error.message, "ShaderPreprocessor::UnknownSnippet: light");
+        assert_eq!(variants.get_variant_count(), 0);
+    }
+}
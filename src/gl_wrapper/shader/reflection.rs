@@ -0,0 +1,309 @@
+//! Description of active program inputs (uniforms and vertex attributes).
+
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::super::backend::interface::GlBackend;
+use super::super::vertex_array_object::VertexBufferAttribute;
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShaderDataType {
+    Float,
+    FloatVec2,
+    FloatVec3,
+    FloatVec4,
+    Int,
+    IntVec2,
+    IntVec3,
+    IntVec4,
+    UnsignedInt,
+    UnsignedIntVec2,
+    UnsignedIntVec3,
+    UnsignedIntVec4,
+    Bool,
+    FloatMat2,
+    FloatMat3,
+    FloatMat4,
+    Sampler2D,
+    Sampler3D,
+    SamplerCube,
+    Sampler2DArray,
+    /// Any other GL type (e.g. non-square matrices or integer samplers).
+    Other(u32),
+}
+
+impl ShaderDataType {
+    pub fn from_gl_property_value(value: u32) -> Self {
+        match value {
+            crate::AppContext::FLOAT => Self::Float,
+            crate::AppContext::FLOAT_VEC2 => Self::FloatVec2,
+            crate::AppContext::FLOAT_VEC3 => Self::FloatVec3,
+            crate::AppContext::FLOAT_VEC4 => Self::FloatVec4,
+            crate::AppContext::INT => Self::Int,
+            crate::AppContext::INT_VEC2 => Self::IntVec2,
+            crate::AppContext::INT_VEC3 => Self::IntVec3,
+            crate::AppContext::INT_VEC4 => Self::IntVec4,
+            crate::AppContext::UNSIGNED_INT => Self::UnsignedInt,
+            crate::AppContext::UNSIGNED_INT_VEC2 => Self::UnsignedIntVec2,
+            crate::AppContext::UNSIGNED_INT_VEC3 => Self::UnsignedIntVec3,
+            crate::AppContext::UNSIGNED_INT_VEC4 => Self::UnsignedIntVec4,
+            crate::AppContext::BOOL => Self::Bool,
+            crate::AppContext::FLOAT_MAT2 => Self::FloatMat2,
+            crate::AppContext::FLOAT_MAT3 => Self::FloatMat3,
+            crate::AppContext::FLOAT_MAT4 => Self::FloatMat4,
+            crate::AppContext::SAMPLER_2D => Self::Sampler2D,
+            crate::AppContext::SAMPLER_3D => Self::Sampler3D,
+            crate::AppContext::SAMPLER_CUBE => Self::SamplerCube,
+            crate::AppContext::SAMPLER_2D_ARRAY => Self::Sampler2DArray,
+            other => Self::Other(other),
+        }
+    }
+
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Float => crate::AppContext::FLOAT,
+            Self::FloatVec2 => crate::AppContext::FLOAT_VEC2,
+            Self::FloatVec3 => crate::AppContext::FLOAT_VEC3,
+            Self::FloatVec4 => crate::AppContext::FLOAT_VEC4,
+            Self::Int => crate::AppContext::INT,
+            Self::IntVec2 => crate::AppContext::INT_VEC2,
+            Self::IntVec3 => crate::AppContext::INT_VEC3,
+            Self::IntVec4 => crate::AppContext::INT_VEC4,
+            Self::UnsignedInt => crate::AppContext::UNSIGNED_INT,
+            Self::UnsignedIntVec2 => crate::AppContext::UNSIGNED_INT_VEC2,
+            Self::UnsignedIntVec3 => crate::AppContext::UNSIGNED_INT_VEC3,
+            Self::UnsignedIntVec4 => crate::AppContext::UNSIGNED_INT_VEC4,
+            Self::Bool => crate::AppContext::BOOL,
+            Self::FloatMat2 => crate::AppContext::FLOAT_MAT2,
+            Self::FloatMat3 => crate::AppContext::FLOAT_MAT3,
+            Self::FloatMat4 => crate::AppContext::FLOAT_MAT4,
+            Self::Sampler2D => crate::AppContext::SAMPLER_2D,
+            Self::Sampler3D => crate::AppContext::SAMPLER_3D,
+            Self::SamplerCube => crate::AppContext::SAMPLER_CUBE,
+            Self::Sampler2DArray => crate::AppContext::SAMPLER_2D_ARRAY,
+            Self::Other(value) => *value,
+        }
+    }
+
+    /// This method returns number of components in a single column (e.g. 3 for vec3 and mat3).
+    pub fn get_component_count(&self) -> Option<u32> {
+        match self {
+            Self::Float | Self::Int | Self::UnsignedInt | Self::Bool => Some(1),
+            Self::FloatVec2 | Self::IntVec2 | Self::UnsignedIntVec2 | Self::FloatMat2 => Some(2),
+            Self::FloatVec3 | Self::IntVec3 | Self::UnsignedIntVec3 | Self::FloatMat3 => Some(3),
+            Self::FloatVec4 | Self::IntVec4 | Self::UnsignedIntVec4 | Self::FloatMat4 => Some(4),
+            _ => None,
+        }
+    }
+
+    /// This method returns number of attribute locations used by this type (matrices use one per column).
+    pub fn get_location_count(&self) -> u32 {
+        match self {
+            Self::FloatMat2 => 2,
+            Self::FloatMat3 => 3,
+            Self::FloatMat4 => 4,
+            _ => 1,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Self::Int
+                | Self::IntVec2
+                | Self::IntVec3
+                | Self::IntVec4
+                | Self::UnsignedInt
+                | Self::UnsignedIntVec2
+                | Self::UnsignedIntVec3
+                | Self::UnsignedIntVec4
+        )
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformDescription {
+    /// Array uniforms are named like in WebGL: "name[0]".
+    pub name: String,
+    pub data_type: ShaderDataType,
+    /// Array length (1 for non-array uniforms).
+    pub size: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeDescription {
+    pub name: String,
+    pub data_type: ShaderDataType,
+    pub size: i32,
+    pub location: u32,
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Typed description of active uniforms and attributes of a linked program (see Program::reflect).
+/// Inputs not used by the shader code are removed by the compiler, so they are not listed here.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramReflection {
+    pub uniforms: Vec<UniformDescription>,
+    pub attributes: Vec<AttributeDescription>,
+}
+
+impl ProgramReflection {
+    pub fn create<B: GlBackend>(context: &B, program: &B::Program) -> Self {
+        let uniform_count = context.get_program_parameter(program, crate::AppContext::ACTIVE_UNIFORMS);
+        let uniforms = (0..uniform_count.max(0) as u32)
+            .filter_map(|index| context.get_active_uniform(program, index))
+            .map(|info| UniformDescription {
+                name: info.name,
+                data_type: ShaderDataType::from_gl_property_value(info.data_type),
+                size: info.size,
+            })
+            .collect();
+
+        let attribute_count = context.get_program_parameter(program, crate::AppContext::ACTIVE_ATTRIBUTES);
+        let mut attributes: Vec<AttributeDescription> = (0..attribute_count.max(0) as u32)
+            .filter_map(|index| context.get_active_attrib(program, index))
+            .filter_map(|info| {
+                let location = context.get_attrib_location(program, &info.name);
+                if location < 0 {
+                    return None;
+                }
+                Some(AttributeDescription {
+                    location: location as u32,
+                    data_type: ShaderDataType::from_gl_property_value(info.data_type),
+                    size: info.size,
+                    name: info.name,
+                })
+            })
+            .collect();
+        attributes.sort_by_key(|a| a.location);
+
+        ProgramReflection { uniforms, attributes }
+    }
+
+    /// This method finds uniform by name, array uniforms can be found by "name" or "name[0]".
+    pub fn get_uniform(&self, name: &str) -> Option<&UniformDescription> {
+        self.uniforms
+            .iter()
+            .find(|u| u.name == name || u.name.trim_end_matches("[0]") == name)
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&AttributeDescription> {
+        self.attributes.iter().find(|a| a.name == name)
+    }
+
+    /// This method checks if VAO attributes (VertexType::get_vao_signature) can feed every active attribute of the program.
+    /// Attribute with location N is fed by N-th element of the signature. Number of components doesn't have to match
+    /// the shader type (GL fills missing ones with defaults and ignores extra ones). Integer shader attributes need
+    /// integer signature elements (VertexBufferAttribute::create_integer) and float ones need non-integer elements.
+    pub fn check_vao_signature(&self, signature: &[VertexBufferAttribute]) -> SWGLResult<()> {
+        let locations: Vec<(u32, VertexBufferAttribute)> = (0..).zip(signature.iter().copied()).collect();
+        self.check_vao_locations(&locations)
+    }
+
+    /// This method works like check_vao_signature, but gets attributes with explicit locations
+    /// (VertexArrayObject::get_signature).
+    pub fn check_vao_locations(&self, signature: &[(u32, VertexBufferAttribute)]) -> SWGLResult<()> {
+        for attribute in &self.attributes {
+            if attribute.data_type.get_component_count().is_none() {
+                return Err(SWGLRuntimeError::new(&format!(
+                    "Program::UnsupportedAttributeType: {}",
+                    attribute.name
+                )));
+            }
+
+            let location_count = attribute.data_type.get_location_count() * attribute.size.max(1) as u32;
+            for location in attribute.location..attribute.location + location_count {
+                let vao_attribute = match signature.iter().find(|(l, _)| *l == location) {
+                    Some((_, vao_attribute)) => vao_attribute,
+                    None => {
+                        return Err(SWGLRuntimeError::new(&format!(
+                            "Program::MissingVertexAttribute: {} (location {})",
+                            attribute.name, location
+                        )))
+                    }
+                };
+
+                if vao_attribute.is_integer() != attribute.data_type.is_integer() {
+                    return Err(SWGLRuntimeError::new(&format!(
+                        "Program::VertexAttributeTypeMismatch: {} (location {})",
+                        attribute.name, location
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Program, ShaderSource};
+    use super::super::super::backend::recording_backend::RecordingBackend;
+    use super::super::super::vertex_array_object::AttributeDataType;
+
+    const VERTEX_SHADER: &str = "#version 300 es
+layout (location=0) in vec2 pos;
+layout (location=1) in vec4 color;
+layout (location=2) in uint layer;
+void main() {}
+";
+
+    fn reflection(context: &RecordingBackend) -> ProgramReflection {
+        let source = ShaderSource::new(VERTEX_SHADER, "#version 300 es\nvoid main() {}\n");
+        Program::new(context, &source).unwrap().reflect(context)
+    }
+
+    fn error_of(reflection: &ProgramReflection, signature: &[VertexBufferAttribute]) -> Option<String> {
+        reflection.check_vao_signature(signature).err().map(|error| error.message)
+    }
+
+    #[test]
+    fn accepts_any_number_of_components() {
+        let context = RecordingBackend::new();
+        let reflection = reflection(&context);
+        let layer = VertexBufferAttribute::create_integer(1, AttributeDataType::UnsignedInt);
+
+        // fewer components than the shader type are filled with defaults, extra ones are ignored
+        let signature = [VertexBufferAttribute::create(4), VertexBufferAttribute::create(3), layer];
+        assert_eq!(error_of(&reflection, &signature), None);
+        let packed_color = VertexBufferAttribute::create_typed(4, AttributeDataType::UnsignedByte, true);
+        let signature = [VertexBufferAttribute::create(1), packed_color, layer];
+        assert_eq!(error_of(&reflection, &signature), None);
+    }
+
+    #[test]
+    fn rejects_missing_locations_and_base_type_mismatches() {
+        let context = RecordingBackend::new();
+        let reflection = reflection(&context);
+        let layer = VertexBufferAttribute::create_integer(1, AttributeDataType::UnsignedInt);
+
+        assert_eq!(
+            error_of(&reflection, &[VertexBufferAttribute::create(2), VertexBufferAttribute::create(4)]).as_deref(),
+            Some("Program::MissingVertexAttribute: layer (location 2)")
+        );
+        let signature = [VertexBufferAttribute::create(2), VertexBufferAttribute::create(4), VertexBufferAttribute::create(1)];
+        assert_eq!(
+            error_of(&reflection, &signature).as_deref(),
+            Some("Program::VertexAttributeTypeMismatch: layer (location 2)")
+        );
+        let signature = [VertexBufferAttribute::create(2), layer, layer];
+        assert_eq!(
+            error_of(&reflection, &signature).as_deref(),
+            Some("Program::VertexAttributeTypeMismatch: color (location 1)")
+        );
+
+        let locations = [(2, layer), (0, VertexBufferAttribute::create(2)), (1, VertexBufferAttribute::create(4))];
+        assert!(reflection.check_vao_locations(&locations).is_ok());
+        assert_eq!(
+            reflection.check_vao_locations(&locations[..2]).unwrap_err().message,
+            "Program::MissingVertexAttribute: color (location 1)"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use nalgebra_glm as glm;
+
+use super::super::backend::interface::GlBackend;
+use super::super::super::global_tools::vector2::Vector2;
+use super::super::super::graphics_2d::color::Color;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This trait is implemented by every type that can be uploaded with Program::set_uniform.
+pub trait UniformValue {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation);
+}
+
+impl<T: UniformValue + ?Sized> UniformValue for &T {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        (**self).upload(context, location);
+    }
+}
+
+impl<T> UniformValue for Vec<T>
+where
+    [T]: UniformValue,
+{
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        self.as_slice().upload(context, location);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// scalars
+
+impl UniformValue for f32 {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform1f(Some(location), *self);
+    }
+}
+
+impl UniformValue for i32 {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform1i(Some(location), *self);
+    }
+}
+
+impl UniformValue for u32 {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform1ui(Some(location), *self);
+    }
+}
+
+impl UniformValue for [f32] {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform1fv(Some(location), self);
+    }
+}
+
+impl UniformValue for [i32] {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform1iv(Some(location), self);
+    }
+}
+
+impl UniformValue for [u32] {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform1uiv(Some(location), self);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// vectors
+
+impl UniformValue for Vector2<f32> {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform2f(Some(location), self.x, self.y);
+    }
+}
+
+impl UniformValue for [Vector2<f32>] {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        let mut data = Vec::with_capacity(self.len() * 2);
+        for v in self {
+            data.extend_from_slice(&[v.x, v.y]);
+        }
+        context.uniform2fv(Some(location), &data);
+    }
+}
+
+impl UniformValue for Color {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        context.uniform4f(Some(location), self.red, self.green, self.blue, self.alpha);
+    }
+}
+
+impl UniformValue for [Color] {
+    fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+        let mut data = Vec::with_capacity(self.len() * 4);
+        for c in self {
+            data.extend_from_slice(&[c.red, c.green, c.blue, c.alpha]);
+        }
+        context.uniform4fv(Some(location), &data);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// glm types
+
+/// Glm vectors and matrices are stored column by column, so they can be uploaded directly.
+macro_rules! impl_glm_uniform_value {
+    ($glm_type:ty, $method:ident) => {
+        impl UniformValue for $glm_type {
+            fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+                context.$method(Some(location), self.as_slice());
+            }
+        }
+
+        impl UniformValue for [$glm_type] {
+            fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+                let mut data = vec![];
+                for v in self {
+                    data.extend_from_slice(v.as_slice());
+                }
+                context.$method(Some(location), &data);
+            }
+        }
+    };
+    ($glm_type:ty, $method:ident, matrix) => {
+        impl UniformValue for $glm_type {
+            fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+                context.$method(Some(location), false, self.as_slice());
+            }
+        }
+
+        impl UniformValue for [$glm_type] {
+            fn upload<B: GlBackend>(&self, context: &B, location: &B::UniformLocation) {
+                let mut data = vec![];
+                for m in self {
+                    data.extend_from_slice(m.as_slice());
+                }
+                context.$method(Some(location), false, &data);
+            }
+        }
+    };
+}
+
+impl_glm_uniform_value!(glm::Vec2, uniform2fv);
+impl_glm_uniform_value!(glm::Vec3, uniform3fv);
+impl_glm_uniform_value!(glm::Vec4, uniform4fv);
+impl_glm_uniform_value!(glm::Mat2, uniform_matrix2fv, matrix);
+impl_glm_uniform_value!(glm::Mat3, uniform_matrix3fv, matrix);
+impl_glm_uniform_value!(glm::Mat4, uniform_matrix4fv, matrix);
@@ -0,0 +1,185 @@
+//! Parser of .cube color lookup tables (Adobe/Resolve format) used for color grading (see Texture3D).
+
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// 3D lookup table with RGB values ordered like texels of 3D texture (red changes the fastest, then green and blue).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeLut {
+    pub title: Option<String>,
+    /// Number of entries along every axis.
+    pub size: u32,
+    /// Input color range mapped to the table (shader has to remap colors if it is not 0..1).
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    /// size^3 RGB triples.
+    pub data: Vec<f32>,
+}
+
+impl CubeLut {
+    /// This method parses text of .cube file. Only 3D tables are supported (LUT_1D_SIZE is rejected and
+    /// LUT_1D_INPUT_RANGE of 1D shaper is ignored), LUT_3D_INPUT_RANGE sets the same domain for all channels.
+    ///
+    /// ```text
+    /// let lut = CubeLut::parse(&fetch_text("grading/warm.cube").await?)?;
+    /// let texture = Texture3D::new_lut_from_cube(&context, &lut, Texture3D::lut_configuration(TextureFormat::Rgba16F))?;
+    /// ```
+    pub fn parse(source: &str) -> SWGLResult<Self> {
+        let mut lut = Self {
+            title: None,
+            size: 0,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            data: Vec::new(),
+        };
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_error = |name: &str| SWGLRuntimeError::new(&format!("CubeLut::{}: line {}", name, line_index + 1));
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            match keyword {
+                "TITLE" => lut.title = Some(line[keyword.len()..].trim().trim_matches('"').to_owned()),
+                "LUT_3D_SIZE" => {
+                    lut.size = words.next().and_then(|size| size.parse().ok()).ok_or_else(|| line_error("InvalidSize"))?;
+                    if !(2..=MAX_SIZE).contains(&lut.size) {
+                        return Err(line_error("InvalidSize"));
+                    }
+                    lut.data.reserve((lut.size as usize).pow(3) * 3);
+                }
+                "LUT_1D_SIZE" => return Err(line_error("Unsupported1DTable")),
+                "DOMAIN_MIN" => lut.domain_min = parse_triple(words).ok_or_else(|| line_error("InvalidDomain"))?,
+                "DOMAIN_MAX" => lut.domain_max = parse_triple(words).ok_or_else(|| line_error("InvalidDomain"))?,
+                "LUT_3D_INPUT_RANGE" => {
+                    let [min, max] = parse_numbers(words).ok_or_else(|| line_error("InvalidDomain"))?;
+                    lut.domain_min = [min; 3];
+                    lut.domain_max = [max; 3];
+                }
+                "LUT_1D_INPUT_RANGE" => {}
+                _ => {
+                    let color = parse_triple(line.split_whitespace()).ok_or_else(|| line_error("InvalidLine"))?;
+                    if lut.size == 0 {
+                        return Err(line_error("MissingSize"));
+                    }
+                    lut.data.extend_from_slice(&color);
+                }
+            }
+        }
+
+        if lut.size == 0 {
+            return Err(SWGLRuntimeError::new("CubeLut::MissingSize"));
+        }
+        if lut.data.len() != (lut.size as usize).pow(3) * 3 {
+            return Err(SWGLRuntimeError::new(&format!(
+                "CubeLut::InvalidEntryCount: {} entries, expected {}",
+                lut.data.len() / 3,
+                (lut.size as usize).pow(3)
+            )));
+        }
+
+        Ok(lut)
+    }
+
+    /// This method returns the table as RGBA values (alpha is 1), which can be uploaded to Rgba16F or Rgba32F texture.
+    pub fn get_rgba_data(&self) -> Vec<f32> {
+        self.data.chunks(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 1.0]).collect()
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// private:
+
+/// The largest LUT_3D_SIZE allowed by the format.
+const MAX_SIZE: u32 = 256;
+
+/// This function parses exactly three numbers (e.g. "0.5 0.25 1.0").
+fn parse_triple<'a>(words: impl Iterator<Item = &'a str>) -> Option<[f32; 3]> {
+    parse_numbers(words)
+}
+
+/// This function parses exactly N numbers.
+fn parse_numbers<'a, const N: usize>(mut words: impl Iterator<Item = &'a str>) -> Option<[f32; N]> {
+    let mut numbers = [0.0; N];
+    for value in numbers.iter_mut() {
+        *value = words.next()?.parse().ok()?;
+    }
+    if words.next().is_some() {
+        return None;
+    }
+    Some(numbers)
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY_2: &str = "0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+
+    fn error_of(source: &str) -> String {
+        CubeLut::parse(source).unwrap_err().message
+    }
+
+    #[test]
+    fn parses_table_with_title_comments_and_blank_lines() {
+        let source = format!("# exported LUT\n\nTITLE \"Warm \"film\" look\"\n  # size\nLUT_3D_SIZE 2\n\n{}", IDENTITY_2);
+        let lut = CubeLut::parse(&source).unwrap();
+
+        assert_eq!(lut.title.as_deref(), Some("Warm \"film\" look"));
+        assert_eq!(lut.size, 2);
+        assert_eq!((lut.domain_min, lut.domain_max), ([0.0; 3], [1.0; 3]));
+        assert_eq!(lut.data.len(), 8 * 3);
+        assert_eq!(&lut.data[3..6], &[1.0, 0.0, 0.0]);
+        assert_eq!(&lut.get_rgba_data()[4..8], &[1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn parses_domain_lines() {
+        let source = format!("LUT_3D_SIZE 2\nDOMAIN_MIN 0 -0.5 0\nDOMAIN_MAX 1 2 4\n{}", IDENTITY_2);
+        let lut = CubeLut::parse(&source).unwrap();
+        assert_eq!((lut.domain_min, lut.domain_max), ([0.0, -0.5, 0.0], [1.0, 2.0, 4.0]));
+
+        assert_eq!(error_of("LUT_3D_SIZE 2\nDOMAIN_MIN 0 0\n"), "CubeLut::InvalidDomain: line 2");
+    }
+
+    #[test]
+    fn parses_resolve_input_ranges() {
+        let source = format!("LUT_1D_INPUT_RANGE 0.0 1.0\nLUT_3D_INPUT_RANGE -0.25 1.5\nLUT_3D_SIZE 2\n{}", IDENTITY_2);
+        let lut = CubeLut::parse(&source).unwrap();
+        assert_eq!((lut.domain_min, lut.domain_max), ([-0.25; 3], [1.5; 3]));
+
+        assert_eq!(error_of("LUT_3D_INPUT_RANGE 0 1 2\n"), "CubeLut::InvalidDomain: line 1");
+    }
+
+    #[test]
+    fn rejects_missing_size() {
+        assert_eq!(error_of("TITLE \"empty\"\n"), "CubeLut::MissingSize");
+        assert_eq!(error_of(&format!("# no size\n{}", IDENTITY_2)), "CubeLut::MissingSize: line 2");
+    }
+
+    #[test]
+    fn rejects_invalid_size() {
+        assert_eq!(error_of("LUT_3D_SIZE 1\n"), "CubeLut::InvalidSize: line 1");
+        assert_eq!(error_of("LUT_3D_SIZE 257\n"), "CubeLut::InvalidSize: line 1");
+        assert_eq!(error_of("LUT_3D_SIZE big\n"), "CubeLut::InvalidSize: line 1");
+    }
+
+    #[test]
+    fn rejects_wrong_entry_count() {
+        let source = format!("LUT_3D_SIZE 2\n{}1 1 1\n", IDENTITY_2);
+        assert_eq!(error_of(&source), "CubeLut::InvalidEntryCount: 9 entries, expected 8");
+    }
+
+    #[test]
+    fn rejects_1d_table_and_malformed_lines() {
+        assert_eq!(error_of("LUT_1D_SIZE 1024\n"), "CubeLut::Unsupported1DTable: line 1");
+        assert_eq!(error_of("LUT_3D_SIZE 2\n0 0\n"), "CubeLut::InvalidLine: line 2");
+        assert_eq!(error_of("LUT_3D_SIZE 2\nUNKNOWN_KEYWORD 1\n"), "CubeLut::InvalidLine: line 2");
+    }
+}
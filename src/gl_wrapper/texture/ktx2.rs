@@ -0,0 +1,304 @@
+//! Parser of KTX2 texture containers (e.g. textures encoded by toktx or basisu with --ktx2 and without supercompression).
+
+use super::texture_config::{CompressedFormat, TextureFormat};
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+
+// -----------------------------------------------------------------------------------------------------------
+
+const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const HEADER_SIZE: usize = 80;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+/// 2D texture or texture array stored in KTX2 file. Cubemaps, 3D textures and supercompressed files (BasisLZ and
+/// Zstandard) aren't supported.
+///
+/// ```text
+/// let astc = Ktx2Texture::parse(&fetch_bytes("terrain.astc.ktx2").await?)?;
+/// let bc = Ktx2Texture::parse(&fetch_bytes("terrain.bc.ktx2").await?)?;
+/// let texture = Texture2D::from_compressed(&context, &[&astc, &bc], Some(&terrain_png), conf)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ktx2Texture {
+    /// VkFormat of texels (see CompressedFormat::from_vk_format).
+    pub vk_format: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Number of array layers, 0 if the texture isn't an array.
+    pub layer_count: u32,
+    /// Mipmap levels from the largest one, every level contains all layers stored one after another.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl Ktx2Texture {
+    /// This method parses whole file. Errors of malformed files contain byte offset where the problem was found.
+    pub fn parse(bytes: &[u8]) -> SWGLResult<Self> {
+        if bytes.len() < HEADER_SIZE || bytes[..IDENTIFIER.len()] != IDENTIFIER {
+            return Err(SWGLRuntimeError::new("Ktx2::InvalidIdentifier"));
+        }
+
+        let vk_format = read_u32(bytes, 12)?;
+        let width = read_u32(bytes, 20)?;
+        let height = read_u32(bytes, 24)?;
+        let depth = read_u32(bytes, 28)?;
+        let layer_count = read_u32(bytes, 32)?;
+        let face_count = read_u32(bytes, 36)?;
+        let level_count = read_u32(bytes, 40)?;
+        let supercompression = read_u32(bytes, 44)?;
+
+        if vk_format == 0 {
+            return Err(SWGLRuntimeError::new("Ktx2::UndefinedFormat"));
+        }
+        if width == 0 || height == 0 {
+            return Err(SWGLRuntimeError::new("Ktx2::Unsupported1DTexture"));
+        }
+        if depth != 0 {
+            return Err(SWGLRuntimeError::new("Ktx2::Unsupported3DTexture"));
+        }
+        if face_count != 1 {
+            return Err(SWGLRuntimeError::new("Ktx2::UnsupportedCubemap"));
+        }
+        if supercompression != 0 {
+            return Err(SWGLRuntimeError::new(&format!("Ktx2::UnsupportedSupercompression: {}", supercompression)));
+        }
+        // 0 means that the file contains only the base level
+        let level_count = level_count.max(1) as usize;
+        if level_count > 32 - width.max(height).leading_zeros() as usize {
+            return Err(SWGLRuntimeError::new("Ktx2::InvalidLevelCount"));
+        }
+
+        let mut levels = Vec::with_capacity(level_count);
+        for level in 0..level_count {
+            let entry = HEADER_SIZE + level * LEVEL_INDEX_ENTRY_SIZE;
+            let offset = read_u64(bytes, entry)?;
+            let length = read_u64(bytes, entry + 8)?;
+            let end = offset.checked_add(length).filter(|end| *end <= bytes.len() as u64);
+            match end {
+                Some(end) => levels.push(bytes[offset as usize..end as usize].to_vec()),
+                None => return Err(SWGLRuntimeError::new(&format!("Ktx2::UnexpectedEnd: {}", entry))),
+            }
+        }
+
+        Ok(Self {
+            vk_format,
+            width,
+            height,
+            layer_count,
+            levels,
+        })
+    }
+
+    /// This method returns None if texels aren't block compressed or their format isn't supported.
+    pub fn get_compressed_format(&self) -> Option<CompressedFormat> {
+        CompressedFormat::from_vk_format(self.vk_format)
+    }
+
+    /// This method returns format of not compressed texels which can be uploaded without conversion
+    /// (8-bit unsigned normalized and float formats).
+    pub fn get_uncompressed_format(&self) -> Option<TextureFormat> {
+        match self.vk_format {
+            9 => Some(TextureFormat::R8),
+            16 => Some(TextureFormat::Rg8),
+            23 => Some(TextureFormat::Rgb8),
+            37 => Some(TextureFormat::Rgba8),
+            43 => Some(TextureFormat::Srgb8Alpha8),
+            76 => Some(TextureFormat::R16F),
+            83 => Some(TextureFormat::Rg16F),
+            97 => Some(TextureFormat::Rgba16F),
+            100 => Some(TextureFormat::R32F),
+            103 => Some(TextureFormat::Rg32F),
+            109 => Some(TextureFormat::Rgba32F),
+            _ => None,
+        }
+    }
+
+    /// This method returns size of mipmap level in texels.
+    pub fn get_level_size(&self, level: usize) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// private:
+
+fn read_u32(bytes: &[u8], offset: usize) -> SWGLResult<u32> {
+    match bytes.get(offset..offset + 4) {
+        Some(value) => Ok(u32::from_le_bytes([value[0], value[1], value[2], value[3]])),
+        None => Err(SWGLRuntimeError::new(&format!("Ktx2::UnexpectedEnd: {}", offset))),
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> SWGLResult<u64> {
+    let low = read_u32(bytes, offset)? as u64;
+    let high = read_u32(bytes, offset + 4)? as u64;
+    Ok(high << 32 | low)
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Header fields of test files, levels are placed right after the level index.
+    struct Header {
+        vk_format: u32,
+        width: u32,
+        height: u32,
+        depth: u32,
+        layer_count: u32,
+        face_count: u32,
+        level_count: u32,
+        supercompression: u32,
+    }
+
+    impl Header {
+        fn new(vk_format: u32, width: u32, height: u32) -> Self {
+            Self {
+                vk_format,
+                width,
+                height,
+                depth: 0,
+                layer_count: 0,
+                face_count: 1,
+                level_count: 1,
+                supercompression: 0,
+            }
+        }
+
+        fn write(&self, levels: &[Vec<u8>]) -> Vec<u8> {
+            let mut bytes = IDENTIFIER.to_vec();
+            let fields = [
+                self.vk_format,
+                1,
+                self.width,
+                self.height,
+                self.depth,
+                self.layer_count,
+                self.face_count,
+                self.level_count,
+                self.supercompression,
+            ];
+            for field in fields.iter() {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+            // data format descriptor, key/value data and supercompression global data are empty
+            bytes.extend_from_slice(&[0; 32]);
+
+            let mut offset = (HEADER_SIZE + levels.len() * LEVEL_INDEX_ENTRY_SIZE) as u64;
+            for level in levels {
+                bytes.extend_from_slice(&offset.to_le_bytes());
+                bytes.extend_from_slice(&(level.len() as u64).to_le_bytes());
+                bytes.extend_from_slice(&(level.len() as u64).to_le_bytes());
+                offset += level.len() as u64;
+            }
+            for level in levels {
+                bytes.extend_from_slice(level);
+            }
+            bytes
+        }
+    }
+
+    fn error_of(bytes: &[u8]) -> String {
+        Ktx2Texture::parse(bytes).unwrap_err().message
+    }
+
+    #[test]
+    fn parses_2d_texture() {
+        // BC3 8x4 texture has 2 blocks of 16 bytes
+        let bytes = Header::new(137, 8, 4).write(&[vec![7; 32]]);
+        let texture = Ktx2Texture::parse(&bytes).unwrap();
+
+        assert_eq!(
+            texture,
+            Ktx2Texture {
+                vk_format: 137,
+                width: 8,
+                height: 4,
+                layer_count: 0,
+                levels: vec![vec![7; 32]],
+            }
+        );
+        assert_eq!(texture.get_compressed_format(), Some(CompressedFormat::Dxt5 { srgb: false }));
+        assert_eq!(texture.get_uncompressed_format(), None);
+    }
+
+    #[test]
+    fn parses_array_with_levels() {
+        let header = Header {
+            layer_count: 3,
+            level_count: 3,
+            ..Header::new(37, 4, 2)
+        };
+        let levels = vec![vec![1; 4 * 2 * 4 * 3], vec![2; 2 * 4 * 3], vec![3; 4 * 3]];
+        let texture = Ktx2Texture::parse(&header.write(&levels)).unwrap();
+
+        assert_eq!(texture.layer_count, 3);
+        assert_eq!(texture.levels, levels);
+        assert_eq!(texture.get_level_size(1), (2, 1));
+        assert_eq!(texture.get_level_size(2), (1, 1));
+        assert_eq!(texture.get_uncompressed_format(), Some(TextureFormat::Rgba8));
+    }
+
+    #[test]
+    fn treats_zero_level_count_as_base_level() {
+        let header = Header {
+            level_count: 0,
+            ..Header::new(37, 1, 1)
+        };
+        let texture = Ktx2Texture::parse(&header.write(&[vec![9; 4]])).unwrap();
+        assert_eq!(texture.levels, vec![vec![9; 4]]);
+    }
+
+    #[test]
+    fn rejects_invalid_identifier_and_short_input() {
+        let mut bytes = Header::new(37, 1, 1).write(&[vec![0; 4]]);
+        bytes[5] = b'1';
+        assert_eq!(error_of(&bytes), "Ktx2::InvalidIdentifier");
+        assert_eq!(error_of(&IDENTIFIER), "Ktx2::InvalidIdentifier");
+        assert_eq!(error_of(&[0; HEADER_SIZE - 1]), "Ktx2::InvalidIdentifier");
+    }
+
+    #[test]
+    fn rejects_level_index_past_end() {
+        let header = Header {
+            level_count: 2,
+            ..Header::new(37, 2, 1)
+        };
+        // index contains only the first entry, so the second one overlaps data of the first level and file ends
+        // in the middle of its offset
+        let bytes = header.write(&[vec![0; 4]]);
+        let second_entry = HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE;
+        assert_eq!(error_of(&bytes), format!("Ktx2::UnexpectedEnd: {}", second_entry + 4));
+    }
+
+    #[test]
+    fn rejects_level_outside_file() {
+        let mut bytes = Header::new(37, 1, 1).write(&[vec![0; 4]]);
+        let expected = format!("Ktx2::UnexpectedEnd: {}", HEADER_SIZE);
+
+        // length is one byte longer than the file
+        bytes[HEADER_SIZE + 8..HEADER_SIZE + 16].copy_from_slice(&5u64.to_le_bytes());
+        assert_eq!(error_of(&bytes), expected);
+
+        // offset + length overflows
+        bytes[HEADER_SIZE..HEADER_SIZE + 8].copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+        bytes[HEADER_SIZE + 8..HEADER_SIZE + 16].copy_from_slice(&4u64.to_le_bytes());
+        assert_eq!(error_of(&bytes), expected);
+    }
+
+    #[test]
+    fn rejects_unsupported_textures() {
+        let level = [vec![0; 4]];
+        let cases = [
+            (Header { depth: 2, ..Header::new(37, 1, 1) }, "Ktx2::Unsupported3DTexture"),
+            (Header { face_count: 6, ..Header::new(37, 1, 1) }, "Ktx2::UnsupportedCubemap"),
+            (Header { supercompression: 2, ..Header::new(37, 1, 1) }, "Ktx2::UnsupportedSupercompression: 2"),
+            (Header::new(0, 1, 1), "Ktx2::UndefinedFormat"),
+            (Header::new(37, 1, 0), "Ktx2::Unsupported1DTexture"),
+            (Header { level_count: 2, ..Header::new(37, 1, 1) }, "Ktx2::InvalidLevelCount"),
+        ];
+        for (header, error) in cases.iter() {
+            assert_eq!(&error_of(&header.write(&level)), error);
+        }
+    }
+}
@@ -1,2 +1,6 @@
 pub mod texture_2d;
-pub mod texture_config;
\ No newline at end of file
+pub mod texture_3d;
+pub mod texture_config;
+pub mod sampler;
+pub mod cube_lut;
+pub mod ktx2;
\ No newline at end of file
@@ -0,0 +1,74 @@
+//! WebGL2 Sampler Object abstraction.
+
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::super::backend::interface::GlBackend;
+use super::texture_config::{TextureConfiguration, TextureFilter, TextureFormat, TextureWrap};
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Wrap and filter parameters which override parameters of any texture bound to the same texture unit, so the same
+/// texture can be sampled differently in every pass (e.g. nearest for pixel-perfect preview, linear for minimap).
+/// Mipmap filters can be used only with textures which have mipmaps.
+/// GL sampler is deleted when this object is dropped.
+///
+/// ```text
+/// let nearest = Sampler::new(&context, TextureWrap::ClampToEdge, TextureWrap::ClampToEdge, TextureFilter::Nearest, TextureFilter::Nearest)?;
+/// renderer.bind_textures_with_samplers(&context, &[&texture], &[Some(&nearest)]);
+/// ```
+pub struct Sampler<B: GlBackend = crate::AppContext> {
+    sampler: B::Sampler,
+    context: B,
+}
+
+impl<B: GlBackend> Sampler<B> {
+    pub fn new(
+        context: &B,
+        wrap_x: TextureWrap,
+        wrap_y: TextureWrap,
+        min_filter: TextureFilter,
+        mag_filter: TextureFilter,
+    ) -> SWGLResult<Self> {
+        let conf = TextureConfiguration::new(wrap_x, wrap_y, min_filter, mag_filter, TextureFormat::Rgba8);
+        Self::from_configuration(context, &conf)
+    }
+
+    /// This method uses wrap, filters, LOD range and anisotropy of the configuration (format, mipmaps and levels
+    /// belong to the texture).
+    pub fn from_configuration(context: &B, conf: &TextureConfiguration) -> SWGLResult<Self> {
+        conf.validate_sampling()?;
+
+        if let Some(sampler) = context.create_sampler() {
+            conf.apply_to_sampler(context, &sampler);
+            return Ok(Self {
+                sampler,
+                context: context.clone(),
+            });
+        }
+
+        Err(SWGLRuntimeError::new("Sampler::CreationError"))
+    }
+
+    /// This method deletes the sampler immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    /// This method binds the sampler to texture unit (the same location as in Texture2D::active).
+    pub fn bind(&self, context: &B, location: u32) {
+        context.bind_sampler(location, Some(&self.sampler));
+    }
+
+    /// This method unbinds sampler from texture unit, so parameters of the texture are used again.
+    pub fn unbind(context: &B, location: u32) {
+        context.bind_sampler(location, None);
+    }
+
+    /// This method returns object that can be used directly inside WebGL low level calls.
+    pub fn get_raw_id(&self) -> &B::Sampler {
+        &self.sampler
+    }
+}
+
+impl<B: GlBackend> Drop for Sampler<B> {
+    fn drop(&mut self) {
+        self.context.delete_sampler(Some(&self.sampler));
+    }
+}
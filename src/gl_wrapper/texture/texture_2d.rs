@@ -1,123 +1,661 @@
-use web_sys::{WebGlTexture, HtmlImageElement};
-use super::texture_config::TextureConfiguration;
+use web_sys::HtmlImageElement;
+use super::ktx2::Ktx2Texture;
+use super::texture_config::{CompressedFormat, PixelType, TextureConfiguration, TextureFormat, TextureMipmaps};
+use super::super::backend::interface::{self, GlBackend};
 use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
 use super::super::super::global_tools::vector2::Vector2;
 
 // --------------------------------------------------------------------------------------------------
 
-pub struct Texture2D {
-    texture: std::option::Option<WebGlTexture>,
+/// GL texture is deleted when this object is dropped.
+pub struct Texture2D<B: GlBackend = crate::AppContext> {
+    texture: std::option::Option<B::Texture>,
     texture_dim: Vector2<u32>,
+    layer_dim: Vector2<u32>,
+    layer_count: u32,
     configuration: TextureConfiguration,
+    compressed_format: Option<CompressedFormat>,
     texture_type: u32,
+    context: B,
 }
 
 // --------------------------------------------------------------------------------------------------
 
-impl Texture2D {
+impl<B: GlBackend> Texture2D<B> {
 
-    pub fn new_texture2d(context: &crate::AppContext, img: &HtmlImageElement, conf: TextureConfiguration) -> SWGLResult<Self> {
-        let buffer = context.create_texture();
-        let texture = Self {
-            texture: buffer,
-            texture_dim: Vector2::new(img.width(), img.height()),
-            configuration: conf,
-            texture_type: crate::AppContext::TEXTURE_2D,
-        };
+    /// Depth formats can't be loaded from images.
+    pub fn new_texture2d(context: &B, img: &HtmlImageElement, conf: TextureConfiguration) -> SWGLResult<Self> {
+        Self::check_image_format(context, &conf)?;
+        let texture = Self::create(context, crate::AppContext::TEXTURE_2D, Vector2::new(img.width(), img.height()), 1, conf);
 
         texture.bind(context);
         texture.config_texture_2d(context, img)?;
+        texture.update_mipmaps(context);
         texture.general_config(context);
 
+        Ok(texture)
+    }
+
+    pub fn new_texture_array(context: &B, img: &HtmlImageElement, conf: TextureConfiguration, slice_dim: Vector2<f32>, slice_count: u32) -> SWGLResult<Self> {
+        Self::check_image_format(context, &conf)?;
+        let mut texture = Self::create(
+            context,
+            crate::AppContext::TEXTURE_2D_ARRAY,
+            Vector2::new(slice_dim.x as u32, slice_dim.y as u32),
+            slice_count,
+            conf,
+        );
+        // size of the source image (used by texture atlas), layer size is given by slice_dim
+        texture.texture_dim = Vector2::new(img.width(), img.height());
+
+        texture.bind(context);
+        texture.config_texture_array(context, img, slice_dim, slice_count)?;
+        texture.update_mipmaps(context);
+        texture.general_config(context);
 
         Ok(texture)
     }
 
-    pub fn new_texture_array(context: &crate::AppContext, img: &HtmlImageElement, conf: TextureConfiguration, slice_dim: Vector2<f32>, slice_count: u32) -> SWGLResult<Self> {
-        let buffer = context.create_texture();
+    /// This method creates texture with given size in pixels and uninitialized content (e.g. color attachment of
+    /// framebuffer). If the configuration generates mipmaps, their storage is allocated and they can be updated
+    /// with generate_mipmaps after rendering.
+    pub fn new_empty(context: &B, width: u32, height: u32, conf: TextureConfiguration) -> SWGLResult<Self> {
+        Self::check_configuration(context, &conf, false)?;
+        let texture = Self::create(context, crate::AppContext::TEXTURE_2D, Vector2::new(width, height), 1, conf);
 
-        let texture = Texture2D {
-            texture: buffer,
-            texture_dim: Vector2::new(img.width(), img.height()),
-            configuration: conf,
-            texture_type: crate::AppContext::TEXTURE_2D_ARRAY,
+        texture.bind(context);
+        context.tex_image_2d_with_u8_array(
+            crate::AppContext::TEXTURE_2D,
+            0,
+            texture.configuration.format.get_internal_format() as i32,
+            width as i32,
+            height as i32,
+            texture.configuration.format.get_format(),
+            texture.configuration.format.get_default_data_type(),
+            None,
+        ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTexture"))?;
+        texture.update_mipmaps(context);
+        texture.general_config(context);
+
+        Ok(texture)
+    }
+
+    /// This method creates texture from pixels stored row by row starting from the top-left corner (the same order
+    /// as image data of canvas). Length of pixels has to be width * height * number of channels of the format
+    /// and their type has to be valid for the format (e.g. u8 for Rgba8, f32 for R32F or u16 and f32 for Rgba16F).
+    /// Mipmaps are generated if mipmaps of the configuration are TextureMipmaps::Generate.
+    ///
+    /// ```text
+    /// let mask = Texture2D::new_from_data(&context, 256, 256, &vec![255u8; 256 * 256 * 4], conf)?;
+    /// ```
+    pub fn new_from_data<T: PixelType>(context: &B, width: u32, height: u32, pixels: &[T], conf: TextureConfiguration) -> SWGLResult<Self> {
+        Self::check_data(&conf, pixels, width as usize * height as usize)?;
+        Self::check_configuration(context, &conf, false)?;
+        let texture = Self::create(context, crate::AppContext::TEXTURE_2D, Vector2::new(width, height), 1, conf);
+
+        texture.bind(context);
+        Self::config_unpack(context);
+        context.tex_image_2d_with_u8_array(
+            crate::AppContext::TEXTURE_2D,
+            0,
+            texture.configuration.format.get_internal_format() as i32,
+            width as i32,
+            height as i32,
+            texture.configuration.format.get_format(),
+            T::get_gl_property_value(),
+            Some(interface::as_byte_slice(pixels)),
+        ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTexture"))?;
+        texture.update_mipmaps(context);
+        texture.general_config(context);
+
+        Ok(texture)
+    }
+
+    /// This method creates texture array from layers of the same size stored one after another (every layer is
+    /// stored like pixels of new_from_data). Length of pixels has to be width * height * layer_count * number of
+    /// channels of the format.
+    pub fn new_texture_array_from_data<T: PixelType>(
+        context: &B,
+        width: u32,
+        height: u32,
+        layer_count: u32,
+        pixels: &[T],
+        conf: TextureConfiguration,
+    ) -> SWGLResult<Self> {
+        Self::check_data(&conf, pixels, width as usize * height as usize * layer_count as usize)?;
+        Self::check_configuration(context, &conf, false)?;
+        let texture = Self::create(context, crate::AppContext::TEXTURE_2D_ARRAY, Vector2::new(width, height), layer_count, conf);
+
+        texture.bind(context);
+        Self::config_unpack(context);
+        context.tex_image_3d_with_u8_array(
+            crate::AppContext::TEXTURE_2D_ARRAY,
+            0,
+            texture.configuration.format.get_internal_format() as i32,
+            width as i32,
+            height as i32,
+            layer_count as i32,
+            texture.configuration.format.get_format(),
+            T::get_gl_property_value(),
+            Some(interface::as_byte_slice(pixels)),
+        ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTextureArray"))?;
+        texture.update_mipmaps(context);
+        texture.general_config(context);
+
+        Ok(texture)
+    }
+
+    /// This method creates texture array with one layer for every image, all images must have the same size.
+    /// Returned layer indices (in order of images) can be used as array_index of Vertex2D.
+    ///
+    /// ```text
+    /// let (texture, layers) = Texture2D::new_texture_array_from_images(&context, &[&grass, &sand, &water], conf)?;
+    /// let vertex = Vertex2D::new_general(Color::default(), layers[1], 0.0, 0.0);
+    /// ```
+    pub fn new_texture_array_from_images(context: &B, images: &[&HtmlImageElement], conf: TextureConfiguration) -> SWGLResult<(Self, Vec<u32>)> {
+        Self::check_image_format(context, &conf)?;
+        let (width, height) = match images.first() {
+            Some(img) => (img.width(), img.height()),
+            None => return Err(SWGLRuntimeError::new("Texture2D::NoLayers")),
         };
+        if images.iter().any(|img| img.width() != width || img.height() != height) {
+            return Err(SWGLRuntimeError::new("Texture2D::LayerSizeMismatch"));
+        }
+
+        let texture = Self::allocate_array(context, width, height, images.len() as u32, conf)?;
+        for (layer, img) in images.iter().enumerate() {
+            texture.upload_image_layer(context, layer as u32, img)?;
+        }
+        texture.update_mipmaps(context);
+        texture.general_config(context);
+
+        Ok((texture, (0..images.len() as u32).collect()))
+    }
+
+    /// This method creates texture array with one layer for every buffer (see new_texture_array_from_images),
+    /// every buffer contains pixels of width x height layer stored like in new_from_data.
+    pub fn new_texture_array_from_layers<T: PixelType>(
+        context: &B,
+        width: u32,
+        height: u32,
+        layers: &[&[T]],
+        conf: TextureConfiguration,
+    ) -> SWGLResult<(Self, Vec<u32>)> {
+        if layers.is_empty() {
+            return Err(SWGLRuntimeError::new("Texture2D::NoLayers"));
+        }
+        for pixels in layers {
+            Self::check_data(&conf, pixels, width as usize * height as usize)?;
+        }
+        Self::check_configuration(context, &conf, false)?;
+
+        let texture = Self::allocate_array(context, width, height, layers.len() as u32, conf)?;
+        Self::config_unpack(context);
+        for (layer, pixels) in layers.iter().enumerate() {
+            context.tex_sub_image_3d_with_u8_array(
+                crate::AppContext::TEXTURE_2D_ARRAY,
+                0,
+                0,
+                0,
+                layer as i32,
+                width as i32,
+                height as i32,
+                1,
+                texture.configuration.format.get_format(),
+                T::get_gl_property_value(),
+                interface::as_byte_slice(pixels),
+            ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTextureArray"))?;
+        }
+        texture.update_mipmaps(context);
+        texture.general_config(context);
 
+        Ok((texture, (0..layers.len() as u32).collect()))
+    }
+
+    /// This method creates texture array from sprite sheet divided into grid of columns x rows cells of the same
+    /// size (image size has to be divisible by them). Every cell becomes one layer, returned layer indices are
+    /// ordered row by row starting from the top-left cell.
+    pub fn new_texture_array_from_grid(
+        context: &B,
+        img: &HtmlImageElement,
+        columns: u32,
+        rows: u32,
+        conf: TextureConfiguration,
+    ) -> SWGLResult<(Self, Vec<u32>)> {
+        Self::check_image_format(context, &conf)?;
+        let layer_count = columns.checked_mul(rows).ok_or(SWGLRuntimeError::new("Texture2D::InvalidGridSize"))?;
+        if layer_count == 0 || img.width() % columns != 0 || img.height() % rows != 0 {
+            return Err(SWGLRuntimeError::new("Texture2D::InvalidGridSize"));
+        }
+        let (cell_width, cell_height) = (img.width() / columns, img.height() / rows);
+
+        let texture = Self::allocate_array(context, cell_width, cell_height, layer_count, conf)?;
+        context.pixel_storei(crate::AppContext::UNPACK_ROW_LENGTH, img.width() as i32);
+        let mut result = Ok(());
+        for layer in 0..layer_count {
+            context.pixel_storei(crate::AppContext::UNPACK_SKIP_PIXELS, ((layer % columns) * cell_width) as i32);
+            context.pixel_storei(crate::AppContext::UNPACK_SKIP_ROWS, ((layer / columns) * cell_height) as i32);
+            result = texture.upload_image_layer(context, layer, img);
+            if result.is_err() {
+                break;
+            }
+        }
+        // other uploads read whole images
+        context.pixel_storei(crate::AppContext::UNPACK_ROW_LENGTH, 0);
+        context.pixel_storei(crate::AppContext::UNPACK_SKIP_PIXELS, 0);
+        context.pixel_storei(crate::AppContext::UNPACK_SKIP_ROWS, 0);
+        result?;
+
+        texture.update_mipmaps(context);
+        texture.general_config(context);
+
+        Ok((texture, (0..layer_count).collect()))
+    }
+
+    /// This method creates TEXTURE_2D_ARRAY texture with uninitialized layers of given size in pixels.
+    fn allocate_array(context: &B, width: u32, height: u32, layer_count: u32, conf: TextureConfiguration) -> SWGLResult<Self> {
+        if layer_count > context.get_parameter_i32(crate::AppContext::MAX_ARRAY_TEXTURE_LAYERS).max(0) as u32 {
+            return Err(SWGLRuntimeError::new("Texture2D::TooManyLayers"));
+        }
+
+        let texture = Self::create(context, crate::AppContext::TEXTURE_2D_ARRAY, Vector2::new(width, height), layer_count, conf);
         texture.bind(context);
-        texture.config_texture_array(context, img, slice_dim, slice_count)?;
+        context.tex_image_3d_with_u8_array(
+            crate::AppContext::TEXTURE_2D_ARRAY,
+            0,
+            texture.configuration.format.get_internal_format() as i32,
+            width as i32,
+            height as i32,
+            layer_count as i32,
+            texture.configuration.format.get_format(),
+            texture.configuration.format.get_default_data_type(),
+            None,
+        ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTextureArray"))?;
+
+        Ok(texture)
+    }
+
+    /// This method fills whole layer with the image (or its part selected by unpack pixel storage modes).
+    fn upload_image_layer(&self, context: &B, layer: u32, img: &HtmlImageElement) -> SWGLResult<()> {
+        context.tex_sub_image_3d_with_html_image_element(
+            crate::AppContext::TEXTURE_2D_ARRAY,
+            0,
+            0,
+            0,
+            layer as i32,
+            self.layer_dim.x as i32,
+            self.layer_dim.y as i32,
+            1,
+            self.configuration.format.get_format(),
+            self.configuration.format.get_default_data_type(),
+            img,
+        ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTextureArray"))
+    }
+
+    /// This method creates texture with mipmap levels given from the largest one (level 0 has width x height pixels,
+    /// every next level has half size of the previous one rounded down, but at least 1 pixel). Mipmaps of the
+    /// configuration have to be TextureMipmaps::Provided, TEXTURE_MAX_LEVEL is limited to the last given level.
+    ///
+    /// ```text
+    /// let conf = TextureConfiguration { mipmaps: TextureMipmaps::Provided, ..TextureConfiguration::default() };
+    /// let texture = Texture2D::new_from_levels(&context, 4, 4, &[&level_4x4, &level_2x2, &level_1x1], conf)?;
+    /// ```
+    pub fn new_from_levels<T: PixelType>(context: &B, width: u32, height: u32, levels: &[&[T]], conf: TextureConfiguration) -> SWGLResult<Self> {
+        if conf.mipmaps != TextureMipmaps::Provided {
+            return Err(SWGLRuntimeError::new("Texture2D::MipmapsNotProvided"));
+        }
+        let max_level_count = 32 - width.max(height).max(1).leading_zeros() as usize;
+        if levels.is_empty() || levels.len() > max_level_count {
+            return Err(SWGLRuntimeError::new("Texture2D::InvalidMipmapLevelCount"));
+        }
+        for (level, pixels) in levels.iter().enumerate() {
+            let (level_width, level_height) = ((width >> level).max(1), (height >> level).max(1));
+            Self::check_data(&conf, pixels, level_width as usize * level_height as usize)?;
+        }
+        Self::check_configuration(context, &conf, true)?;
+        let texture = Self::create(context, crate::AppContext::TEXTURE_2D, Vector2::new(width, height), 1, conf);
+
+        texture.bind(context);
+        Self::config_unpack(context);
+        for (level, pixels) in levels.iter().enumerate() {
+            context.tex_image_2d_with_u8_array(
+                crate::AppContext::TEXTURE_2D,
+                level as i32,
+                texture.configuration.format.get_internal_format() as i32,
+                (width >> level).max(1) as i32,
+                (height >> level).max(1) as i32,
+                texture.configuration.format.get_format(),
+                T::get_gl_property_value(),
+                Some(interface::as_byte_slice(pixels)),
+            ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTexture"))?;
+        }
         texture.general_config(context);
+        texture.limit_max_level(context, levels.len() as u32 - 1);
 
         Ok(texture)
     }
 
-    fn general_config(&self, context: &crate::AppContext) {
-        context.tex_parameteri(
-            self.texture_type,
-            crate::AppContext::TEXTURE_WRAP_S,
-            self.configuration.wrap_x.get_gl_property_value() as i32,
-        );
-        context.tex_parameteri(
-            self.texture_type,
-            crate::AppContext::TEXTURE_WRAP_T,
-            self.configuration.wrap_y.get_gl_property_value() as i32,
-        );
+    /// This method creates texture from the first KTX2 file whose format can be used on the device, candidates
+    /// should contain the same texture encoded to different compressed formats (e.g. ASTC or ETC2 for mobiles and
+    /// S3TC for desktops). Compressed files are tried before not compressed ones, if none of them can be used,
+    /// the texture is loaded from the fallback image (so it ends up uncompressed).
+    /// Mipmaps of KTX2 textures are taken from the file (mipmaps of the configuration are ignored), min filter
+    /// sampling mipmaps is replaced by Nearest or Linear for files with only the base level. Format of not compressed
+    /// file replaces format of the configuration. KTX2 texture arrays create TEXTURE_2D_ARRAY textures.
+    ///
+    /// ```text
+    /// let conf = TextureConfiguration { min_filter: TextureFilter::LinearMipmapLinear, ..TextureConfiguration::default() };
+    /// let texture = Texture2D::from_compressed(&context, &[&astc, &etc2, &bc3], Some(&png_image), conf)?;
+    /// ```
+    pub fn from_compressed(
+        context: &B,
+        candidates: &[&Ktx2Texture],
+        fallback: Option<&HtmlImageElement>,
+        conf: TextureConfiguration,
+    ) -> SWGLResult<Self> {
+        if let Some(source) = Self::select_ktx2(context, candidates) {
+            return Self::new_from_ktx2(context, source, conf);
+        }
 
-        context.tex_parameteri(
-            self.texture_type,
-            crate::AppContext::TEXTURE_MIN_FILTER,
-            self.configuration.min_filter.get_gl_property_value() as i32,
-        );
+        match fallback {
+            Some(img) => Self::new_texture2d(context, img, conf),
+            None => Err(SWGLRuntimeError::new("Texture2D::NoSupportedFormat")),
+        }
+    }
 
-        context.tex_parameteri(
-            self.texture_type,
-            crate::AppContext::TEXTURE_MAG_FILTER,
-            self.configuration.mag_filter.get_gl_property_value() as i32,
-        );
-    } 
+    /// This method returns the first supported compressed candidate, otherwise the first not compressed one.
+    fn select_ktx2<'a>(context: &B, candidates: &[&'a Ktx2Texture]) -> Option<&'a Ktx2Texture> {
+        let compressed = candidates.iter().find(|source| {
+            source.get_compressed_format().is_some_and(|format| format.is_supported(context))
+        });
+        compressed.or_else(|| candidates.iter().find(|source| source.get_uncompressed_format().is_some())).copied()
+    }
+
+    fn new_from_ktx2(context: &B, source: &Ktx2Texture, mut conf: TextureConfiguration) -> SWGLResult<Self> {
+        let compressed_format = source.get_compressed_format();
+        if compressed_format.is_none() {
+            conf.format = source.get_uncompressed_format().ok_or(SWGLRuntimeError::new("Texture2D::UnsupportedFormat"))?;
+        }
+        if source.levels.len() > 1 {
+            conf.mipmaps = TextureMipmaps::Provided;
+        } else {
+            conf.mipmaps = TextureMipmaps::None;
+            conf.min_filter = conf.min_filter.without_mipmaps();
+        }
+
+        let layer_count = source.layer_count.max(1);
+        for (level, data) in source.levels.iter().enumerate() {
+            let (width, height) = source.get_level_size(level);
+            let image_size = match compressed_format {
+                Some(format) => format.get_image_size(width, height),
+                None => width as usize * height as usize * conf.format.get_pixel_size(),
+            };
+            if data.len() != image_size * layer_count as usize {
+                return Err(SWGLRuntimeError::new("Texture2D::InvalidDataSize"));
+            }
+        }
+        Self::check_configuration(context, &conf, true)?;
+
+        let texture_type = if source.layer_count > 0 {
+            if layer_count > context.get_parameter_i32(crate::AppContext::MAX_ARRAY_TEXTURE_LAYERS).max(0) as u32 {
+                return Err(SWGLRuntimeError::new("Texture2D::TooManyLayers"));
+            }
+            crate::AppContext::TEXTURE_2D_ARRAY
+        } else {
+            crate::AppContext::TEXTURE_2D
+        };
+        let mut texture = Self::create(context, texture_type, Vector2::new(source.width, source.height), layer_count, conf);
+        texture.compressed_format = compressed_format;
+
+        texture.bind(context);
+        Self::config_unpack(context);
+        for (level, data) in source.levels.iter().enumerate() {
+            let (width, height) = source.get_level_size(level);
+            texture.upload_ktx2_level(context, level as i32, width as i32, height as i32, data)?;
+        }
+        texture.general_config(context);
+        texture.limit_max_level(context, source.levels.len() as u32 - 1);
+
+        Ok(texture)
+    }
+
+    /// This method uploads all layers of mipmap level in format of the texture.
+    fn upload_ktx2_level(&self, context: &B, level: i32, width: i32, height: i32, data: &[u8]) -> SWGLResult<()> {
+        let is_array = self.texture_type == crate::AppContext::TEXTURE_2D_ARRAY;
+        match self.compressed_format {
+            Some(format) => {
+                let internal_format = format.get_gl_property_value().ok_or(SWGLRuntimeError::new("Texture2D::UnsupportedFormat"))?;
+                if is_array {
+                    context.compressed_tex_image_3d_with_u8_array(
+                        self.texture_type,
+                        level,
+                        internal_format,
+                        width,
+                        height,
+                        self.layer_count as i32,
+                        data,
+                    );
+                } else {
+                    context.compressed_tex_image_2d_with_u8_array(self.texture_type, level, internal_format, width, height, data);
+                }
+                Ok(())
+            }
+            None if is_array => context.tex_image_3d_with_u8_array(
+                self.texture_type,
+                level,
+                self.configuration.format.get_internal_format() as i32,
+                width,
+                height,
+                self.layer_count as i32,
+                self.configuration.format.get_format(),
+                self.configuration.format.get_default_data_type(),
+                Some(data),
+            ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTextureArray")),
+            None => context.tex_image_2d_with_u8_array(
+                self.texture_type,
+                level,
+                self.configuration.format.get_internal_format() as i32,
+                width,
+                height,
+                self.configuration.format.get_format(),
+                self.configuration.format.get_default_data_type(),
+                Some(data),
+            ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTexture")),
+        }
+    }
+
+    /// Levels after the last uploaded one don't exist, so they can't be sampled.
+    fn limit_max_level(&self, context: &B, last_level: u32) {
+        if last_level < self.configuration.max_level {
+            context.tex_parameteri(self.texture_type, crate::AppContext::TEXTURE_MAX_LEVEL, last_level as i32);
+        }
+    }
+
+    fn create(context: &B, texture_type: u32, layer_dim: Vector2<u32>, layer_count: u32, conf: TextureConfiguration) -> Self {
+        Self {
+            texture: context.create_texture(),
+            texture_dim: layer_dim,
+            layer_dim,
+            layer_count,
+            configuration: conf,
+            compressed_format: None,
+            texture_type,
+            context: context.clone(),
+        }
+    }
+
+    fn check_image_format(context: &B, conf: &TextureConfiguration) -> SWGLResult<()> {
+        if conf.format.is_depth() {
+            return Err(SWGLRuntimeError::new("Texture2D::UnsupportedFormat"));
+        }
+        Self::check_configuration(context, conf, false)
+    }
+
+    /// Only new_from_levels uploads mipmap levels, so other constructors reject TextureMipmaps::Provided.
+    fn check_configuration(context: &B, conf: &TextureConfiguration, levels_provided: bool) -> SWGLResult<()> {
+        if conf.mipmaps == TextureMipmaps::Provided && !levels_provided {
+            return Err(SWGLRuntimeError::new("Texture2D::MissingMipmapLevels"));
+        }
+        conf.check_support(context, false)
+    }
+
+    /// This method checks type and length of pixels, pixel_count is number of pixels of all layers.
+    fn check_data<T: PixelType>(conf: &TextureConfiguration, pixels: &[T], pixel_count: usize) -> SWGLResult<()> {
+        if !conf.format.is_valid_data_type(T::get_gl_property_value()) {
+            return Err(SWGLRuntimeError::new("Texture2D::InvalidPixelType"));
+        }
+        if pixels.len() != pixel_count * conf.format.get_channel_count() {
+            return Err(SWGLRuntimeError::new("Texture2D::InvalidDataSize"));
+        }
+        Ok(())
+    }
+
+    /// Rows of pixel data are tightly packed (default alignment of 4 bytes breaks e.g. RGB textures with odd width).
+    fn config_unpack(context: &B) {
+        context.pixel_storei(crate::AppContext::UNPACK_ALIGNMENT, 1);
+    }
+
+    fn update_mipmaps(&self, context: &B) {
+        if self.configuration.mipmaps == TextureMipmaps::Generate {
+            context.generate_mipmap(self.texture_type);
+        }
+    }
+
+    fn general_config(&self, context: &B) {
+        self.configuration.apply(context, self.texture_type);
+    }
 
     // 2d texture
-    fn config_texture_2d(&self, context: &crate::AppContext, img: &HtmlImageElement) -> SWGLResult<()> {
+    fn config_texture_2d(&self, context: &B, img: &HtmlImageElement) -> SWGLResult<()> {
 
-        context.tex_image_2d_with_u32_and_u32_and_html_image_element(
+        context.tex_image_2d_with_html_image_element(
             crate::AppContext::TEXTURE_2D,
             0,
-            self.configuration.chanels.get_gl_property_value() as i32,
-            self.configuration.chanels.get_gl_property_value(),
-            crate::AppContext::UNSIGNED_BYTE,
+            self.configuration.format.get_internal_format() as i32,
+            self.configuration.format.get_format(),
+            self.configuration.format.get_default_data_type(),
             img,
         ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTexture"))?;
 
-        context.generate_mipmap(crate::AppContext::TEXTURE_2D);
-
         Ok(())
     }
 
     // texture array
-    fn config_texture_array(&self, context: &crate::AppContext, img: &HtmlImageElement, slice_dim: Vector2<f32>, slice_count: u32) -> SWGLResult<()> {
+    fn config_texture_array(&self, context: &B, img: &HtmlImageElement, slice_dim: Vector2<f32>, slice_count: u32) -> SWGLResult<()> {
 
         context.tex_image_3d_with_html_image_element(
             crate::AppContext::TEXTURE_2D_ARRAY,
             0,                                                         // mipmap level
-            self.configuration.chanels.get_gl_property_value() as i32, // gpu texel format
+            self.configuration.format.get_internal_format() as i32,    // gpu texel format
             slice_dim.x as i32,                                        // texture file pixel width 
             slice_dim.y as i32,                                        // height file pixel height
             slice_count as i32,                                        // depth ( how many slices )
             0,                                                         // border
-            self.configuration.chanels.get_gl_property_value(),        // cpu pixel format
-            crate::AppContext::UNSIGNED_BYTE,                                         // cpu pixel coord type
+            self.configuration.format.get_format(),                    // cpu pixel format
+            self.configuration.format.get_default_data_type(),         // cpu pixel coord type
             img,                                                       // pixel data
         ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotConfigTextureArray"))?;
 
-        context.generate_mipmap(crate::AppContext::TEXTURE_2D_ARRAY);
+        Ok(())
+    }
+
+    /// This method replaces pixels of rectangle of TEXTURE_2D texture (e.g. painting into destructible terrain mask).
+    /// Position and size are given in pixels, pixels are stored like in new_from_data and their type has to be valid
+    /// for the format of the texture.
+    /// Mipmaps are regenerated if the configuration generates them, so textures updated every frame should use
+    /// TextureMipmaps::None.
+    pub fn update_region<T: PixelType>(&self, context: &B, x: u32, y: u32, width: u32, height: u32, pixels: &[T]) -> SWGLResult<()> {
+        if self.texture_type != crate::AppContext::TEXTURE_2D {
+            return Err(SWGLRuntimeError::new("Texture2D::UnsupportedTextureType"));
+        }
+        self.check_region(0, x, y, width, height, pixels)?;
+
+        self.bind(context);
+        Self::config_unpack(context);
+        context.tex_sub_image_2d_with_u8_array(
+            crate::AppContext::TEXTURE_2D,
+            0,
+            x as i32,
+            y as i32,
+            width as i32,
+            height as i32,
+            self.configuration.format.get_format(),
+            T::get_gl_property_value(),
+            interface::as_byte_slice(pixels),
+        ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotUpdateRegion"))?;
+        self.update_mipmaps(context);
+
+        Ok(())
+    }
+
+    /// This method replaces pixels of rectangle of one layer of TEXTURE_2D_ARRAY texture (see update_region).
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_layer_region<T: PixelType>(
+        &self,
+        context: &B,
+        layer: u32,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        pixels: &[T],
+    ) -> SWGLResult<()> {
+        if self.texture_type != crate::AppContext::TEXTURE_2D_ARRAY {
+            return Err(SWGLRuntimeError::new("Texture2D::UnsupportedTextureType"));
+        }
+        self.check_region(layer, x, y, width, height, pixels)?;
+
+        self.bind(context);
+        Self::config_unpack(context);
+        context.tex_sub_image_3d_with_u8_array(
+            crate::AppContext::TEXTURE_2D_ARRAY,
+            0,
+            x as i32,
+            y as i32,
+            layer as i32,
+            width as i32,
+            height as i32,
+            1,
+            self.configuration.format.get_format(),
+            T::get_gl_property_value(),
+            interface::as_byte_slice(pixels),
+        ).ok().ok_or(SWGLRuntimeError::new("Texture2D::CannotUpdateRegion"))?;
+        self.update_mipmaps(context);
+
+        Ok(())
+    }
+
+    fn check_region<T: PixelType>(&self, layer: u32, x: u32, y: u32, width: u32, height: u32, pixels: &[T]) -> SWGLResult<()> {
+        if self.compressed_format.is_some() {
+            return Err(SWGLRuntimeError::new("Texture2D::CompressedTexture"));
+        }
+        if layer >= self.layer_count
+            || x as u64 + width as u64 > self.layer_dim.x as u64
+            || y as u64 + height as u64 > self.layer_dim.y as u64
+        {
+            return Err(SWGLRuntimeError::new("Texture2D::RegionOutOfBounds"));
+        }
+        Self::check_data(&self.configuration, pixels, width as usize * height as usize)
+    }
 
+    /// This method regenerates mipmaps from the base level (e.g. after rendering into texture of RenderTarget).
+    pub fn generate_mipmaps(&self, context: &B) -> SWGLResult<()> {
+        if self.configuration.mipmaps != TextureMipmaps::Generate {
+            return Err(SWGLRuntimeError::new("Texture2D::MipmapsNotGenerated"));
+        }
+        self.bind(context);
+        context.generate_mipmap(self.texture_type);
         Ok(())
     }
 
-    pub fn bind(&self, context: &crate::AppContext) {
+    /// This method deletes the texture immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    pub fn bind(&self, context: &B) {
         context.bind_texture(self.texture_type, self.texture.as_ref());
     }
 
-    pub fn active(&self, context: &crate::AppContext, location: u32) {
+    pub fn active(&self, context: &B, location: u32) {
         context.active_texture(crate::AppContext::TEXTURE0 + location);
         self.bind(context);
     }
@@ -125,14 +663,167 @@ impl Texture2D {
     // -----------------------------------
 
     /// This method returns object that can be used directly inside WebGL low level calls.
-    pub fn get_raw_id(&self) -> &std::option::Option<WebGlTexture> {
+    pub fn get_raw_id(&self) -> &std::option::Option<B::Texture> {
         &self.texture
     }
 
+    /// This method returns GL target of the texture (TEXTURE_2D or TEXTURE_2D_ARRAY).
+    pub fn get_texture_type(&self) -> u32 {
+        self.texture_type
+    }
+
     pub fn get_size(&self) -> (f32, f32) {
         (self.texture_dim.x as f32, self.texture_dim.y as f32)
     }
+
+    /// This method returns size of single layer in pixels (the same as get_size for TEXTURE_2D textures).
+    pub fn get_layer_size(&self) -> (u32, u32) {
+        (self.layer_dim.x, self.layer_dim.y)
+    }
+
+    /// This method returns number of layers of TEXTURE_2D_ARRAY texture (1 for TEXTURE_2D textures).
+    pub fn get_layer_count(&self) -> u32 {
+        self.layer_count
+    }
+
+    /// This method returns format of texels, it is meaningless for compressed textures (see get_compressed_format).
+    pub fn get_format(&self) -> TextureFormat {
+        self.configuration.format
+    }
+
+    /// This method returns format of texture created from compressed KTX2 file (see from_compressed), regions of
+    /// such textures can't be updated.
+    pub fn get_compressed_format(&self) -> Option<CompressedFormat> {
+        self.compressed_format
+    }
 }
 
+impl<B: GlBackend> Drop for Texture2D<B> {
+    fn drop(&mut self) {
+        if self.texture.is_some() {
+            self.context.delete_texture(self.texture.as_ref());
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::texture_config::{TextureFilter, TextureWrap};
+    use super::super::super::backend::recording_backend::{GlCall, RecordingBackend};
+
+    fn configuration() -> TextureConfiguration {
+        TextureConfiguration::new(
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::Linear,
+            TextureFilter::Linear,
+            TextureFormat::Rgba8,
+        )
+    }
+
+    fn ktx2(vk_format: u32, layer_count: u32, level: Vec<u8>) -> Ktx2Texture {
+        Ktx2Texture {
+            vk_format,
+            width: 4,
+            height: 4,
+            layer_count,
+            levels: vec![level],
+        }
+    }
+
+    #[test]
+    fn from_compressed_prefers_supported_compressed_candidate() {
+        let context = RecordingBackend::new();
+        // ASTC 4x4, BC3 sRGB and RGBA8 array
+        let astc = ktx2(157, 0, vec![0; 16]);
+        let bc3 = ktx2(138, 0, vec![0; 16]);
+        let rgba = ktx2(37, 2, vec![0; 4 * 4 * 4 * 2]);
+        context.set_extension_supported("WEBGL_compressed_texture_astc", false);
+
+        let texture = Texture2D::from_compressed(&context, &[&rgba, &astc, &bc3], None, configuration()).unwrap();
+        assert_eq!(texture.get_compressed_format(), Some(CompressedFormat::Dxt5 { srgb: true }));
+        let uploads: Vec<_> = context.calls().into_iter().filter(|c| matches!(c, GlCall::CompressedTexImage2D { .. })).collect();
+        assert_eq!(
+            uploads,
+            vec![GlCall::CompressedTexImage2D {
+                target: crate::AppContext::TEXTURE_2D,
+                level: 0,
+                internal_format: interface::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT,
+                width: 4,
+                height: 4,
+                data_size: 16,
+            }]
+        );
+
+        drop(texture);
+        assert_eq!(context.live_object_count(), 0);
+    }
+
+    #[test]
+    fn from_compressed_falls_back_to_uncompressed_candidate() {
+        let context = RecordingBackend::new();
+        let astc = ktx2(157, 0, vec![0; 16]);
+        let bc3 = ktx2(137, 0, vec![0; 16]);
+        let rgba = ktx2(37, 2, vec![0; 4 * 4 * 4 * 2]);
+        context.set_extension_supported("WEBGL_compressed_texture_astc", false);
+        context.set_extension_supported("WEBGL_compressed_texture_s3tc", false);
 
+        let texture = Texture2D::from_compressed(&context, &[&astc, &bc3, &rgba], None, configuration()).unwrap();
+        assert_eq!(texture.get_compressed_format(), None);
+        assert!(context.calls().iter().any(|c| matches!(
+            c,
+            GlCall::TexImage3DWithData { target: crate::AppContext::TEXTURE_2D_ARRAY, width: 4, height: 4, depth: 2, .. }
+        )));
+        assert!(!context.calls().iter().any(|c| matches!(c, GlCall::CompressedTexImage2D { .. } | GlCall::CompressedTexImage3D { .. })));
 
+        drop(texture);
+        assert_eq!(context.live_object_count(), 0);
+    }
+
+    #[test]
+    fn from_compressed_samples_single_level_file_without_mipmaps() {
+        let context = RecordingBackend::new();
+        let bc3 = ktx2(137, 0, vec![0; 16]);
+
+        let texture = Texture2D::from_compressed(&context, &[&bc3], None, TextureConfiguration::default()).unwrap();
+        assert!(context.calls().contains(&GlCall::TexParameteri {
+            target: crate::AppContext::TEXTURE_2D,
+            pname: crate::AppContext::TEXTURE_MIN_FILTER,
+            param: crate::AppContext::LINEAR as i32,
+        }));
+        assert!(!context.calls().iter().any(|c| matches!(c, GlCall::GenerateMipmap { .. })));
+        drop(texture);
+
+        // files with mipmaps keep the filter
+        let mut with_levels = ktx2(137, 0, vec![0; 16]);
+        with_levels.levels.extend(vec![vec![0; 16]; 2]);
+        context.clear_calls();
+        let _texture = Texture2D::from_compressed(&context, &[&with_levels], None, TextureConfiguration::default()).unwrap();
+        assert!(context.calls().contains(&GlCall::TexParameteri {
+            target: crate::AppContext::TEXTURE_2D,
+            pname: crate::AppContext::TEXTURE_MIN_FILTER,
+            param: crate::AppContext::LINEAR_MIPMAP_LINEAR as i32,
+        }));
+    }
+
+    #[test]
+    fn from_compressed_without_usable_candidate_and_fallback_fails() {
+        let context = RecordingBackend::new();
+        let bc3 = ktx2(137, 0, vec![0; 16]);
+        // BC7 isn't supported by CompressedFormat
+        let bc7 = ktx2(145, 0, vec![0; 16]);
+        context.set_extension_supported("WEBGL_compressed_texture_s3tc", false);
+
+        // missing candidate leads to the fallback image (HtmlImageElement can't be created in native tests)
+        assert!(Texture2D::select_ktx2(&context, &[&bc3, &bc7]).is_none());
+        let error = Texture2D::from_compressed(&context, &[&bc3, &bc7], None, configuration()).err().unwrap();
+        assert_eq!(error.message, "Texture2D::NoSupportedFormat");
+        assert_eq!(context.live_object_count(), 0);
+
+        context.set_extension_supported("WEBGL_compressed_texture_s3tc", true);
+        assert_eq!(Texture2D::select_ktx2(&context, &[&bc7, &bc3]), Some(&bc3));
+    }
+}
@@ -0,0 +1,385 @@
+use web_sys::HtmlImageElement;
+use super::cube_lut::CubeLut;
+use super::texture_config::{PixelType, TextureConfiguration, TextureFilter, TextureFormat, TextureMipmaps, TextureWrap};
+use super::super::backend::interface::{self, GlBackend};
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+
+// --------------------------------------------------------------------------------------------------
+
+/// TEXTURE_3D texture (e.g. color grading LUT or volume noise), it is sampled with sampler3D in shaders.
+/// Linear filters interpolate between slices too (trilinear sampling).
+/// GL texture is deleted when this object is dropped.
+///
+/// ```text
+/// let lut = Texture3D::new_lut_from_strip(&context, &lut_image, Texture3D::lut_configuration(TextureFormat::Rgba8))?;
+/// lut.active(&context, 1);
+/// program.set1i(&context, "u_lut", 1)?;
+/// ```
+pub struct Texture3D<B: GlBackend = crate::AppContext> {
+    texture: B::Texture,
+    width: u32,
+    height: u32,
+    depth: u32,
+    configuration: TextureConfiguration,
+    context: B,
+}
+
+// --------------------------------------------------------------------------------------------------
+
+impl<B: GlBackend> Texture3D<B> {
+    /// This method returns configuration suited for lookup tables: linear filters, no mipmaps and all wraps
+    /// clamped to edge.
+    pub fn lut_configuration(format: TextureFormat) -> TextureConfiguration {
+        TextureConfiguration::new(
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::Linear,
+            TextureFilter::Linear,
+            format,
+        )
+    }
+
+    /// This method creates texture from slices stored one after another (every slice is stored row by row starting
+    /// from the top-left corner). Length of pixels has to be width * height * depth * number of channels of the
+    /// format and their type has to be valid for the format.
+    pub fn new_from_data<T: PixelType>(
+        context: &B,
+        width: u32,
+        height: u32,
+        depth: u32,
+        pixels: &[T],
+        conf: TextureConfiguration,
+    ) -> SWGLResult<Self> {
+        Self::check_data(&conf, pixels, width as usize * height as usize * depth as usize)?;
+        let texture = Self::allocate(context, width, height, depth, conf, Some(interface::as_byte_slice(pixels)), T::get_gl_property_value())?;
+        texture.update_mipmaps(context);
+        texture.configuration.apply(context, crate::AppContext::TEXTURE_3D);
+
+        Ok(texture)
+    }
+
+    /// This method creates size x size x size LUT from strip image with size slices placed side by side
+    /// (e.g. 1024x32 image for 32^3 LUT). Red grows along x inside every slice, green grows from the top row
+    /// and blue selects the slice from the left.
+    pub fn new_lut_from_strip(context: &B, img: &HtmlImageElement, conf: TextureConfiguration) -> SWGLResult<Self> {
+        let size = img.height();
+        if size < 2 || size.checked_mul(size) != Some(img.width()) {
+            return Err(SWGLRuntimeError::new("Texture3D::InvalidLutStrip"));
+        }
+        if conf.format.is_depth() {
+            return Err(SWGLRuntimeError::new("Texture3D::UnsupportedFormat"));
+        }
+
+        let texture = Self::allocate(context, size, size, size, conf, None, 0)?;
+        texture.upload_strip(context, img.width(), |slice| {
+            context.tex_sub_image_3d_with_html_image_element(
+                crate::AppContext::TEXTURE_3D,
+                0,
+                0,
+                0,
+                slice as i32,
+                size as i32,
+                size as i32,
+                1,
+                texture.configuration.format.get_format(),
+                texture.configuration.format.get_default_data_type(),
+                img,
+            ).ok().ok_or(SWGLRuntimeError::new("Texture3D::CannotConfigTexture"))
+        })?;
+
+        texture.update_mipmaps(context);
+        texture.configuration.apply(context, crate::AppContext::TEXTURE_3D);
+
+        Ok(texture)
+    }
+
+    /// This method creates LUT from parsed .cube file, format of the configuration has to store RGBA floats
+    /// (Rgba16F is filterable without extensions). Domain of the table is not applied by the texture.
+    pub fn new_lut_from_cube(context: &B, lut: &CubeLut, conf: TextureConfiguration) -> SWGLResult<Self> {
+        Self::new_from_data(context, lut.size, lut.size, lut.size, &lut.get_rgba_data(), conf)
+    }
+
+    /// This method replaces pixels of box inside the texture (e.g. animated volume data), pixels are stored like in
+    /// new_from_data. Mipmaps are regenerated if the configuration generates them.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_region<T: PixelType>(
+        &self,
+        context: &B,
+        x: u32,
+        y: u32,
+        z: u32,
+        width: u32,
+        height: u32,
+        depth: u32,
+        pixels: &[T],
+    ) -> SWGLResult<()> {
+        if x as u64 + width as u64 > self.width as u64
+            || y as u64 + height as u64 > self.height as u64
+            || z as u64 + depth as u64 > self.depth as u64
+        {
+            return Err(SWGLRuntimeError::new("Texture3D::RegionOutOfBounds"));
+        }
+        Self::check_data(&self.configuration, pixels, width as usize * height as usize * depth as usize)?;
+
+        self.bind(context);
+        context.pixel_storei(crate::AppContext::UNPACK_ALIGNMENT, 1);
+        context.tex_sub_image_3d_with_u8_array(
+            crate::AppContext::TEXTURE_3D,
+            0,
+            x as i32,
+            y as i32,
+            z as i32,
+            width as i32,
+            height as i32,
+            depth as i32,
+            self.configuration.format.get_format(),
+            T::get_gl_property_value(),
+            interface::as_byte_slice(pixels),
+        ).ok().ok_or(SWGLRuntimeError::new("Texture3D::CannotUpdateRegion"))?;
+        self.update_mipmaps(context);
+
+        Ok(())
+    }
+
+    /// This method creates and binds texture with storage filled with pixels (None leaves it uninitialized).
+    fn allocate(
+        context: &B,
+        width: u32,
+        height: u32,
+        depth: u32,
+        conf: TextureConfiguration,
+        pixels: Option<&[u8]>,
+        data_type: u32,
+    ) -> SWGLResult<Self> {
+        if conf.mipmaps == TextureMipmaps::Provided {
+            return Err(SWGLRuntimeError::new("Texture3D::MissingMipmapLevels"));
+        }
+        conf.check_support(context, false)?;
+        let max_size = context.get_parameter_i32(crate::AppContext::MAX_3D_TEXTURE_SIZE).max(0) as u32;
+        if width.max(height).max(depth) > max_size {
+            return Err(SWGLRuntimeError::new("Texture3D::TooLarge"));
+        }
+
+        let texture = match context.create_texture() {
+            Some(texture) => Self {
+                texture,
+                width,
+                height,
+                depth,
+                configuration: conf,
+                context: context.clone(),
+            },
+            None => return Err(SWGLRuntimeError::new("Texture3D::CreationError")),
+        };
+
+        texture.bind(context);
+        context.pixel_storei(crate::AppContext::UNPACK_ALIGNMENT, 1);
+        context.tex_image_3d_with_u8_array(
+            crate::AppContext::TEXTURE_3D,
+            0,
+            texture.configuration.format.get_internal_format() as i32,
+            width as i32,
+            height as i32,
+            depth as i32,
+            texture.configuration.format.get_format(),
+            if pixels.is_some() { data_type } else { texture.configuration.format.get_default_data_type() },
+            pixels,
+        ).ok().ok_or(SWGLRuntimeError::new("Texture3D::CannotConfigTexture"))?;
+
+        Ok(texture)
+    }
+
+    /// This method uploads every slice of strip (slices placed side by side) with upload_slice, which reads the
+    /// first slice of the strip. Pixel store parameters are reset even if an upload fails.
+    fn upload_strip(&self, context: &B, strip_width: u32, upload_slice: impl Fn(u32) -> SWGLResult<()>) -> SWGLResult<()> {
+        context.pixel_storei(crate::AppContext::UNPACK_ROW_LENGTH, strip_width as i32);
+        let mut result = Ok(());
+        for slice in 0..self.depth {
+            context.pixel_storei(crate::AppContext::UNPACK_SKIP_PIXELS, (slice * self.width) as i32);
+            result = upload_slice(slice);
+            if result.is_err() {
+                break;
+            }
+        }
+        // other uploads read whole images
+        context.pixel_storei(crate::AppContext::UNPACK_ROW_LENGTH, 0);
+        context.pixel_storei(crate::AppContext::UNPACK_SKIP_PIXELS, 0);
+        result
+    }
+
+    /// This method checks type and length of pixels.
+    fn check_data<T: PixelType>(conf: &TextureConfiguration, pixels: &[T], pixel_count: usize) -> SWGLResult<()> {
+        if !conf.format.is_valid_data_type(T::get_gl_property_value()) {
+            return Err(SWGLRuntimeError::new("Texture3D::InvalidPixelType"));
+        }
+        if pixels.len() != pixel_count * conf.format.get_channel_count() {
+            return Err(SWGLRuntimeError::new("Texture3D::InvalidDataSize"));
+        }
+        Ok(())
+    }
+
+    fn update_mipmaps(&self, context: &B) {
+        if self.configuration.mipmaps == TextureMipmaps::Generate {
+            context.generate_mipmap(crate::AppContext::TEXTURE_3D);
+        }
+    }
+
+    /// This method deletes the texture immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    pub fn bind(&self, context: &B) {
+        context.bind_texture(crate::AppContext::TEXTURE_3D, Some(&self.texture));
+    }
+
+    pub fn active(&self, context: &B, location: u32) {
+        context.active_texture(crate::AppContext::TEXTURE0 + location);
+        self.bind(context);
+    }
+
+    // -----------------------------------
+
+    /// This method returns object that can be used directly inside WebGL low level calls.
+    pub fn get_raw_id(&self) -> &B::Texture {
+        &self.texture
+    }
+
+    /// This method returns width, height and depth in pixels.
+    pub fn get_size(&self) -> (u32, u32, u32) {
+        (self.width, self.height, self.depth)
+    }
+}
+
+impl<B: GlBackend> Drop for Texture3D<B> {
+    fn drop(&mut self) {
+        self.context.delete_texture(Some(&self.texture));
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::backend::recording_backend::{GlCall, RecordingBackend};
+
+    /// This function returns pixel store and 3D upload calls (x_offset and z_offset of uploads).
+    fn upload_calls(context: &RecordingBackend) -> Vec<String> {
+        context
+            .calls()
+            .iter()
+            .filter_map(|call| match call {
+                GlCall::PixelStorei { pname: crate::AppContext::UNPACK_ROW_LENGTH, param } => Some(format!("row_length {}", param)),
+                GlCall::PixelStorei { pname: crate::AppContext::UNPACK_SKIP_PIXELS, param } => Some(format!("skip_pixels {}", param)),
+                GlCall::TexSubImage3D { x_offset, z_offset, .. } => Some(format!("upload {} {}", x_offset, z_offset)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn empty_lut(context: &RecordingBackend, size: u32) -> Texture3D<RecordingBackend> {
+        Texture3D::allocate(context, size, size, size, Texture3D::<RecordingBackend>::lut_configuration(TextureFormat::Rgba8), None, 0)
+            .unwrap()
+    }
+
+    fn upload_slice(context: &RecordingBackend, slice: u32) -> SWGLResult<()> {
+        context
+            .tex_sub_image_3d_with_u8_array(
+                crate::AppContext::TEXTURE_3D,
+                0,
+                0,
+                0,
+                slice as i32,
+                2,
+                2,
+                1,
+                crate::AppContext::RGBA,
+                crate::AppContext::UNSIGNED_BYTE,
+                &[0u8; 16],
+            )
+            .ok()
+            .ok_or(SWGLRuntimeError::new("Texture3D::CannotConfigTexture"))
+    }
+
+    #[test]
+    fn strip_slices_are_read_with_skipped_pixels_and_store_is_reset() {
+        let context = RecordingBackend::new();
+        let texture = empty_lut(&context, 2);
+        context.clear_calls();
+
+        texture.upload_strip(&context, 4, |slice| upload_slice(&context, slice)).unwrap();
+        assert_eq!(
+            upload_calls(&context),
+            vec![
+                "row_length 4",
+                "skip_pixels 0",
+                "upload 0 0",
+                "skip_pixels 2",
+                "upload 0 1",
+                "row_length 0",
+                "skip_pixels 0",
+            ]
+        );
+        assert!(context.errors().is_empty());
+    }
+
+    #[test]
+    fn failed_strip_upload_resets_pixel_store() {
+        let context = RecordingBackend::new();
+        let texture = empty_lut(&context, 3);
+        context.clear_calls();
+
+        let result = texture.upload_strip(&context, 9, |slice| match slice {
+            1 => Err(SWGLRuntimeError::new("Texture3D::CannotConfigTexture")),
+            _ => upload_slice(&context, slice),
+        });
+        assert_eq!(result.unwrap_err().message, "Texture3D::CannotConfigTexture");
+        assert_eq!(
+            upload_calls(&context),
+            vec!["row_length 9", "skip_pixels 0", "upload 0 0", "skip_pixels 3", "row_length 0", "skip_pixels 0"]
+        );
+    }
+
+    #[test]
+    fn update_region_checks_bounds_and_data_size() {
+        let context = RecordingBackend::new();
+        let conf = Texture3D::<RecordingBackend>::lut_configuration(TextureFormat::Rgba8);
+        let texture = Texture3D::new_from_data(&context, 2, 2, 2, &[0u8; 2 * 2 * 2 * 4], conf).unwrap();
+        assert_eq!(texture.get_size(), (2, 2, 2));
+        context.clear_calls();
+
+        let error = texture.update_region(&context, 1, 0, 0, 2, 1, 1, &[0u8; 8]).unwrap_err();
+        assert_eq!(error.message, "Texture3D::RegionOutOfBounds");
+        let error = texture.update_region(&context, 0, 0, 1, 1, 1, u32::MAX, &[0u8; 4]).unwrap_err();
+        assert_eq!(error.message, "Texture3D::RegionOutOfBounds");
+        let error = texture.update_region(&context, 0, 0, 1, 2, 2, 1, &[0u8; 15]).unwrap_err();
+        assert_eq!(error.message, "Texture3D::InvalidDataSize");
+        let error = texture.update_region(&context, 0, 0, 0, 1, 1, 1, &[0.0f32; 4]).unwrap_err();
+        assert_eq!(error.message, "Texture3D::InvalidPixelType");
+        // rejected regions don't touch the texture
+        assert!(context.calls().is_empty());
+
+        texture.update_region(&context, 0, 0, 1, 2, 2, 1, &[0u8; 16]).unwrap();
+        assert!(context.calls().contains(&GlCall::TexSubImage3D {
+            target: crate::AppContext::TEXTURE_3D,
+            level: 0,
+            x_offset: 0,
+            y_offset: 0,
+            z_offset: 1,
+            width: 2,
+            height: 2,
+            depth: 1,
+            format: crate::AppContext::RGBA,
+            data_type: crate::AppContext::UNSIGNED_BYTE,
+            data_size: 16,
+        }));
+    }
+
+    #[test]
+    fn new_from_data_rejects_invalid_data_size() {
+        let context = RecordingBackend::new();
+        let conf = Texture3D::<RecordingBackend>::lut_configuration(TextureFormat::Rgba8);
+        let error = Texture3D::new_from_data(&context, 2, 2, 2, &[0u8; 31], conf).err().unwrap();
+        assert_eq!(error.message, "Texture3D::InvalidDataSize");
+        assert_eq!(context.live_object_count(), 0);
+    }
+}
@@ -1,3 +1,5 @@
+use super::super::backend::interface::{self, GlBackend};
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
 use super::super::super::global_tools::vector2::Vector2;
 
 // --------------------------------------------------------------------------------------------------
@@ -49,6 +51,25 @@ impl TextureFilter {
             Self::LinearMipmapLinear => crate::AppContext::LINEAR_MIPMAP_LINEAR,
         }
     }
+
+    /// This method returns true if the filter samples mipmap levels (texture needs mipmaps to be complete).
+    pub fn uses_mipmaps(&self) -> bool {
+        !matches!(self, Self::Nearest | Self::Linear)
+    }
+
+    /// This method returns filter which samples only the base level the same way as this filter samples texels
+    /// inside a level (e.g. Linear for LinearMipmapNearest).
+    pub fn without_mipmaps(&self) -> Self {
+        match self {
+            Self::Nearest | Self::NearestMipmapLinear | Self::NearestMipmapNearest => Self::Nearest,
+            Self::Linear | Self::LinearMipmapNearest | Self::LinearMipmapLinear => Self::Linear,
+        }
+    }
+
+    /// This method returns true if the filter interpolates between texels or mipmap levels.
+    pub fn is_linear(&self) -> bool {
+        !matches!(self, Self::Nearest | Self::NearestMipmapNearest)
+    }
 }
 
 // --------------------------------------------------------------------------------------------------
@@ -72,6 +93,7 @@ impl TextureWrap {
 
 // --------------------------------------------------------------------------------------------------
 
+/// Shorthand for the most common 8-bit formats (see TextureFormat).
 #[derive(Debug, Clone, Copy)]
 pub enum TextureChannels {
     Rgb,
@@ -87,40 +109,747 @@ impl TextureChannels {
     }
 }
 
+impl From<TextureChannels> for TextureFormat {
+    fn from(chanels: TextureChannels) -> Self {
+        match chanels {
+            TextureChannels::Rgb => Self::Rgb8,
+            TextureChannels::Rgba => Self::Rgba8,
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+/// Format of texels stored on GPU together with format of uploaded pixels.
+/// Float formats can be rendered into only with EXT_color_buffer_float extension, 32-bit float formats can be
+/// filtered linearly only with OES_texture_float_linear extension and depth formats can't be filtered linearly
+/// at all (see TextureConfiguration::check_support).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureFormat {
+    R8,
+    Rg8,
+    Rgb8,
+    Rgba8,
+    /// Colors are converted from sRGB to linear space when sampled.
+    Srgb8Alpha8,
+    R16F,
+    Rg16F,
+    Rgba16F,
+    R32F,
+    Rg32F,
+    Rgba32F,
+    Depth24Stencil8,
+    DepthComponent32F,
+}
+
+impl TextureFormat {
+    pub fn get_internal_format(&self) -> u32 {
+        match self {
+            Self::R8 => crate::AppContext::R8,
+            Self::Rg8 => crate::AppContext::RG8,
+            Self::Rgb8 => crate::AppContext::RGB8,
+            Self::Rgba8 => crate::AppContext::RGBA8,
+            Self::Srgb8Alpha8 => crate::AppContext::SRGB8_ALPHA8,
+            Self::R16F => crate::AppContext::R16F,
+            Self::Rg16F => crate::AppContext::RG16F,
+            Self::Rgba16F => crate::AppContext::RGBA16F,
+            Self::R32F => crate::AppContext::R32F,
+            Self::Rg32F => crate::AppContext::RG32F,
+            Self::Rgba32F => crate::AppContext::RGBA32F,
+            Self::Depth24Stencil8 => crate::AppContext::DEPTH24_STENCIL8,
+            Self::DepthComponent32F => crate::AppContext::DEPTH_COMPONENT32F,
+        }
+    }
+
+    /// This method returns format of uploaded pixels (which channels they contain).
+    pub fn get_format(&self) -> u32 {
+        match self {
+            Self::R8 | Self::R16F | Self::R32F => crate::AppContext::RED,
+            Self::Rg8 | Self::Rg16F | Self::Rg32F => crate::AppContext::RG,
+            Self::Rgb8 => crate::AppContext::RGB,
+            Self::Rgba8 | Self::Srgb8Alpha8 | Self::Rgba16F | Self::Rgba32F => crate::AppContext::RGBA,
+            Self::Depth24Stencil8 => crate::AppContext::DEPTH_STENCIL,
+            Self::DepthComponent32F => crate::AppContext::DEPTH_COMPONENT,
+        }
+    }
+
+    /// This method returns type of uploaded pixels used when there are no pixels (e.g. empty texture) or they come
+    /// from an image.
+    pub fn get_default_data_type(&self) -> u32 {
+        match self {
+            Self::R8 | Self::Rg8 | Self::Rgb8 | Self::Rgba8 | Self::Srgb8Alpha8 => crate::AppContext::UNSIGNED_BYTE,
+            Self::R16F | Self::Rg16F | Self::Rgba16F => crate::AppContext::HALF_FLOAT,
+            Self::R32F | Self::Rg32F | Self::Rgba32F | Self::DepthComponent32F => crate::AppContext::FLOAT,
+            Self::Depth24Stencil8 => crate::AppContext::UNSIGNED_INT_24_8,
+        }
+    }
+
+    /// This method checks if pixels of given type can be uploaded to texture of this format
+    /// (half float formats accept also FLOAT pixels, the rest accepts only the default type).
+    pub fn is_valid_data_type(&self, data_type: u32) -> bool {
+        match self {
+            Self::R16F | Self::Rg16F | Self::Rgba16F => {
+                data_type == crate::AppContext::HALF_FLOAT || data_type == crate::AppContext::FLOAT
+            }
+            _ => data_type == self.get_default_data_type(),
+        }
+    }
+
+    /// This method returns number of values per pixel in uploaded data (depth and stencil are packed into one value).
+    pub fn get_channel_count(&self) -> usize {
+        match self {
+            Self::R8 | Self::R16F | Self::R32F | Self::Depth24Stencil8 | Self::DepthComponent32F => 1,
+            Self::Rg8 | Self::Rg16F | Self::Rg32F => 2,
+            Self::Rgb8 => 3,
+            Self::Rgba8 | Self::Srgb8Alpha8 | Self::Rgba16F | Self::Rgba32F => 4,
+        }
+    }
+
+    /// This method returns number of bytes per pixel in uploaded data of the default type.
+    pub fn get_pixel_size(&self) -> usize {
+        let channel_size = match self.get_default_data_type() {
+            crate::AppContext::UNSIGNED_BYTE => 1,
+            crate::AppContext::HALF_FLOAT => 2,
+            _ => 4,
+        };
+        self.get_channel_count() * channel_size
+    }
+
+    pub fn is_depth(&self) -> bool {
+        matches!(self, Self::Depth24Stencil8 | Self::DepthComponent32F)
+    }
+
+    /// This method returns extension required to use texture of this format as color attachment.
+    pub fn get_render_extension(&self) -> Option<&'static str> {
+        match self {
+            Self::R16F | Self::Rg16F | Self::Rgba16F | Self::R32F | Self::Rg32F | Self::Rgba32F => {
+                Some("EXT_color_buffer_float")
+            }
+            _ => None,
+        }
+    }
+
+    /// This method returns extension required to filter texture of this format linearly.
+    pub fn get_linear_filter_extension(&self) -> Option<&'static str> {
+        match self {
+            Self::R32F | Self::Rg32F | Self::Rgba32F => Some("OES_texture_float_linear"),
+            _ => None,
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+/// Type of channel values of pixels uploaded from memory (see Texture2D::new_from_data), it has to be valid for
+/// format of the texture (see TextureFormat::is_valid_data_type). It can't be implemented outside of this crate
+/// (see interface::PlainData).
+pub trait PixelType: interface::PlainData {
+    fn get_gl_property_value() -> u32;
+}
+
+impl PixelType for u8 {
+    fn get_gl_property_value() -> u32 {
+        crate::AppContext::UNSIGNED_BYTE
+    }
+}
+
+/// Values are raw bits of half precision floats.
+impl PixelType for u16 {
+    fn get_gl_property_value() -> u32 {
+        crate::AppContext::HALF_FLOAT
+    }
+}
+
+/// Values contain 24-bit depth in the highest bits and 8-bit stencil in the lowest bits.
+impl PixelType for u32 {
+    fn get_gl_property_value() -> u32 {
+        crate::AppContext::UNSIGNED_INT_24_8
+    }
+}
+
+impl PixelType for f32 {
+    fn get_gl_property_value() -> u32 {
+        crate::AppContext::FLOAT
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+/// Block compressed format of texels (see Texture2D::from_compressed). Every format requires its WebGL extension,
+/// which is usually available only on some platforms (S3TC on desktops, ETC2 and ASTC on mobiles).
+/// Mipmaps of compressed textures can't be generated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompressedFormat {
+    /// BC1 with 1-bit alpha if alpha is true.
+    Dxt1 { alpha: bool, srgb: bool },
+    /// BC2
+    Dxt3 { srgb: bool },
+    /// BC3
+    Dxt5 { srgb: bool },
+    Etc2Rgb8 { srgb: bool },
+    /// ETC2 with 1-bit alpha.
+    Etc2Rgb8A1 { srgb: bool },
+    Etc2Rgba8 { srgb: bool },
+    /// LDR ASTC with given size of blocks in texels (e.g. 4x4 or 8x8).
+    Astc { block_width: u32, block_height: u32, srgb: bool },
+}
+
+impl CompressedFormat {
+    /// This method converts VkFormat value used by KTX2 files, None is returned for other than supported
+    /// compressed formats.
+    pub fn from_vk_format(vk_format: u32) -> Option<Self> {
+        let srgb = vk_format % 2 == 0;
+        match vk_format {
+            131 | 132 => Some(Self::Dxt1 { alpha: false, srgb }),
+            133 | 134 => Some(Self::Dxt1 { alpha: true, srgb }),
+            135 | 136 => Some(Self::Dxt3 { srgb }),
+            137 | 138 => Some(Self::Dxt5 { srgb }),
+            147 | 148 => Some(Self::Etc2Rgb8 { srgb }),
+            149 | 150 => Some(Self::Etc2Rgb8A1 { srgb }),
+            151 | 152 => Some(Self::Etc2Rgba8 { srgb }),
+            157..=184 => {
+                let (block_width, block_height) = ASTC_BLOCK_SIZES[(vk_format - 157) as usize / 2];
+                Some(Self::Astc { block_width, block_height, srgb })
+            }
+            _ => None,
+        }
+    }
+
+    /// This method returns None for ASTC block size which isn't supported by WebGL.
+    pub fn get_gl_property_value(&self) -> Option<u32> {
+        match *self {
+            Self::Dxt1 { alpha: false, srgb: false } => Some(interface::COMPRESSED_RGB_S3TC_DXT1_EXT),
+            Self::Dxt1 { alpha: true, srgb: false } => Some(interface::COMPRESSED_RGBA_S3TC_DXT1_EXT),
+            Self::Dxt1 { alpha: false, srgb: true } => Some(interface::COMPRESSED_SRGB_S3TC_DXT1_EXT),
+            Self::Dxt1 { alpha: true, srgb: true } => Some(interface::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT),
+            Self::Dxt3 { srgb: false } => Some(interface::COMPRESSED_RGBA_S3TC_DXT3_EXT),
+            Self::Dxt3 { srgb: true } => Some(interface::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT),
+            Self::Dxt5 { srgb: false } => Some(interface::COMPRESSED_RGBA_S3TC_DXT5_EXT),
+            Self::Dxt5 { srgb: true } => Some(interface::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT),
+            Self::Etc2Rgb8 { srgb: false } => Some(interface::COMPRESSED_RGB8_ETC2),
+            Self::Etc2Rgb8 { srgb: true } => Some(interface::COMPRESSED_SRGB8_ETC2),
+            Self::Etc2Rgb8A1 { srgb: false } => Some(interface::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2),
+            Self::Etc2Rgb8A1 { srgb: true } => Some(interface::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2),
+            Self::Etc2Rgba8 { srgb: false } => Some(interface::COMPRESSED_RGBA8_ETC2_EAC),
+            Self::Etc2Rgba8 { srgb: true } => Some(interface::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC),
+            Self::Astc { block_width, block_height, srgb } => {
+                let index = ASTC_BLOCK_SIZES.iter().position(|size| *size == (block_width, block_height))? as u32;
+                let first = if srgb {
+                    interface::COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR
+                } else {
+                    interface::COMPRESSED_RGBA_ASTC_4X4_KHR
+                };
+                Some(first + index)
+            }
+        }
+    }
+
+    /// This method returns extension required to upload texture of this format.
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            Self::Dxt1 { srgb: true, .. } | Self::Dxt3 { srgb: true } | Self::Dxt5 { srgb: true } => {
+                "WEBGL_compressed_texture_s3tc_srgb"
+            }
+            Self::Dxt1 { .. } | Self::Dxt3 { .. } | Self::Dxt5 { .. } => "WEBGL_compressed_texture_s3tc",
+            Self::Etc2Rgb8 { .. } | Self::Etc2Rgb8A1 { .. } | Self::Etc2Rgba8 { .. } => "WEBGL_compressed_texture_etc",
+            Self::Astc { .. } => "WEBGL_compressed_texture_astc",
+        }
+    }
+
+    /// This method enables extension of the format and returns false if it isn't available.
+    pub fn is_supported<B: GlBackend>(&self, context: &B) -> bool {
+        self.get_gl_property_value().is_some() && context.enable_extension(self.get_extension())
+    }
+
+    /// This method returns width and height of blocks in texels.
+    pub fn get_block_size(&self) -> (u32, u32) {
+        match *self {
+            Self::Astc { block_width, block_height, .. } => (block_width, block_height),
+            _ => (4, 4),
+        }
+    }
+
+    /// This method returns number of bytes of one block.
+    pub fn get_block_bytes(&self) -> usize {
+        match self {
+            Self::Dxt1 { .. } | Self::Etc2Rgb8 { .. } | Self::Etc2Rgb8A1 { .. } => 8,
+            _ => 16,
+        }
+    }
+
+    /// This method returns number of bytes of image with given size in texels (partial blocks are stored whole).
+    pub fn get_image_size(&self, width: u32, height: u32) -> usize {
+        let (block_width, block_height) = self.get_block_size();
+        let blocks_x = width.div_ceil(block_width) as usize;
+        let blocks_y = height.div_ceil(block_height) as usize;
+        blocks_x * blocks_y * self.get_block_bytes()
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+
+/// Source of mipmap levels of texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureMipmaps {
+    /// Texture has only the base level (e.g. pixel-art or framebuffer textures), min_filter can't use mipmaps.
+    None,
+    /// Levels are generated from the base level after every upload.
+    Generate,
+    /// Levels are uploaded by the user (see Texture2D::new_from_levels).
+    Provided,
+}
+
 // --------------------------------------------------------------------------------------------------
 
+/// Level and LOD fields have the same meaning and default values as TEXTURE_BASE_LEVEL, TEXTURE_MAX_LEVEL,
+/// TEXTURE_MIN_LOD and TEXTURE_MAX_LOD parameters, they are set only if they differ from defaults.
+/// Anisotropy greater than 1 enables anisotropic filtering. It is clamped to the maximum of the device and ignored
+/// if EXT_texture_filter_anisotropic is not supported.
+///
+/// ```text
+/// let conf = TextureConfiguration { anisotropy: 8.0, max_lod: 4.0, ..TextureConfiguration::default() };
+/// ```
 pub struct TextureConfiguration {
     pub wrap_x: TextureWrap,
     pub wrap_y: TextureWrap,
+    /// Wrap along depth of 3D textures (see Texture3D).
+    pub wrap_z: TextureWrap,
     pub min_filter: TextureFilter,
     pub mag_filter: TextureFilter,
-    pub chanels: TextureChannels,
+    pub format: TextureFormat,
+    pub mipmaps: TextureMipmaps,
+    pub base_level: u32,
+    pub max_level: u32,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    pub anisotropy: f32,
 }
 
 impl TextureConfiguration {
-    pub fn new(
+    pub const DEFAULT_MAX_LEVEL: u32 = 1000;
+    pub const DEFAULT_MIN_LOD: f32 = -1000.0;
+    pub const DEFAULT_MAX_LOD: f32 = 1000.0;
+
+    /// This method gets TextureFormat or TextureChannels as format parameter.
+    /// Mipmaps are generated only if min_filter uses them, other fields have default values (wrap_z is ClampToEdge).
+    pub fn new<F: Into<TextureFormat>>(
         wrap_x: TextureWrap,
         wrap_y: TextureWrap,
         min_filter: TextureFilter,
         mag_filter: TextureFilter,
-        chanels: TextureChannels,
+        format: F,
     ) -> TextureConfiguration {
         TextureConfiguration {
             wrap_x,
             wrap_y,
+            wrap_z: TextureWrap::ClampToEdge,
             min_filter,
             mag_filter,
-            chanels,
+            format: format.into(),
+            mipmaps: if min_filter.uses_mipmaps() { TextureMipmaps::Generate } else { TextureMipmaps::None },
+            base_level: 0,
+            max_level: Self::DEFAULT_MAX_LEVEL,
+            min_lod: Self::DEFAULT_MIN_LOD,
+            max_lod: Self::DEFAULT_MAX_LOD,
+            anisotropy: 1.0,
         }
     }
 
     pub fn default() -> TextureConfiguration {
-        TextureConfiguration {
-            wrap_x: TextureWrap::ClampToEdge,
-            wrap_y: TextureWrap::ClampToEdge,
-            min_filter: TextureFilter::LinearMipmapLinear,
-            mag_filter: TextureFilter::LinearMipmapLinear,
-            chanels: TextureChannels::Rgba,
+        Self::new(
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::LinearMipmapLinear,
+            TextureFilter::Linear,
+            TextureFormat::Rgba8,
+        )
+    }
+
+    /// This method checks if filters, mipmaps, levels and LOD range can be used together.
+    pub fn validate(&self) -> SWGLResult<()> {
+        self.validate_sampling()?;
+        if self.min_filter.uses_mipmaps() && self.mipmaps == TextureMipmaps::None {
+            return Err(SWGLRuntimeError::new(&format!(
+                "TextureConfiguration::MissingMipmaps: {:?} min_filter requires mipmaps",
+                self.min_filter
+            )));
+        }
+        if self.base_level > self.max_level {
+            return Err(SWGLRuntimeError::new("TextureConfiguration::InvalidLevelRange: base_level > max_level"));
+        }
+
+        Ok(())
+    }
+
+    /// This method validates only the parameters which are shared with samplers (see Sampler).
+    pub(crate) fn validate_sampling(&self) -> SWGLResult<()> {
+        if self.mag_filter.uses_mipmaps() {
+            return Err(SWGLRuntimeError::new(&format!(
+                "TextureConfiguration::InvalidMagFilter: {:?} (mag_filter has to be Nearest or Linear)",
+                self.mag_filter
+            )));
+        }
+        if self.min_lod.is_nan() || self.max_lod.is_nan() || self.min_lod > self.max_lod {
+            return Err(SWGLRuntimeError::new("TextureConfiguration::InvalidLodRange: min_lod > max_lod"));
+        }
+        if self.anisotropy.is_nan() || self.anisotropy < 1.0 {
+            return Err(SWGLRuntimeError::new("TextureConfiguration::InvalidAnisotropy: anisotropy < 1"));
+        }
+
+        Ok(())
+    }
+
+    /// This method validates the configuration, checks if filters and mipmaps can be used with the format and
+    /// enables required extensions. Renderable has to be true if the texture is used as color attachment of
+    /// framebuffer.
+    pub fn check_support<B: GlBackend>(&self, context: &B, renderable: bool) -> SWGLResult<()> {
+        self.validate()?;
+
+        let generate_mipmaps = self.mipmaps == TextureMipmaps::Generate;
+        if self.min_filter.is_linear() || self.mag_filter.is_linear() || generate_mipmaps {
+            if self.format.is_depth() {
+                return Err(SWGLRuntimeError::new("TextureConfiguration::FormatNotFilterable"));
+            }
+            if let Some(extension) = self.format.get_linear_filter_extension() {
+                Self::require_extension(context, extension)?;
+            }
+        }
+        // mipmaps are generated by rendering, so the format has to be renderable
+        if renderable || generate_mipmaps {
+            if let Some(extension) = self.format.get_render_extension() {
+                Self::require_extension(context, extension)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This method sets texture parameters of texture bound to target.
+    pub fn apply<B: GlBackend>(&self, context: &B, target: u32) {
+        self.set_sampling_parameters(
+            context,
+            |pname, param| context.tex_parameteri(target, pname, param),
+            |pname, param| context.tex_parameterf(target, pname, param),
+        );
+
+        if self.base_level != 0 {
+            context.tex_parameteri(target, crate::AppContext::TEXTURE_BASE_LEVEL, self.base_level as i32);
+        }
+        if self.max_level != Self::DEFAULT_MAX_LEVEL {
+            context.tex_parameteri(target, crate::AppContext::TEXTURE_MAX_LEVEL, self.max_level as i32);
+        }
+    }
+
+    /// This method sets sampler parameters (wraps, filters, LOD range and anisotropy), other fields are ignored.
+    pub fn apply_to_sampler<B: GlBackend>(&self, context: &B, sampler: &B::Sampler) {
+        self.set_sampling_parameters(
+            context,
+            |pname, param| context.sampler_parameteri(sampler, pname, param),
+            |pname, param| context.sampler_parameterf(sampler, pname, param),
+        );
+    }
+
+    fn set_sampling_parameters<B: GlBackend>(&self, context: &B, set_i: impl Fn(u32, i32), set_f: impl Fn(u32, f32)) {
+        set_i(crate::AppContext::TEXTURE_WRAP_S, self.wrap_x.get_gl_property_value() as i32);
+        set_i(crate::AppContext::TEXTURE_WRAP_T, self.wrap_y.get_gl_property_value() as i32);
+        set_i(crate::AppContext::TEXTURE_WRAP_R, self.wrap_z.get_gl_property_value() as i32);
+        set_i(crate::AppContext::TEXTURE_MIN_FILTER, self.min_filter.get_gl_property_value() as i32);
+        set_i(crate::AppContext::TEXTURE_MAG_FILTER, self.mag_filter.get_gl_property_value() as i32);
+
+        if self.min_lod != Self::DEFAULT_MIN_LOD {
+            set_f(crate::AppContext::TEXTURE_MIN_LOD, self.min_lod);
+        }
+        if self.max_lod != Self::DEFAULT_MAX_LOD {
+            set_f(crate::AppContext::TEXTURE_MAX_LOD, self.max_lod);
+        }
+        if self.anisotropy > 1.0 && context.enable_extension("EXT_texture_filter_anisotropic") {
+            let max_anisotropy = context.get_parameter_i32(interface::MAX_TEXTURE_MAX_ANISOTROPY_EXT).max(1) as f32;
+            set_f(interface::TEXTURE_MAX_ANISOTROPY_EXT, self.anisotropy.min(max_anisotropy));
+        }
+    }
+
+    fn require_extension<B: GlBackend>(context: &B, extension: &str) -> SWGLResult<()> {
+        if !context.enable_extension(extension) {
+            return Err(SWGLRuntimeError::new(&format!("TextureConfiguration::MissingExtension: {}", extension)));
+        }
+        Ok(())
+    }
+}
+
+// --------------------------------------------------------------------------------------------------
+// private:
+
+/// Block sizes of ASTC formats in the order of their VkFormat and GL values.
+const ASTC_BLOCK_SIZES: [(u32, u32); 14] = [
+    (4, 4),
+    (5, 4),
+    (5, 5),
+    (6, 5),
+    (6, 6),
+    (8, 5),
+    (8, 6),
+    (8, 8),
+    (10, 5),
+    (10, 6),
+    (10, 8),
+    (10, 10),
+    (12, 10),
+    (12, 12),
+];
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::backend::recording_backend::{GlCall, RecordingBackend};
+
+    fn configuration(min_filter: TextureFilter, mag_filter: TextureFilter, format: TextureFormat) -> TextureConfiguration {
+        TextureConfiguration::new(TextureWrap::ClampToEdge, TextureWrap::ClampToEdge, min_filter, mag_filter, format)
+    }
+
+    fn support_error(context: &RecordingBackend, conf: &TextureConfiguration, renderable: bool) -> Option<String> {
+        conf.check_support(context, renderable).err().map(|error| error.message)
+    }
+
+    #[test]
+    fn validates_pixel_types_of_formats() {
+        let valid = [
+            (TextureFormat::Rgba8, crate::AppContext::UNSIGNED_BYTE),
+            (TextureFormat::Srgb8Alpha8, crate::AppContext::UNSIGNED_BYTE),
+            (TextureFormat::Rgba16F, crate::AppContext::HALF_FLOAT),
+            (TextureFormat::R16F, crate::AppContext::FLOAT),
+            (TextureFormat::Rg32F, crate::AppContext::FLOAT),
+            (TextureFormat::Depth24Stencil8, crate::AppContext::UNSIGNED_INT_24_8),
+            (TextureFormat::DepthComponent32F, crate::AppContext::FLOAT),
+        ];
+        for (format, data_type) in valid.iter() {
+            assert!(format.is_valid_data_type(*data_type), "{:?} {}", format, data_type);
+        }
+
+        let invalid = [
+            (TextureFormat::Rgba8, crate::AppContext::FLOAT),
+            (TextureFormat::R8, crate::AppContext::HALF_FLOAT),
+            (TextureFormat::Rgba16F, crate::AppContext::UNSIGNED_BYTE),
+            (TextureFormat::Rgba32F, crate::AppContext::HALF_FLOAT),
+            (TextureFormat::Depth24Stencil8, crate::AppContext::FLOAT),
+            (TextureFormat::DepthComponent32F, crate::AppContext::UNSIGNED_INT_24_8),
+        ];
+        for (format, data_type) in invalid.iter() {
+            assert!(!format.is_valid_data_type(*data_type), "{:?} {}", format, data_type);
         }
+
+        assert_eq!(TextureFormat::Rgb8.get_pixel_size(), 3);
+        assert_eq!(TextureFormat::Rgba16F.get_pixel_size(), 8);
+        assert_eq!(TextureFormat::Depth24Stencil8.get_pixel_size(), 4);
+    }
+
+    #[test]
+    fn requires_extensions_for_float_filtering_and_rendering() {
+        let context = RecordingBackend::new();
+        context.set_extension_supported("OES_texture_float_linear", false);
+        context.set_extension_supported("EXT_color_buffer_float", false);
+
+        let linear_r32f = configuration(TextureFilter::Linear, TextureFilter::Nearest, TextureFormat::R32F);
+        assert_eq!(
+            support_error(&context, &linear_r32f, false).as_deref(),
+            Some("TextureConfiguration::MissingExtension: OES_texture_float_linear")
+        );
+        let nearest_r32f = configuration(TextureFilter::Nearest, TextureFilter::Nearest, TextureFormat::R32F);
+        assert_eq!(support_error(&context, &nearest_r32f, false), None);
+        assert_eq!(
+            support_error(&context, &nearest_r32f, true).as_deref(),
+            Some("TextureConfiguration::MissingExtension: EXT_color_buffer_float")
+        );
+
+        // half floats are filterable, but mipmaps are generated by rendering
+        let linear_rgba16f = configuration(TextureFilter::Linear, TextureFilter::Linear, TextureFormat::Rgba16F);
+        assert_eq!(support_error(&context, &linear_rgba16f, false), None);
+        let mipmapped_rgba16f = configuration(TextureFilter::LinearMipmapLinear, TextureFilter::Linear, TextureFormat::Rgba16F);
+        assert_eq!(
+            support_error(&context, &mipmapped_rgba16f, false).as_deref(),
+            Some("TextureConfiguration::MissingExtension: EXT_color_buffer_float")
+        );
+
+        let linear_depth = configuration(TextureFilter::Nearest, TextureFilter::Linear, TextureFormat::DepthComponent32F);
+        assert_eq!(support_error(&context, &linear_depth, false).as_deref(), Some("TextureConfiguration::FormatNotFilterable"));
+
+        context.set_extension_supported("OES_texture_float_linear", true);
+        context.set_extension_supported("EXT_color_buffer_float", true);
+        assert_eq!(support_error(&context, &linear_r32f, true), None);
+        assert_eq!(support_error(&context, &mipmapped_rgba16f, true), None);
+    }
+
+    fn validation_error(conf: &TextureConfiguration) -> Option<String> {
+        conf.validate().err().map(|error| error.message)
+    }
+
+    #[test]
+    fn rejects_mipmap_filters_without_mipmaps() {
+        let mut conf = configuration(TextureFilter::LinearMipmapNearest, TextureFilter::Linear, TextureFormat::Rgba8);
+        assert!(conf.mipmaps == TextureMipmaps::Generate);
+        assert_eq!(validation_error(&conf), None);
+        conf.mipmaps = TextureMipmaps::Provided;
+        assert_eq!(validation_error(&conf), None);
+
+        conf.mipmaps = TextureMipmaps::None;
+        assert_eq!(
+            validation_error(&conf).as_deref(),
+            Some("TextureConfiguration::MissingMipmaps: LinearMipmapNearest min_filter requires mipmaps")
+        );
+        conf.min_filter = TextureFilter::Linear;
+        assert_eq!(validation_error(&conf), None);
+
+        conf.mag_filter = TextureFilter::NearestMipmapLinear;
+        assert_eq!(
+            validation_error(&conf).as_deref(),
+            Some("TextureConfiguration::InvalidMagFilter: NearestMipmapLinear (mag_filter has to be Nearest or Linear)")
+        );
+
+        conf.mag_filter = TextureFilter::Nearest;
+        conf.base_level = 3;
+        conf.max_level = 2;
+        assert_eq!(
+            validation_error(&conf).as_deref(),
+            Some("TextureConfiguration::InvalidLevelRange: base_level > max_level")
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rejects_invalid_lod_range_and_anisotropy() {
+        let lod_error = Some("TextureConfiguration::InvalidLodRange: min_lod > max_lod");
+        let anisotropy_error = Some("TextureConfiguration::InvalidAnisotropy: anisotropy < 1");
+        let mut conf = TextureConfiguration::default();
+        assert_eq!(validation_error(&conf), None);
+
+        conf.min_lod = 2.0;
+        conf.max_lod = 1.0;
+        assert_eq!(validation_error(&conf).as_deref(), lod_error);
+        conf.max_lod = 2.0;
+        assert_eq!(validation_error(&conf), None);
+        conf.min_lod = f32::NAN;
+        assert_eq!(validation_error(&conf).as_deref(), lod_error);
+        conf.min_lod = 0.0;
+        conf.max_lod = f32::NAN;
+        assert_eq!(validation_error(&conf).as_deref(), lod_error);
+
+        conf.max_lod = 2.0;
+        conf.anisotropy = 0.5;
+        assert_eq!(validation_error(&conf).as_deref(), anisotropy_error);
+        conf.anisotropy = f32::NAN;
+        assert_eq!(validation_error(&conf).as_deref(), anisotropy_error);
+        conf.anisotropy = 1.0;
+        assert_eq!(validation_error(&conf), None);
+    }
+
+    #[test]
+    fn applies_lod_range_and_clamped_anisotropy() {
+        let context = RecordingBackend::new();
+        let mut conf = TextureConfiguration::default();
+        conf.apply(&context, crate::AppContext::TEXTURE_2D);
+        let float_parameters = |context: &RecordingBackend| -> Vec<(u32, f32)> {
+            context
+                .calls()
+                .iter()
+                .filter_map(|call| match call {
+                    GlCall::TexParameterf { pname, param, .. } => Some((*pname, *param)),
+                    _ => None,
+                })
+                .collect()
+        };
+        // default LOD range and anisotropy are not sent
+        assert!(float_parameters(&context).is_empty());
+
+        let context = RecordingBackend::new();
+        conf.min_lod = 1.0;
+        conf.max_lod = 4.0;
+        conf.anisotropy = 64.0;
+        conf.apply(&context, crate::AppContext::TEXTURE_2D);
+        assert_eq!(
+            float_parameters(&context),
+            vec![
+                (crate::AppContext::TEXTURE_MIN_LOD, 1.0),
+                (crate::AppContext::TEXTURE_MAX_LOD, 4.0),
+                (interface::TEXTURE_MAX_ANISOTROPY_EXT, 16.0),
+            ]
+        );
+
+        let context = RecordingBackend::new();
+        context.set_extension_supported("EXT_texture_filter_anisotropic", false);
+        conf.apply(&context, crate::AppContext::TEXTURE_2D);
+        assert_eq!(float_parameters(&context).len(), 2);
+    }
+
+    fn gl_format(vk_format: u32) -> Option<u32> {
+        CompressedFormat::from_vk_format(vk_format)?.get_gl_property_value()
+    }
+
+    #[test]
+    fn converts_bc_formats() {
+        // odd VkFormats are UNORM, even ones are SRGB
+        assert_eq!(CompressedFormat::from_vk_format(131), Some(CompressedFormat::Dxt1 { alpha: false, srgb: false }));
+        assert_eq!(CompressedFormat::from_vk_format(134), Some(CompressedFormat::Dxt1 { alpha: true, srgb: true }));
+        let expected = [
+            (131, interface::COMPRESSED_RGB_S3TC_DXT1_EXT),
+            (132, interface::COMPRESSED_SRGB_S3TC_DXT1_EXT),
+            (133, interface::COMPRESSED_RGBA_S3TC_DXT1_EXT),
+            (134, interface::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT),
+            (135, interface::COMPRESSED_RGBA_S3TC_DXT3_EXT),
+            (136, interface::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT),
+            (137, interface::COMPRESSED_RGBA_S3TC_DXT5_EXT),
+            (138, interface::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT),
+        ];
+        for (vk_format, gl_value) in expected.iter() {
+            assert_eq!(gl_format(*vk_format), Some(*gl_value), "VkFormat {}", vk_format);
+        }
+
+        assert_eq!(CompressedFormat::Dxt1 { alpha: true, srgb: false }.get_extension(), "WEBGL_compressed_texture_s3tc");
+        assert_eq!(CompressedFormat::Dxt5 { srgb: true }.get_extension(), "WEBGL_compressed_texture_s3tc_srgb");
+        assert_eq!(CompressedFormat::Dxt1 { alpha: false, srgb: false }.get_image_size(6, 6), 4 * 8);
+        assert_eq!(CompressedFormat::Dxt5 { srgb: false }.get_image_size(1, 1), 16);
+    }
+
+    #[test]
+    fn converts_etc2_formats() {
+        let expected = [
+            (147, interface::COMPRESSED_RGB8_ETC2),
+            (148, interface::COMPRESSED_SRGB8_ETC2),
+            (149, interface::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2),
+            (150, interface::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2),
+            (151, interface::COMPRESSED_RGBA8_ETC2_EAC),
+            (152, interface::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC),
+        ];
+        for (vk_format, gl_value) in expected.iter() {
+            assert_eq!(gl_format(*vk_format), Some(*gl_value), "VkFormat {}", vk_format);
+            assert_eq!(CompressedFormat::from_vk_format(*vk_format).unwrap().get_extension(), "WEBGL_compressed_texture_etc");
+        }
+        // EAC formats (153..=156) aren't supported
+        assert_eq!(CompressedFormat::from_vk_format(153), None);
+    }
+
+    #[test]
+    fn converts_astc_formats() {
+        for vk_format in 157..=184u32 {
+            let index = (vk_format - 157) / 2;
+            let first = if vk_format % 2 == 0 {
+                interface::COMPRESSED_SRGB8_ALPHA8_ASTC_4X4_KHR
+            } else {
+                interface::COMPRESSED_RGBA_ASTC_4X4_KHR
+            };
+            assert_eq!(gl_format(vk_format), Some(first + index), "VkFormat {}", vk_format);
+        }
+        assert_eq!(gl_format(157), Some(0x93B0));
+        assert_eq!(gl_format(184), Some(0x93DD));
+        assert_eq!(
+            CompressedFormat::from_vk_format(175),
+            Some(CompressedFormat::Astc { block_width: 10, block_height: 6, srgb: false })
+        );
+        assert_eq!(CompressedFormat::from_vk_format(185), None);
+        assert_eq!(CompressedFormat::from_vk_format(156), None);
+
+        let astc_8x8 = CompressedFormat::Astc { block_width: 8, block_height: 8, srgb: false };
+        assert_eq!(astc_8x8.get_image_size(17, 8), 3 * 16);
+        // block size which isn't in the ASTC table
+        assert_eq!(CompressedFormat::Astc { block_width: 7, block_height: 7, srgb: false }.get_gl_property_value(), None);
+    }
+}
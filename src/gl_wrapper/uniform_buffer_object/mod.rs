@@ -0,0 +1,123 @@
+//! WebGL UBO Object abstraction.
+
+use super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::backend::interface::GlBackend;
+use super::vertex_buffer_object::DataStorageType;
+
+pub mod std140;
+use std140::{Std140Value, Std140Writer};
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Buffer with data of uniform block. The same UBO can be used by many programs: bind it to a binding point with
+/// bind_base and connect program blocks to the same point with Program::bind_uniform_block.
+pub struct UniformBufferObject<B: GlBackend = crate::AppContext> {
+    ubo: B::Buffer,
+    size: usize,
+    #[allow(dead_code)]
+    data_storage_type: DataStorageType,
+    context: B,
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+impl<B: GlBackend> UniformBufferObject<B> {
+    pub fn new(context: &B, data: &[u8], data_storage_type: DataStorageType) -> SWGLResult<Self> {
+        if let Some(buffer) = context.create_buffer() {
+            let ubo = UniformBufferObject {
+                ubo: buffer,
+                size: data.len(),
+                data_storage_type,
+                context: context.clone(),
+            };
+            ubo.bind(context);
+
+            context.buffer_data_with_u8_array(
+                crate::AppContext::UNIFORM_BUFFER,
+                data,
+                data_storage_type.get_gl_property_value(),
+            );
+            return Ok(ubo);
+        }
+
+        Err(SWGLRuntimeError::new("UniformBufferObject::CreationError"))
+    }
+
+    /// This method creates buffer with data of given value written in std140 layout.
+    pub fn from_value<T: Std140Value + ?Sized>(context: &B, value: &T, data_storage_type: DataStorageType) -> SWGLResult<Self> {
+        let mut writer = Std140Writer::new();
+        writer.write(value);
+        Self::new(context, &writer.finish(), data_storage_type)
+    }
+
+    /// This method gets size parameter in bytes.
+    pub fn new_empty(context: &B, size: usize, data_storage_type: DataStorageType) -> SWGLResult<Self> {
+        if let Some(buffer) = context.create_buffer() {
+            let ubo = UniformBufferObject {
+                ubo: buffer,
+                size,
+                data_storage_type,
+                context: context.clone(),
+            };
+            ubo.bind(context);
+            context.buffer_data_with_size(
+                crate::AppContext::UNIFORM_BUFFER,
+                size as i32,
+                data_storage_type.get_gl_property_value(),
+            );
+
+            return Ok(ubo);
+        }
+
+        Err(SWGLRuntimeError::new("UniformBufferObject::CreationError"))
+    }
+
+    /// This method deletes the buffer immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    pub fn bind(&self, context: &B) {
+        context.bind_buffer(crate::AppContext::UNIFORM_BUFFER, Some(&self.ubo));
+    }
+
+    /// This method binds the buffer to the uniform block binding point.
+    pub fn bind_base(&self, context: &B, binding: u32) {
+        context.bind_buffer_base(crate::AppContext::UNIFORM_BUFFER, binding, Some(&self.ubo));
+    }
+
+    /// This method returns size in bytes.
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// This method gets offset parameter in bytes.
+    pub fn update_data(&self, context: &B, data: &[u8], offset: u32) {
+        self.bind(context);
+        context.buffer_sub_data_with_u8_array(crate::AppContext::UNIFORM_BUFFER, offset as i32, data);
+    }
+
+    pub fn update_data_safe(&self, context: &B, data: &[u8], offset: u32) -> SWGLResult<()> {
+        if offset as usize + data.len() > self.size {
+            return Err(SWGLRuntimeError::new("UniformBufferObject::UpdateDataOverflow"));
+        }
+        self.update_data(context, data, offset);
+        Ok(())
+    }
+
+    /// This method replaces buffer data with given value written in std140 layout.
+    pub fn update_value<T: Std140Value + ?Sized>(&self, context: &B, value: &T) -> SWGLResult<()> {
+        let mut writer = Std140Writer::new();
+        writer.write(value);
+        self.update_data_safe(context, &writer.finish(), 0)
+    }
+
+    /// This method returns object that can be used directly inside WebGL low level calls.
+    pub fn get_raw_id(&self) -> &B::Buffer {
+        &self.ubo
+    }
+}
+
+impl<B: GlBackend> Drop for UniformBufferObject<B> {
+    fn drop(&mut self) {
+        self.context.delete_buffer(Some(&self.ubo));
+    }
+}
@@ -0,0 +1,309 @@
+//! Writer of uniform block data in std140 layout.
+
+use nalgebra_glm as glm;
+
+use super::super::super::global_tools::vector2::Vector2;
+use super::super::super::graphics_2d::color::Color;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This trait is implemented by every type that can be written into std140 uniform block.
+/// Use impl_std140! macro for your own structs.
+pub trait Std140Value {
+    fn write_std140(&self, writer: &mut Std140Writer);
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This type builds byte buffer with std140 alignment rules: scalars are aligned to 4 bytes, vec2 to 8, vec3 and vec4
+/// to 16. Every matrix column, array element and struct is aligned to 16 bytes.
+#[derive(Debug, Clone, Default)]
+pub struct Std140Writer {
+    data: Vec<u8>,
+}
+
+impl Std140Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This method moves write position to the multiple of alignment (in bytes), skipped bytes are set to 0.
+    pub fn align(&mut self, alignment: usize) {
+        let rest = self.data.len() % alignment;
+        if rest != 0 {
+            self.data.resize(self.data.len() + alignment - rest, 0);
+        }
+    }
+
+    pub fn write<T: Std140Value + ?Sized>(&mut self, value: &T) -> &mut Self {
+        value.write_std140(self);
+        self
+    }
+
+    /// This method writes struct members. Struct start and size are aligned to 16 bytes.
+    pub fn write_struct(&mut self, write_members: impl FnOnce(&mut Self)) -> &mut Self {
+        self.align(16);
+        write_members(self);
+        self.align(16);
+        self
+    }
+
+    pub fn write_f32(&mut self, alignment: usize, values: &[f32]) {
+        self.align(alignment);
+        for v in values {
+            self.data.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    pub fn write_i32(&mut self, alignment: usize, values: &[i32]) {
+        self.align(alignment);
+        for v in values {
+            self.data.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    pub fn write_u32(&mut self, alignment: usize, values: &[u32]) {
+        self.align(alignment);
+        for v in values {
+            self.data.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    /// This method writes matrix stored column by column (every column takes 16 bytes).
+    pub fn write_matrix(&mut self, rows: usize, values: &[f32]) {
+        for column in values.chunks(rows) {
+            self.write_f32(16, column);
+            self.align(16);
+        }
+    }
+
+    /// This method returns data size in bytes.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// This method returns data padded to 16 bytes (size of the uniform block).
+    pub fn finish(mut self) -> Vec<u8> {
+        self.align(16);
+        self.data
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+impl<T: Std140Value + ?Sized> Std140Value for &T {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        (**self).write_std140(writer);
+    }
+}
+
+/// Array elements are aligned to 16 bytes (e.g. float[4] takes 64 bytes).
+impl<T: Std140Value> Std140Value for [T] {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        for element in self {
+            writer.align(16);
+            element.write_std140(writer);
+            writer.align(16);
+        }
+    }
+}
+
+impl<T: Std140Value, const N: usize> Std140Value for [T; N] {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        self.as_slice().write_std140(writer);
+    }
+}
+
+impl Std140Value for f32 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_f32(4, &[*self]);
+    }
+}
+
+impl Std140Value for i32 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_i32(4, &[*self]);
+    }
+}
+
+impl Std140Value for u32 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_u32(4, &[*self]);
+    }
+}
+
+/// GLSL bool takes 4 bytes.
+impl Std140Value for bool {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_u32(4, &[*self as u32]);
+    }
+}
+
+impl Std140Value for Vector2<f32> {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_f32(8, &[self.x, self.y]);
+    }
+}
+
+/// Color is written as vec4.
+impl Std140Value for Color {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_f32(16, &[self.red, self.green, self.blue, self.alpha]);
+    }
+}
+
+impl Std140Value for glm::Vec2 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_f32(8, self.as_slice());
+    }
+}
+
+impl Std140Value for glm::Vec3 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_f32(16, self.as_slice());
+    }
+}
+
+impl Std140Value for glm::Vec4 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_f32(16, self.as_slice());
+    }
+}
+
+impl Std140Value for glm::Mat2 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_matrix(2, self.as_slice());
+    }
+}
+
+impl Std140Value for glm::Mat3 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_matrix(3, self.as_slice());
+    }
+}
+
+impl Std140Value for glm::Mat4 {
+    fn write_std140(&self, writer: &mut Std140Writer) {
+        writer.write_matrix(4, self.as_slice());
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This macro implements Std140Value for struct, members are written in given order (it should be the same as
+/// in GLSL block declaration).
+///
+/// ```text
+/// struct Camera { projection: glm::Mat4, model: glm::Mat4 }
+/// impl_std140!(Camera { projection, model });
+/// ```
+#[macro_export]
+macro_rules! impl_std140 {
+    ($struct_type:ty { $($member:ident),* $(,)? }) => {
+        impl $crate::gl_wrapper::uniform_buffer_object::std140::Std140Value for $struct_type {
+            fn write_std140(&self, writer: &mut $crate::gl_wrapper::uniform_buffer_object::std140::Std140Writer) {
+                writer.write_struct(|writer| {
+                    $(writer.write(&self.$member);)*
+                });
+            }
+        }
+    };
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Light {
+        position: glm::Vec3,
+        intensity: f32,
+    }
+
+    struct Scene {
+        time: f32,
+        light: Light,
+        offset: glm::Vec2,
+        enabled: bool,
+    }
+
+    impl_std140!(Light { position, intensity });
+    impl_std140!(Scene { time, light, offset, enabled });
+
+    /// This function returns 4-byte values of the data as floats (offset of N-th value is 4 * N).
+    fn floats(data: &[u8]) -> Vec<f32> {
+        data.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect()
+    }
+
+    #[test]
+    fn packs_float_after_vec3() {
+        let mut writer = Std140Writer::new();
+        writer.write(&glm::vec3(1.0, 2.0, 3.0)).write(&4.0f32).write(&glm::vec2(5.0, 6.0));
+        let data = writer.finish();
+
+        // float fills the last component of vec3 slot, vec2 starts at 16
+        assert_eq!(data.len(), 32);
+        assert_eq!(floats(&data), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn pads_matrix_columns() {
+        let mut writer = Std140Writer::new();
+        writer.write(&1.0f32).write(&glm::mat3(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0));
+        let data = writer.finish();
+
+        assert_eq!(data.len(), 64);
+        // glm::mat3 takes rows, columns start at 16, 32 and 48
+        assert_eq!(
+            floats(&data),
+            vec![1.0, 0.0, 0.0, 0.0, 1.0, 4.0, 7.0, 0.0, 2.0, 5.0, 8.0, 0.0, 3.0, 6.0, 9.0, 0.0]
+        );
+
+        let mut writer = Std140Writer::new();
+        writer.write(&glm::Mat2::identity());
+        assert_eq!(floats(writer.get_data()), vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn aligns_array_elements_to_16_bytes() {
+        let mut writer = Std140Writer::new();
+        writer.write(&[1.0f32, 2.0, 3.0]).write(&7.0f32);
+        assert_eq!(floats(&writer.finish()), vec![1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 7.0, 0.0, 0.0, 0.0]);
+
+        let mut writer = Std140Writer::new();
+        writer.write(&[glm::vec2(1.0, 2.0), glm::vec2(3.0, 4.0)]);
+        assert_eq!(floats(&writer.finish()), vec![1.0, 2.0, 0.0, 0.0, 3.0, 4.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn aligns_nested_structs() {
+        let scene = Scene {
+            time: 0.5,
+            light: Light {
+                position: glm::vec3(1.0, 2.0, 3.0),
+                intensity: 4.0,
+            },
+            offset: glm::vec2(5.0, 6.0),
+            enabled: true,
+        };
+        let mut writer = Std140Writer::new();
+        writer.write(&scene);
+        let data = writer.finish();
+
+        // time: 0, light: 16 (position 16, intensity 28), offset: 32, enabled: 40, size rounded up to 48
+        assert_eq!(data.len(), 48);
+        let values = floats(&data);
+        assert_eq!(values[..10].to_vec(), vec![0.5, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(u32::from_le_bytes([data[40], data[41], data[42], data[43]]), 1);
+        assert_eq!(values[11], 0.0);
+    }
+}
@@ -1,9 +1,9 @@
 //! WebGL VAO Object abstraction. 
 
-use web_sys::WebGlVertexArrayObject;
 use super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::backend::interface::GlBackend;
 use super::vertex_buffer_object::VertexBufferObject;
-use super::element_buffer_object::ElementBufferObject;
+use super::element_buffer_object::{ElementBufferObject, IndexType};
 
 // -----------------------------------------------------------------------------------------------------------
 
@@ -34,117 +34,385 @@ impl PrimitiveType {
 
 // -----------------------------------------------------------------------------------------------------------
 
+/// Type of single attribute component stored in VBO.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttributeDataType {
+    Byte,
+    UnsignedByte,
+    Short,
+    UnsignedShort,
+    Int,
+    UnsignedInt,
+    Float,
+    HalfFloat,
+}
+
+impl AttributeDataType {
+    pub fn get_gl_property_value(&self) -> u32 {
+        match self {
+            Self::Byte => crate::AppContext::BYTE,
+            Self::UnsignedByte => crate::AppContext::UNSIGNED_BYTE,
+            Self::Short => crate::AppContext::SHORT,
+            Self::UnsignedShort => crate::AppContext::UNSIGNED_SHORT,
+            Self::Int => crate::AppContext::INT,
+            Self::UnsignedInt => crate::AppContext::UNSIGNED_INT,
+            Self::Float => crate::AppContext::FLOAT,
+            Self::HalfFloat => crate::AppContext::HALF_FLOAT,
+        }
+    }
+
+    /// This method returns size of single component in bytes.
+    pub fn get_byte_size(&self) -> u32 {
+        match self {
+            Self::Byte | Self::UnsignedByte => 1,
+            Self::Short | Self::UnsignedShort | Self::HalfFloat => 2,
+            Self::Int | Self::UnsignedInt | Self::Float => 4,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::Float | Self::HalfFloat)
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
 #[derive(Debug, Copy, Clone)]
 pub struct VertexBufferAttribute {
     element_count: u32,
+    data_type: AttributeDataType,
+    normalized: bool,
+    integer: bool,
 }
 
 impl VertexBufferAttribute {
+    /// This method creates float attribute (e.g. vec2 for element_count = 2).
     pub fn create(element_count: u32) -> VertexBufferAttribute {
-        VertexBufferAttribute { element_count }
+        Self::create_typed(element_count, AttributeDataType::Float, false)
+    }
+
+    /// This method creates attribute which is converted to float in shader. Normalized integer values are mapped
+    /// to [0, 1] (unsigned) or [-1, 1] (signed) range, e.g. color stored as 4 normalized UnsignedByte.
+    pub fn create_typed(element_count: u32, data_type: AttributeDataType, normalized: bool) -> VertexBufferAttribute {
+        VertexBufferAttribute { element_count, data_type, normalized, integer: false }
+    }
+
+    /// This method creates integer attribute (int/uint, ivecN/uvecN in shader), it is set with vertexAttribIPointer.
+    pub fn create_integer(element_count: u32, data_type: AttributeDataType) -> VertexBufferAttribute {
+        VertexBufferAttribute { element_count, data_type, normalized: false, integer: true }
     }
 
     pub fn get_element_count(&self) -> u32 {
         self.element_count
     }
+
+    pub fn get_data_type(&self) -> AttributeDataType {
+        self.data_type
+    }
+
+    pub fn is_normalized(&self) -> bool {
+        self.normalized
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.integer
+    }
+
+    /// This method returns size of the attribute in bytes.
+    pub fn get_byte_size(&self) -> u32 {
+        self.element_count * self.data_type.get_byte_size()
+    }
 }
 
 // -----------------------------------------------------------------------------------------------------------
+// private:
 
-pub struct VertexArrayObject {
-    row_width: u32,
-    physical_vertex_count: usize,
-    vao: WebGlVertexArrayObject,
+/// WebGL2 guarantees at least 16 vertex attributes.
+const MAX_ATTRIBUTES: usize = 16;
+
+fn check_attributes(stride: u32, attributes: &[VertexBufferAttribute]) -> SWGLResult<()> {
+    if attributes.is_empty() {
+        return Err(SWGLRuntimeError::new("VertexArrayObject::NoAttributes"));
+    }
+
+    let attributes_byte_size: u32 = attributes.iter().map(|attr| attr.get_byte_size()).sum();
+    if attributes_byte_size > stride {
+        return Err(SWGLRuntimeError::new("VertexArrayObject::AttributesFitProblem"));
+    }
+
+    // WebGL requires offsets and stride to be multiples of the component size.
+    let mut pos = 0u32;
+    for attr in attributes {
+        if attr.get_element_count() == 0 || attr.get_element_count() > 4 {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::AttributeSizeProblem"));
+        }
+
+        if attr.is_integer() && attr.get_data_type().is_float() {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::IntegerAttributeTypeProblem"));
+        }
+
+        let component_size = attr.get_data_type().get_byte_size();
+        if pos % component_size != 0 || stride % component_size != 0 {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::AttributeAlignmentProblem"));
+        }
+        pos += attr.get_byte_size();
+    }
+
+    Ok(())
+}
+
+/// This function sets pointers of attributes stored in the bound buffer, starting from the given attribute index.
+fn set_attribute_pointers<B: GlBackend>(
+    context: &B,
+    stride: u32,
+    attributes: &[VertexBufferAttribute],
+    first_index: u32,
+    divisor: u32,
+) {
+    let mut pos = 0u32;
+    for (i, attr) in attributes.iter().enumerate() {
+        let index = first_index + i as u32;
+        if attr.is_integer() {
+            context.vertex_attrib_i_pointer(
+                index,
+                attr.get_element_count() as i32,
+                attr.get_data_type().get_gl_property_value(),
+                stride as i32,
+                pos as i32
+            );
+        } else {
+            context.vertex_attrib_pointer(
+                index,
+                attr.get_element_count() as i32,
+                attr.get_data_type().get_gl_property_value(),
+                attr.is_normalized(),
+                stride as i32,
+                pos as i32
+            );
+        }
+        context.enable_vertex_attrib_array(index);
+        if divisor != 0 {
+            context.vertex_attrib_divisor(index, divisor);
+        }
+        pos += attr.get_byte_size();
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Single buffer of the VertexArrayObject together with layout of its data.
+pub struct VertexBufferStream<'a, B: GlBackend = crate::AppContext> {
+    vbo: &'a VertexBufferObject<B>,
+    stride: u32,
     attributes: Vec<VertexBufferAttribute>,
+    start_location: Option<u32>,
+    divisor: u32,
 }
 
-impl VertexArrayObject {
+impl<'a, B: GlBackend> VertexBufferStream<'a, B> {
+    /// This method gets row_width parameter in 4-byte items (number of f32 values for float attributes).
+    pub fn create(vbo: &'a VertexBufferObject<B>, row_width: u32, attributes: Vec<VertexBufferAttribute>) -> Self {
+        Self::create_with_stride(vbo, row_width * std::mem::size_of::<f32>() as u32, attributes)
+    }
+
+    /// This method gets stride parameter (size of one row) in bytes.
+    pub fn create_with_stride(vbo: &'a VertexBufferObject<B>, stride: u32, attributes: Vec<VertexBufferAttribute>) -> Self {
+        Self {
+            vbo,
+            stride,
+            attributes,
+            start_location: None,
+            divisor: 0,
+        }
+    }
+
+    /// This method sets location of the first attribute. By default locations continue after the previous stream.
+    pub fn start_location(mut self, location: u32) -> Self {
+        self.start_location = Some(location);
+        self
+    }
+
+    /// This method makes stream per-instance, every row is used by divisor instances (0 means per-vertex stream).
+    pub fn divisor(mut self, divisor: u32) -> Self {
+        self.divisor = divisor;
+        self
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// GL vertex array is deleted when this object is dropped (buffers are owned by their own objects).
+pub struct VertexArrayObject<B: GlBackend = crate::AppContext> {
+    physical_vertex_count: usize,
+    vao: B::VertexArray,
+    /// Attributes of all streams with their locations.
+    attributes: Vec<(u32, VertexBufferAttribute)>,
+    next_location: u32,
+    physical_instance_count: Option<usize>,
+    context: B,
+}
+
+impl<B: GlBackend> VertexArrayObject<B> {
+    /// This method gets row_width parameter in 4-byte items (number of f32 values for float attributes).
     pub fn new(
-        context: &crate::AppContext,
-        vbo: &VertexBufferObject,
+        context: &B,
+        vbo: &VertexBufferObject<B>,
         row_width: u32,
         attributes: Vec<VertexBufferAttribute>,
     ) -> SWGLResult<Self> {
+        Self::new_with_streams(context, vec![VertexBufferStream::create(vbo, row_width, attributes)])
+    }
+
+    /// This method gets stride parameter (size of one vertex) in bytes.
+    pub fn new_with_stride(
+        context: &B,
+        vbo: &VertexBufferObject<B>,
+        stride: u32,
+        attributes: Vec<VertexBufferAttribute>,
+    ) -> SWGLResult<Self> {
+        Self::new_with_streams(context, vec![VertexBufferStream::create_with_stride(vbo, stride, attributes)])
+    }
+
+    /// This method creates VAO which reads attributes from many buffers (e.g. static positions in one buffer
+    /// and often updated colors in another). Number of vertices is limited by the shortest per-vertex stream.
+    pub fn new_with_streams(context: &B, streams: Vec<VertexBufferStream<B>>) -> SWGLResult<Self> {
+        if streams.is_empty() {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::NoAttributes"));
+        }
 
         if let Some(vertex_array) = context.create_vertex_array() {
-            let vao = VertexArrayObject {
+            let mut vao = VertexArrayObject {
                 vao: vertex_array,
-                physical_vertex_count: vbo.get_vertex_count() / row_width as usize,
-                row_width,
-                attributes,
+                physical_vertex_count: usize::MAX,
+                attributes: Vec::new(),
+                next_location: 0,
+                physical_instance_count: None,
+                context: context.clone(),
             };
-    
-            vao.check_errors()?;
-            vao.set_vertex_attributes(vbo, context);
-    
+
+            for stream in streams {
+                vao.add_stream(context, stream)?;
+            }
+
+            if vao.physical_vertex_count == usize::MAX {
+                return Err(SWGLRuntimeError::new("VertexArrayObject::NoVertexStream"));
+            }
+
             return Ok(vao);
         }
-        
+
         Err(SWGLRuntimeError::new("VertexArrayObject::CreationError"))
     }
 
-    pub fn bind(&self, context: &crate::AppContext) {
-        context.bind_vertex_array(Some(&self.vao));
-    }
+    /// This method adds next buffer to the VAO.
+    pub fn add_stream(&mut self, context: &B, stream: VertexBufferStream<B>) -> SWGLResult<()> {
+        if stream.stride == 0 {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::ZeroStride"));
+        }
 
-    fn set_vertex_attributes(&self, vbo: &VertexBufferObject, context: &crate::AppContext) {
-        self.bind(context);
-        vbo.bind(context);
-
-        let mut pos = 0u32;
-        let len = self.attributes.len();
-        for i in 0..len {
-            let attr = &self.attributes[i];
-            context.vertex_attrib_pointer_with_i32(
-                i as u32,
-                attr.get_element_count() as i32,
-                crate::AppContext::FLOAT,
-                false,
-                (self.row_width * std::mem::size_of::<f32>() as u32) as i32,
-                pos as i32
-            );
-            context.enable_vertex_attrib_array(i as u32);
-            pos += attr.get_element_count() * std::mem::size_of::<f32>() as u32;
+        check_attributes(stream.stride, &stream.attributes)?;
+        if self.attributes.len() + stream.attributes.len() > MAX_ATTRIBUTES {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::AttributesLimit"));
         }
-    }
 
-    fn check_errors(&self) -> SWGLResult<()> {
-        if self.attributes.len() == 0 {
-            return Err(SWGLRuntimeError::new("VertexArrayObject::NoAttributes"));
+        let first_location = stream.start_location.unwrap_or(self.next_location);
+        let end_location = first_location + stream.attributes.len() as u32;
+        if end_location as usize > MAX_ATTRIBUTES {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::AttributesLimit"));
         }
 
-        if self.get_attributes_byte_size() > self.row_width as usize * std::mem::size_of::<f32>() as usize {
-            return Err(SWGLRuntimeError::new("VertexArrayObject::AttributesFitProblem"));
-		}
+        let overlaps = self.attributes.iter().any(|(location, _)| (first_location..end_location).contains(location));
+        if overlaps {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::AttributeLocationOverlap"));
+        }
 
-		if self.attributes.len() > 16usize {
-            return Err(SWGLRuntimeError::new("VertexArrayObject::AttributesLimit"));
+        self.bind(context);
+        stream.vbo.bind(context);
+        set_attribute_pointers(context, stream.stride, &stream.attributes, first_location, stream.divisor);
+
+        let row_count = stream.vbo.get_bytes_count() / stream.stride as usize;
+        if stream.divisor == 0 {
+            self.physical_vertex_count = self.physical_vertex_count.min(row_count);
+        } else {
+            let instance_count = row_count * stream.divisor as usize;
+            self.physical_instance_count = Some(self.physical_instance_count.map_or(instance_count, |c| c.min(instance_count)));
         }
-        
+
+        self.attributes.extend((first_location..).zip(stream.attributes));
+        self.attributes.sort_by_key(|(location, _)| *location);
+        self.next_location = end_location;
         Ok(())
     }
 
-    fn get_attributes_byte_size(&self) -> usize {
-        let mut stride_size = 0usize;
-        for attr in &self.attributes {
-            stride_size += attr.get_element_count() as usize *  std::mem::size_of::<f32>();
+    /// This method adds per-instance attributes (one value per instance) stored in the next buffer. Their indices
+    /// (shader locations) continue after the previous attributes. The row_width parameter is in 4-byte items.
+    pub fn set_instance_buffer(
+        &mut self,
+        context: &B,
+        instance_vbo: &VertexBufferObject<B>,
+        row_width: u32,
+        attributes: Vec<VertexBufferAttribute>,
+    ) -> SWGLResult<()> {
+        self.add_stream(context, VertexBufferStream::create(instance_vbo, row_width, attributes).divisor(1))
+    }
+
+    /// This method works like set_instance_buffer, but gets stride in bytes and divisor (number of instances that
+    /// use the same attribute values).
+    pub fn set_instance_buffer_with_stride(
+        &mut self,
+        context: &B,
+        instance_vbo: &VertexBufferObject<B>,
+        stride: u32,
+        attributes: Vec<VertexBufferAttribute>,
+        divisor: u32,
+    ) -> SWGLResult<()> {
+        if divisor == 0 {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::ZeroDivisor"));
+        }
+        self.add_stream(context, VertexBufferStream::create_with_stride(instance_vbo, stride, attributes).divisor(divisor))
+    }
+
+    /// This method deletes the vertex array immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    pub fn bind(&self, context: &B) {
+        context.bind_vertex_array(Some(&self.vao));
+    }
+
+    /// This method returns attributes of all streams with their locations (sorted by location), it can be checked
+    /// with ProgramReflection::check_vao_locations.
+    pub fn get_signature(&self) -> &[(u32, VertexBufferAttribute)] {
+        &self.attributes
+    }
+
+    /// This method returns number of instances that fit in the instance buffers (None if there is no instance buffer).
+    pub fn get_instance_capacity(&self) -> Option<usize> {
+        self.physical_instance_count
+    }
+
+    fn check_instance_count(&self, instance_count: u32) -> SWGLResult<()> {
+        match self.physical_instance_count {
+            None => Err(SWGLRuntimeError::new("VertexArrayObject::NoInstanceBuffer")),
+            Some(capacity) if instance_count as usize > capacity => {
+                Err(SWGLRuntimeError::new("VertexArrayObject::InstanceCountOverflow"))
+            }
+            _ => Ok(()),
         }
-        stride_size
     }
 
     /// This method returns object that can be used directly inside WebGL low level calls.
-    pub fn get_raw_id(&self) -> &WebGlVertexArrayObject {
+    pub fn get_raw_id(&self) -> &B::VertexArray {
         &self.vao
     }
 
     // -----------------------------------------------------------------------------------------------------------
 
-    pub fn draw_arrays(&self, context: &crate::AppContext, ptype: PrimitiveType, start: u32, n: u32) {
+    pub fn draw_arrays(&self, context: &B, ptype: PrimitiveType, start: u32, n: u32) {
         self.bind(context);
         context.draw_arrays(ptype.get_gl_property_value(), start as i32, n as i32);
     }
 
-    pub fn draw_arrays_safe(&self, context: &crate::AppContext, ptype: PrimitiveType, start: u32, n: u32) -> SWGLResult<()> {
+    pub fn draw_arrays_safe(&self, context: &B, ptype: PrimitiveType, start: u32, n: u32) -> SWGLResult<()> {
         if start + n > self.physical_vertex_count as u32 {
             return Err(SWGLRuntimeError::new("VertexArrayObject::DrawArraysOverflow"));
         }
@@ -152,19 +420,34 @@ impl VertexArrayObject {
         Ok(())
     }
 
-    pub fn draw_all_arrays(&self, context: &crate::AppContext, ptype: PrimitiveType) {
+    pub fn draw_all_arrays(&self, context: &B, ptype: PrimitiveType) {
         self.draw_arrays(context, ptype, 0, self.physical_vertex_count as u32);
     }
 
+    pub fn draw_arrays_instanced(&self, context: &B, ptype: PrimitiveType, start: u32, n: u32, instance_count: u32) {
+        self.bind(context);
+        context.draw_arrays_instanced(ptype.get_gl_property_value(), start as i32, n as i32, instance_count as i32);
+    }
+
+    /// This method also checks if the instance buffer has enough data for given number of instances.
+    pub fn draw_arrays_instanced_safe(&self, context: &B, ptype: PrimitiveType, start: u32, n: u32, instance_count: u32) -> SWGLResult<()> {
+        if start + n > self.physical_vertex_count as u32 {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::DrawArraysOverflow"));
+        }
+        self.check_instance_count(instance_count)?;
+        self.draw_arrays_instanced(context, ptype, start, n, instance_count);
+        Ok(())
+    }
+
     // -----------------------------------------------------------------------------------------------------------
 
-    pub fn draw_elements(&self, context: &crate::AppContext, ptype: PrimitiveType, start: u32, n: u32, ebo: &ElementBufferObject) {
+    pub fn draw_elements<I: IndexType>(&self, context: &B, ptype: PrimitiveType, start: u32, n: u32, ebo: &ElementBufferObject<B, I>) {
         self.bind(context);
         ebo.bind(context);
-        context.draw_elements_with_i32(ptype.get_gl_property_value(), n as i32, crate::AppContext::UNSIGNED_INT, start as i32);
+        context.draw_elements(ptype.get_gl_property_value(), n as i32, ebo.get_index_type(), ebo.get_byte_offset(start) as i32);
     }
 
-    pub fn draw_elements_safe(&self, context: &crate::AppContext, ptype: PrimitiveType, start: u32, n: u32, ebo: &ElementBufferObject) -> SWGLResult<()> {
+    pub fn draw_elements_safe<I: IndexType>(&self, context: &B, ptype: PrimitiveType, start: u32, n: u32, ebo: &ElementBufferObject<B, I>) -> SWGLResult<()> {
         if start + n > ebo.get_size() as u32 {
             return Err(SWGLRuntimeError::new("VertexArrayObject::DrawElementsOverflow"));
         }
@@ -172,7 +455,29 @@ impl VertexArrayObject {
         Ok(())
     }
 
-    pub fn draw_all_elements(&self, context: &crate::AppContext, ptype: PrimitiveType, ebo: &ElementBufferObject) {
+    pub fn draw_all_elements<I: IndexType>(&self, context: &B, ptype: PrimitiveType, ebo: &ElementBufferObject<B, I>) {
         self.draw_elements(context, ptype, 0, ebo.get_size() as u32, ebo);
     }
+
+    pub fn draw_elements_instanced<I: IndexType>(&self, context: &B, ptype: PrimitiveType, start: u32, n: u32, ebo: &ElementBufferObject<B, I>, instance_count: u32) {
+        self.bind(context);
+        ebo.bind(context);
+        context.draw_elements_instanced(ptype.get_gl_property_value(), n as i32, ebo.get_index_type(), ebo.get_byte_offset(start) as i32, instance_count as i32);
+    }
+
+    /// This method also checks if the instance buffer has enough data for given number of instances.
+    pub fn draw_elements_instanced_safe<I: IndexType>(&self, context: &B, ptype: PrimitiveType, start: u32, n: u32, ebo: &ElementBufferObject<B, I>, instance_count: u32) -> SWGLResult<()> {
+        if start + n > ebo.get_size() as u32 {
+            return Err(SWGLRuntimeError::new("VertexArrayObject::DrawElementsOverflow"));
+        }
+        self.check_instance_count(instance_count)?;
+        self.draw_elements_instanced(context, ptype, start, n, ebo, instance_count);
+        Ok(())
+    }
+}
+
+impl<B: GlBackend> Drop for VertexArrayObject<B> {
+    fn drop(&mut self) {
+        self.context.delete_vertex_array(Some(&self.vao));
+    }
 }
@@ -1,7 +1,9 @@
 //! WebGL VBO Object abstraction. 
 
-use web_sys::WebGlBuffer;
 use super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::backend::interface::{self, GlBackend};
+
+pub mod streaming;
 
 // -----------------------------------------------------------------------------------------------------------
 
@@ -24,59 +26,85 @@ impl DataStorageType {
 
 // -----------------------------------------------------------------------------------------------------------
 
-pub struct VertexBufferObject {
-    vbo: WebGlBuffer,
-    vertex_count: usize,
+/// GL buffer is deleted when this object is dropped.
+pub struct VertexBufferObject<B: GlBackend = crate::AppContext> {
+    vbo: B::Buffer,
+    byte_size: usize,
     #[allow(dead_code)]
     data_storage_type: DataStorageType,
+    context: B,
 }
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl VertexBufferObject {
+impl<B: GlBackend> VertexBufferObject<B> {
     pub fn new(
-        context: &crate::AppContext,
+        context: &B,
         data: &[f32],
         data_storage_type: DataStorageType,
-    ) -> SWGLResult<VertexBufferObject> {
-        unsafe {
-            if let Some(buffer) = context.create_buffer() {
-                let vbo = VertexBufferObject {
-                    vbo: buffer,
-                    vertex_count: data.len(),
-                    data_storage_type,
-                };
-                vbo.bind(context);
-
-                let buffer_data = js_sys::Float32Array::view(&data);
-                context.buffer_data_with_array_buffer_view(
-                    crate::AppContext::ARRAY_BUFFER,
-                    &buffer_data,
-                    data_storage_type.get_gl_property_value(),
-                );
-                return Ok(vbo);
-            }
+    ) -> SWGLResult<Self> {
+        if let Some(buffer) = context.create_buffer() {
+            let vbo = VertexBufferObject {
+                vbo: buffer,
+                byte_size: std::mem::size_of_val(data),
+                data_storage_type,
+                context: context.clone(),
+            };
+            vbo.bind(context);
+
+            context.buffer_data_with_u8_array(
+                crate::AppContext::ARRAY_BUFFER,
+                interface::as_byte_slice(data),
+                data_storage_type.get_gl_property_value(),
+            );
+            return Ok(vbo);
+        }
+
+        Err(SWGLRuntimeError::new("VertexBufferObject::CreationError"))
+    }
+
+    /// This method creates buffer from interleaved data of any attribute types (see VertexBufferAttribute::create_typed).
+    pub fn new_from_bytes(
+        context: &B,
+        data: &[u8],
+        data_storage_type: DataStorageType,
+    ) -> SWGLResult<Self> {
+        if let Some(buffer) = context.create_buffer() {
+            let vbo = VertexBufferObject {
+                vbo: buffer,
+                byte_size: data.len(),
+                data_storage_type,
+                context: context.clone(),
+            };
+            vbo.bind(context);
+
+            context.buffer_data_with_u8_array(
+                crate::AppContext::ARRAY_BUFFER,
+                data,
+                data_storage_type.get_gl_property_value(),
+            );
+            return Ok(vbo);
         }
 
         Err(SWGLRuntimeError::new("VertexBufferObject::CreationError"))
     }
 
     pub fn new_empty(
-        context: &crate::AppContext,
+        context: &B,
         vertex_count: usize,
         data_storage_type: DataStorageType,
-    ) -> SWGLResult<VertexBufferObject> {
+    ) -> SWGLResult<Self> {
         if let Some(buffer) = context.create_buffer() {
             let vbo = VertexBufferObject {
                 vbo: buffer,
-                vertex_count,
+                byte_size: vertex_count * std::mem::size_of::<f32>(),
                 data_storage_type,
+                context: context.clone(),
             };
             vbo.bind(context);
-            // i am not sure
-            context.buffer_data_with_f64(
+            context.buffer_data_with_size(
                 crate::AppContext::ARRAY_BUFFER,
-                vertex_count as f64 * std::mem::size_of::<f32>() as f64,
+                vbo.byte_size as i32,
                 data_storage_type.get_gl_property_value(),
             );
 
@@ -86,33 +114,34 @@ impl VertexBufferObject {
         Err(SWGLRuntimeError::new("VertexBufferObject::CreationError"))
     }
 
-    pub fn bind(&self, context: &crate::AppContext) {
+    /// This method deletes the buffer immediately (it is the same as drop).
+    pub fn destroy(self) {}
+
+    pub fn bind(&self, context: &B) {
         context.bind_buffer(crate::AppContext::ARRAY_BUFFER, Some(&self.vbo));
     }
 
     pub fn get_bytes_count(&self) -> usize {
-        self.vertex_count * std::mem::size_of::<f32>()
+        self.byte_size
     }
 
+    /// This method returns size in float items (for byte data it is size in bytes divided by 4).
     pub fn get_vertex_count(&self) -> usize {
-        self.vertex_count
+        self.byte_size / std::mem::size_of::<f32>()
     }
 
     /// This method gets offset parameter, that reffers to n-element (float item), not to n-byte. 
-    pub fn update_data(&self, context: &crate::AppContext, data: &[f32], offset: u32) {
+    pub fn update_data(&self, context: &B, data: &[f32], offset: u32) {
         self.bind(context);
 
-        unsafe {
-            let buffer_data = js_sys::Float32Array::view(&data);
-            context.buffer_sub_data_with_f64_and_array_buffer_view(
-                crate::AppContext::ARRAY_BUFFER,
-                offset as f64 * std::mem::size_of::<f32>() as f64,
-                &buffer_data,
-            );
-        }
+        context.buffer_sub_data_with_u8_array(
+            crate::AppContext::ARRAY_BUFFER,
+            (offset as usize * std::mem::size_of::<f32>()) as i32,
+            interface::as_byte_slice(data),
+        );
     }
 
-    pub fn update_data_safe(&self, context: &crate::AppContext, data: &[f32], offset: u32) -> SWGLResult<()> {
+    pub fn update_data_safe(&self, context: &B, data: &[f32], offset: u32) -> SWGLResult<()> {
         if offset + data.len() as u32 > self.get_vertex_count() as u32 {
             return Err(SWGLRuntimeError::new(
                 "VertexBufferObject::UpdateDataOverflow",
@@ -122,8 +151,30 @@ impl VertexBufferObject {
         Ok(())
     }
 
+    /// This method gets offset parameter in bytes.
+    pub fn update_bytes(&self, context: &B, data: &[u8], offset: u32) {
+        self.bind(context);
+        context.buffer_sub_data_with_u8_array(crate::AppContext::ARRAY_BUFFER, offset as i32, data);
+    }
+
+    pub fn update_bytes_safe(&self, context: &B, data: &[u8], offset: u32) -> SWGLResult<()> {
+        if offset as usize + data.len() > self.byte_size {
+            return Err(SWGLRuntimeError::new(
+                "VertexBufferObject::UpdateDataOverflow",
+            ));
+        }
+        self.update_bytes(context, data, offset);
+        Ok(())
+    }
+
     /// This method returns object that can be used directly inside WebGL low level calls.
-    pub fn get_raw_id(&self) -> &WebGlBuffer {
+    pub fn get_raw_id(&self) -> &B::Buffer {
         &self.vbo
     }
 }
+
+impl<B: GlBackend> Drop for VertexBufferObject<B> {
+    fn drop(&mut self) {
+        self.context.delete_buffer(Some(&self.vbo));
+    }
+}
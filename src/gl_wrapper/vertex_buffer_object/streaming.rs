@@ -0,0 +1,125 @@
+//! VBO for data uploaded in every frame, which avoids waiting for the GPU still reading previous data.
+
+use super::super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::super::backend::interface::GlBackend;
+use super::{DataStorageType, VertexBufferObject};
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamingStrategy {
+    /// Buffer is split into given number of regions. Data is written one after another and writing starts again
+    /// from the first region when the buffer is full, so the GPU has time to finish draws using older regions.
+    RingBuffer(u32),
+    /// Buffer has a single region, when it is full, the storage is orphaned (bufferData with null) and the driver
+    /// gives a fresh memory block, while the old one is still used by pending draws.
+    Orphaning,
+}
+
+impl StreamingStrategy {
+    pub fn get_region_count(&self) -> u32 {
+        match self {
+            Self::RingBuffer(region_count) => *region_count,
+            Self::Orphaning => 1,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Buffer with StreamDraw storage which never overwrites data written since the last wrap (see StreamingStrategy).
+/// Data is written in three steps: allocate returns byte offset and slice to fill, flush uploads all allocated data
+/// and draws use the offset (e.g. first vertex = offset / row_size).
+pub struct StreamingVertexBuffer<B: GlBackend = crate::AppContext> {
+    vbo: VertexBufferObject<B>,
+    strategy: StreamingStrategy,
+    row_size: usize,
+    /// Copy of the buffer data, allocated slices point into it.
+    staging: Vec<u8>,
+    cursor: usize,
+    pending_start: usize,
+    orphan_pending: bool,
+}
+
+impl<B: GlBackend> StreamingVertexBuffer<B> {
+    /// This method gets row_size parameter (e.g. vertex stride) in bytes, allocations start at multiples of it.
+    /// Every region fits rows_per_region rows.
+    pub fn new(context: &B, row_size: usize, rows_per_region: usize, strategy: StreamingStrategy) -> SWGLResult<Self> {
+        if row_size == 0 || rows_per_region == 0 || strategy.get_region_count() == 0 {
+            return Err(SWGLRuntimeError::new("StreamingVertexBuffer::ZeroSize"));
+        }
+
+        let staging = vec![0; row_size * rows_per_region * strategy.get_region_count() as usize];
+        let vbo = VertexBufferObject::new_from_bytes(context, &staging, DataStorageType::StreamDraw)?;
+
+        Ok(Self {
+            vbo,
+            strategy,
+            row_size,
+            staging,
+            cursor: 0,
+            pending_start: 0,
+            orphan_pending: false,
+        })
+    }
+
+    /// This method reserves len bytes and returns their offset in the buffer (in bytes) and slice which should be
+    /// filled with data before flush. Data allocated before the last flush must not be modified.
+    pub fn allocate(&mut self, len: usize) -> SWGLResult<(usize, &mut [u8])> {
+        if len > self.get_region_size() {
+            return Err(SWGLRuntimeError::new("StreamingVertexBuffer::AllocationTooLarge"));
+        }
+
+        let mut offset = self.cursor.div_ceil(self.row_size) * self.row_size;
+        if offset + len > self.staging.len() {
+            if self.pending_start != self.cursor {
+                return Err(SWGLRuntimeError::new("StreamingVertexBuffer::UnflushedData"));
+            }
+            offset = 0;
+            self.pending_start = 0;
+            self.orphan_pending = self.strategy == StreamingStrategy::Orphaning;
+        }
+
+        if self.pending_start == self.cursor {
+            self.pending_start = offset;
+        }
+        self.cursor = offset + len;
+        Ok((offset, &mut self.staging[offset..offset + len]))
+    }
+
+    /// This method uploads data allocated since the last flush.
+    pub fn flush(&mut self, context: &B) {
+        if self.orphan_pending {
+            self.vbo.bind(context);
+            context.buffer_data_with_size(
+                crate::AppContext::ARRAY_BUFFER,
+                self.staging.len() as i32,
+                DataStorageType::StreamDraw.get_gl_property_value(),
+            );
+            self.orphan_pending = false;
+        }
+
+        if self.pending_start < self.cursor {
+            self.vbo.update_bytes(context, &self.staging[self.pending_start..self.cursor], self.pending_start as u32);
+        }
+        self.pending_start = self.cursor;
+    }
+
+    /// This method returns size of a single region in bytes (the largest possible allocation).
+    pub fn get_region_size(&self) -> usize {
+        self.staging.len() / self.strategy.get_region_count() as usize
+    }
+
+    pub fn get_row_size(&self) -> usize {
+        self.row_size
+    }
+
+    pub fn get_strategy(&self) -> StreamingStrategy {
+        self.strategy
+    }
+
+    /// This method returns buffer, which can be used to create VAO.
+    pub fn get_vbo(&self) -> &VertexBufferObject<B> {
+        &self.vbo
+    }
+}
@@ -23,6 +23,12 @@ impl Color {
         Color::new(red as f32 / 255.0, green as f32 / 255.0, blue as f32 / 255.0, alpha as f32 / 255.0)
     }
 
+    /// This method returns color components mapped to 0-255 range (e.g. for normalized UnsignedByte attribute).
+    pub fn to_bytes(&self) -> [u8; 4] {
+        let to_byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        [to_byte(self.red), to_byte(self.green), to_byte(self.blue), to_byte(self.alpha)]
+    }
+
     pub fn from_hex(color: u32) -> Self {
         let r = (color & mask::RED)   >> 24;
         let g = (color & mask::GREEN) >> 16;
@@ -1,7 +1,8 @@
 use super::super::gl_wrapper::element_buffer_object::ElementBufferObject;
+use super::super::gl_wrapper::backend::interface::GlBackend;
 use super::super::runtime_error::{SWGLResult};
 
-pub fn get_rectangle_ebo(context: &crate::AppContext, count: usize) -> SWGLResult<ElementBufferObject> {
+pub fn get_rectangle_ebo<B: GlBackend>(context: &B, count: usize) -> SWGLResult<ElementBufferObject<B>> {
 
     // 0 1 2 2 3 1
     // 4 5 6 6 7 5
@@ -1,27 +1,27 @@
 use super::super::runtime_error::{SWGLResult};
 use super::super::gl_wrapper::vertex_array_object;
 use super::super::gl_wrapper::vertex_buffer_object;
+use super::super::gl_wrapper::backend::interface::GlBackend;
 use super::vertex_2d::interface::VertexType;
 
 /// This type is simple box for VAO & VBO & Data.
-pub struct GeometryObject<T: VertexType + Copy> {
-    vao: vertex_array_object::VertexArrayObject,
-    vbo: vertex_buffer_object::VertexBufferObject,
+pub struct GeometryObject<T: VertexType + Copy, B: GlBackend = crate::AppContext> {
+    vao: vertex_array_object::VertexArrayObject<B>,
+    vbo: vertex_buffer_object::VertexBufferObject<B>,
     data: Vec<T>,
+    /// VBO data of vertices, reused in every flush_data.
+    staging: Vec<u8>,
 }
 
-impl<T: VertexType + Copy> GeometryObject<T> {
+impl<T: VertexType + Copy, B: GlBackend> GeometryObject<T, B> {
 
-    pub fn new(context: &crate::AppContext, vertices: &[T], storage: vertex_buffer_object::DataStorageType) -> SWGLResult<Self> {
-        let mut vbo_data = vec![];
-        for v in vertices {
-            let mut portion = v.to_vec();
-            vbo_data.append(&mut portion);
-        }
+    pub fn new(context: &B, vertices: &[T], storage: vertex_buffer_object::DataStorageType) -> SWGLResult<Self> {
+        let mut staging = vec![];
+        T::write_all_bytes(vertices, &mut staging);
 
-        let vbo = vertex_buffer_object::VertexBufferObject::new(
+        let vbo = vertex_buffer_object::VertexBufferObject::new_from_bytes(
             context,
-            &vbo_data,
+            &staging,
             storage,
         )?;
 
@@ -32,12 +32,12 @@ impl<T: VertexType + Copy> GeometryObject<T> {
             T::get_vao_signature(),
         )?;
 
-        Ok(GeometryObject { vao, vbo, data: vertices.iter().map(|v| *v).collect() })
+        Ok(GeometryObject { vao, vbo, data: vertices.iter().map(|v| *v).collect(), staging })
     }
 
     // -----------------------------------------------------------------------------------------------------------
 
-    pub fn new_empty_dynamic(context: &crate::AppContext, vertex_count: usize) -> SWGLResult<Self> {
+    pub fn new_empty_dynamic(context: &B, vertex_count: usize) -> SWGLResult<Self> {
         let vbo = vertex_buffer_object::VertexBufferObject::new_empty(
             context,
             vertex_count * T::get_row_width() as usize,
@@ -51,18 +51,18 @@ impl<T: VertexType + Copy> GeometryObject<T> {
             T::get_vao_signature(),
         )?;
 
-        Ok(GeometryObject { vao, vbo, data: Vec::new() })
+        Ok(GeometryObject { vao, vbo, data: Vec::new(), staging: Vec::new() })
     }
 
     // -----------------------------------------------------------------------------------------------------------
 
-    pub fn get_vao(&self) -> &vertex_array_object::VertexArrayObject {
+    pub fn get_vao(&self) -> &vertex_array_object::VertexArrayObject<B> {
         &self.vao
     }
 
     // -----------------------------------------------------------------------------------------------------------
 
-    pub fn get_vbo(&self) -> &vertex_buffer_object::VertexBufferObject {
+    pub fn get_vbo(&self) -> &vertex_buffer_object::VertexBufferObject<B> {
         &self.vbo
     }
 
@@ -78,13 +78,10 @@ impl<T: VertexType + Copy> GeometryObject<T> {
 
     // -----------------------------------------------------------------------------------------------------------
 
-    pub fn flush_data(&mut self, context: &crate::AppContext) -> SWGLResult<()> {
-        let mut vbo_data = vec![];
-        for v in &self.data {
-            let mut portion = v.to_vec();
-            vbo_data.append(&mut portion);
-        }
-        self.vbo.update_data_safe(context, &vbo_data, 0)?;
+    /// This method uploads data to VBO, staging buffer is reused so it allocates only when the data grows.
+    pub fn flush_data(&mut self, context: &B) -> SWGLResult<()> {
+        T::write_all_bytes(&self.data, &mut self.staging);
+        self.vbo.update_bytes_safe(context, &self.staging, 0)?;
         Ok(())
     }
 }
@@ -0,0 +1,62 @@
+use nalgebra_glm as glm;
+
+use super::super::super::runtime_error::SWGLResult;
+use super::super::super::camera2d::interface::CameraType;
+use super::super::super::gl_wrapper::backend::interface::GlBackend;
+use super::super::super::gl_wrapper::uniform_buffer_object::UniformBufferObject;
+use super::super::super::gl_wrapper::vertex_buffer_object::DataStorageType;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// Data of camera uniform block, it matches shader_collections::CAMERA_UNIFORM_BLOCK.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraUniforms {
+    pub projection: glm::Mat4,
+    pub model: glm::Mat4,
+}
+
+crate::impl_std140!(CameraUniforms { projection, model });
+
+impl CameraUniforms {
+    pub fn from_camera(camera: &dyn CameraType) -> Self {
+        Self {
+            projection: camera.matrix(),
+            model: camera.dedicated_model(),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This type keeps camera matrices in one UBO shared by every renderer and program in a frame. Renderers use it when
+/// RendererConf::camera_uniform_block_name is set (with the same binding).
+pub struct CameraUniformBuffer<B: GlBackend = crate::AppContext> {
+    ubo: UniformBufferObject<B>,
+    binding: u32,
+}
+
+impl<B: GlBackend> CameraUniformBuffer<B> {
+    pub fn new(context: &B, binding: u32) -> SWGLResult<Self> {
+        let ubo = UniformBufferObject::new_empty(
+            context,
+            2 * std::mem::size_of::<glm::Mat4>(),
+            DataStorageType::DynamicDraw,
+        )?;
+        Ok(Self { ubo, binding })
+    }
+
+    /// This method uploads matrices of the camera and binds the buffer to its binding point.
+    pub fn update(&self, context: &B, camera: &dyn CameraType) -> SWGLResult<()> {
+        self.ubo.update_value(context, &CameraUniforms::from_camera(camera))?;
+        self.ubo.bind_base(context, self.binding);
+        Ok(())
+    }
+
+    pub fn get_binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn ubo(&self) -> &UniformBufferObject<B> {
+        &self.ubo
+    }
+}
@@ -1,70 +1,74 @@
 use std::marker::PhantomData;
-use nalgebra_glm as glm;
 
 use super::super::super::runtime_error::SWGLResult;
 use super::super::super::gl_wrapper::vertex_array_object;
-use super::super::super::gl_wrapper::vertex_buffer_object;
 use super::super::vertex_2d::interface::{DedicatedShader, VertexType};
 use super::super::super::camera2d::interface::CameraType;
 use super::super::super::gl_wrapper::shader::{self, Program};
 use super::super::super::gl_wrapper::texture::texture_2d::Texture2D;
+use super::super::super::gl_wrapper::texture::sampler::Sampler;
 use super::super::super::gl_wrapper::vertex_array_object::PrimitiveType;
+use super::super::super::gl_wrapper::backend::interface::GlBackend;
 
 use super::renderer_conf::RendererConf;
-use super::helpers::create_buffers;
+use super::helpers::{bind_camera_uniform_block, create_buffers, set_camera_uniforms, RendererVertexBuffer};
 
 // -----------------------------------------------------------------------------------------------------------
 
 /// This type is used for renders geometry direct from vertices. 
-pub struct GeometryRenderer<T>
+pub struct GeometryRenderer<T, B = crate::AppContext>
 where
     T: VertexType,
+    B: GlBackend,
 {
     phantom: PhantomData<T>,
-    program: Program,
-    vao: vertex_array_object::VertexArrayObject,
-    vbo: vertex_buffer_object::VertexBufferObject,
+    program: Program<B>,
+    vao: vertex_array_object::VertexArrayObject<B>,
+    vbo: RendererVertexBuffer<B>,
+    /// VBO data of vertices, reused in every draw.
+    staging: Vec<u8>,
     conf: RendererConf,
 }
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl<T> GeometryRenderer<T>
+impl<T, B> GeometryRenderer<T, B>
 where
     T: VertexType + DedicatedShader,
+    B: GlBackend,
 {
-    pub fn init(context: &crate::AppContext, max_vertices_number: usize) -> SWGLResult<Self> {
+    pub fn init(context: &B, max_vertices_number: usize) -> SWGLResult<Self> {
+        Self::init_with_conf(context, max_vertices_number, RendererConf::default())
+    }
+
+    /// This method uses the dedicated shader with given configuration (e.g. DataStorageType::StreamDraw vertex storage).
+    pub fn init_with_conf(context: &B, max_vertices_number: usize, conf: RendererConf) -> SWGLResult<Self> {
         let program = Program::new(
             context,
             &shader::split_vfshader_to_shader_source(T::get_dedicated_shader()),
         )?;
 
-        let (vbo, vao) = create_buffers::<T>(context, max_vertices_number)?;
-
-        Ok(Self {
-            phantom: PhantomData,
-            program,
-            vbo,
-            vao,
-            conf: RendererConf::default(),
-        })
+        Self::init_with_custom_shader(context, max_vertices_number, program, conf)
     }
 }
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl<T> GeometryRenderer<T>
+impl<T, B> GeometryRenderer<T, B>
 where
     T: VertexType,
+    B: GlBackend,
 {
-    pub fn init_with_custom_shader(context: &crate::AppContext, max_vertices_number: usize, program: Program, conf: RendererConf) -> SWGLResult<Self> {
-        let (vbo, vao) = create_buffers::<T>(context, max_vertices_number)?;
+    pub fn init_with_custom_shader(context: &B, max_vertices_number: usize, program: Program<B>, conf: RendererConf) -> SWGLResult<Self> {
+        let (vbo, vao) = create_buffers::<T, B>(context, &program, max_vertices_number, &conf)?;
+        bind_camera_uniform_block(context, &program, &conf)?;
 
         Ok(Self {
             phantom: PhantomData,
             program,
             vbo,
             vao,
+            staging: Vec::with_capacity(max_vertices_number * T::get_row_width() as usize * 4),
             conf,
         })
     }
@@ -72,13 +76,14 @@ where
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl<T> GeometryRenderer<T>
+impl<T, B> GeometryRenderer<T, B>
 where
     T: VertexType,
+    B: GlBackend,
 {
 
     /// This method prepares given number of textures for shader. 
-    pub fn prepare_textures(&self, context: &crate::AppContext, count: usize) -> SWGLResult<()> {
+    pub fn prepare_textures(&self, context: &B, count: usize) -> SWGLResult<()> {
         for i in 0..count {
             self.program.set1i(context, &format!("{}{}", self.conf.texture_uniform_prefix, i), i as i32)?;
         }
@@ -86,40 +91,137 @@ where
     }
 
     /// This method activates textures for shader before draw. 
-    pub fn bind_textures(&self, context: &crate::AppContext, texture_set: &[&Texture2D]) {
+    /// Textures are sampled with their own parameters (samplers bound to their units are unbound).
+    pub fn bind_textures(&self, context: &B, texture_set: &[&Texture2D<B>]) {
+        self.bind_textures_with_samplers(context, texture_set, &[]);
+    }
+
+    /// This method activates textures like bind_textures, sampler of every texture unit is given by samplers at the
+    /// same index. Units without sampler (None or missing entry) use parameters of their textures.
+    pub fn bind_textures_with_samplers(&self, context: &B, texture_set: &[&Texture2D<B>], samplers: &[Option<&Sampler<B>>]) {
         for (i, tex) in texture_set.iter().enumerate() {
             tex.active(context, i as u32);
+            match samplers.get(i).copied().flatten() {
+                Some(sampler) => sampler.bind(context, i as u32),
+                None => Sampler::unbind(context, i as u32),
+            }
         }
     }
 
-    pub fn draw(&self, context: &crate::AppContext, vertices: &[T], draw_type: PrimitiveType, camera: &dyn CameraType) -> SWGLResult<()> {
+    /// This method doesn't allocate memory as long as the number of vertices doesn't exceed max_vertices_number given
+    /// in init.
+    pub fn draw(&mut self, context: &B, vertices: &[T], draw_type: PrimitiveType, camera: &dyn CameraType) -> SWGLResult<()> {
         self.program.use_program(context);
-        self.program.set_mat_4x4f(
-            context,
-            &self.conf.projection_matrix_uniform_name,
-            glm::value_ptr(&camera.matrix()),
-        ).unwrap();
-
-        self.program.set_mat_4x4f(
-            context,
-            &self.conf.model_matrix_uniform_name,
-            glm::value_ptr(&camera.dedicated_model()),
-        ).unwrap();
+        set_camera_uniforms(context, &self.program, &self.conf, camera)?;
 
         self.vao.bind(context);
-        let mut vbo_data = vec![];
-        for v in vertices {
-            let mut portion = v.to_vec();
-            vbo_data.append(&mut portion);
-        }
+        T::write_all_bytes(vertices, &mut self.staging);
 
-        self.vbo.update_data_safe(context, &vbo_data, 0)?;
-        self.vao.draw_arrays(context, draw_type, 0, vertices.len() as u32);
+        let first_vertex = self.vbo.upload(context, &self.staging)?;
+        self.vao.draw_arrays(context, draw_type, first_vertex, vertices.len() as u32);
 
         Ok(())
     }
 
-    pub fn program(&self) -> &Program {
+    pub fn program(&self) -> &Program<B> {
         &self.program
     }
 }
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm as glm;
+    use super::super::super::color::Color;
+    use super::super::super::vertex_2d::predefined::color_vertex2d::ColorVertex2D;
+    use super::super::super::super::camera2d::ratio_view::RatioView;
+    use super::super::super::super::global_tools::vector2::Vector2;
+    use super::super::super::super::gl_wrapper::backend::recording_backend::{GlCall, RecordingBackend, UniformData};
+    use super::super::super::super::gl_wrapper::vertex_buffer_object::DataStorageType;
+    use super::super::super::super::gl_wrapper::vertex_buffer_object::streaming::StreamingStrategy;
+
+    /// Size of ColorVertex2D in bytes (position, color and z_index).
+    const COLOR_VERTEX_SIZE: usize = 4 * 4;
+
+    fn triangle() -> [ColorVertex2D; 3] {
+        let color = Color::new(0.0, 1.0, 0.0, 1.0);
+        [
+            ColorVertex2D::new(Vector2::new(0.0, 0.0), color, 0.0),
+            ColorVertex2D::new(Vector2::new(1.0, 0.0), color, 0.0),
+            ColorVertex2D::new(Vector2::new(0.0, 1.0), color, 0.0),
+        ]
+    }
+
+    #[test]
+    fn draw_uploads_vertices_and_draws_arrays() {
+        let context = RecordingBackend::new();
+        let mut renderer = GeometryRenderer::<ColorVertex2D, _>::init(&context, 6).unwrap();
+        let camera = RatioView::new(Vector2::new(800.0, 600.0), 1.0);
+        context.clear_calls();
+
+        renderer.draw(&context, &triangle(), PrimitiveType::Triangles, &camera).unwrap();
+
+        assert!(context.calls().contains(&GlCall::BufferSubData {
+            target: crate::AppContext::ARRAY_BUFFER,
+            offset: 0,
+            size: 3 * COLOR_VERTEX_SIZE,
+        }));
+        assert_eq!(
+            context.draw_calls(),
+            vec![GlCall::DrawArrays { mode: crate::AppContext::TRIANGLES, first: 0, count: 3 }]
+        );
+
+        let program = *renderer.program().get_program();
+        assert_eq!(
+            context.uniform(program, "projection"),
+            Some(UniformData::Matrix4(glm::value_ptr(&camera.matrix()).to_vec()))
+        );
+        assert_eq!(
+            context.uniform(program, "model"),
+            Some(UniformData::Matrix4(glm::value_ptr(&camera.dedicated_model()).to_vec()))
+        );
+
+        // position, color packed into bytes and z_index of the first vertex
+        let vertices = context.buffer_data(*renderer.vbo.get_vbo().get_raw_id()).unwrap();
+        assert_eq!(&vertices[8..12], &[0, 255, 0, 255]);
+        let vertices = context.buffer_data_f32(*renderer.vbo.get_vbo().get_raw_id()).unwrap();
+        assert_eq!(&vertices[..2], &[0.0, 0.0]);
+        assert_eq!(vertices[3], 0.0);
+        assert_eq!(&vertices[4..6], &[1.0, 0.0]);
+    }
+
+    #[test]
+    fn streaming_ring_buffer_draws_from_uploaded_region() {
+        let context = RecordingBackend::new();
+        let mut conf = RendererConf::default();
+        conf.vertex_storage = DataStorageType::StreamDraw;
+        conf.streaming_strategy = StreamingStrategy::RingBuffer(3);
+        let mut renderer = GeometryRenderer::<ColorVertex2D, _>::init_with_conf(&context, 3, conf).unwrap();
+        let camera = RatioView::new(Vector2::new(800.0, 600.0), 1.0);
+        context.clear_calls();
+
+        for _ in 0..3 {
+            renderer.draw(&context, &triangle(), PrimitiveType::Triangles, &camera).unwrap();
+        }
+
+        let firsts: Vec<i32> = context
+            .calls()
+            .iter()
+            .filter_map(|call| match call {
+                GlCall::BufferSubData { target: crate::AppContext::ARRAY_BUFFER, offset, .. } => {
+                    Some((offset / COLOR_VERTEX_SIZE) as i32)
+                }
+                _ => None,
+            })
+            .collect();
+        let draws: Vec<GlCall> = firsts
+            .iter()
+            .map(|first| GlCall::DrawArrays { mode: crate::AppContext::TRIANGLES, first: *first, count: 3 })
+            .collect();
+
+        assert_eq!(firsts, vec![0, 3, 6]);
+        assert_eq!(context.draw_calls(), draws);
+    }
+}
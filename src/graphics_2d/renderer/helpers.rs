@@ -1,26 +1,77 @@
 use super::super::super::gl_wrapper::vertex_array_object::VertexArrayObject;
 use super::super::super::gl_wrapper::vertex_buffer_object;
 use super::super::super::gl_wrapper::vertex_buffer_object::VertexBufferObject;
+use super::super::super::gl_wrapper::vertex_buffer_object::streaming::StreamingVertexBuffer;
 use super::super::super::gl_wrapper::element_buffer_object::ElementBufferObject;
+use super::super::super::gl_wrapper::shader::Program;
+use super::super::super::camera2d::interface::CameraType;
+use super::renderer_conf::RendererConf;
+use nalgebra_glm as glm;
 use super::super::super::runtime_error::SWGLResult;
+use super::super::super::gl_wrapper::backend::interface::GlBackend;
 use super::super::vertex_2d::interface::VertexType;
 
-pub fn create_buffers<T>(
-    context: &crate::AppContext,
+/// VBO of renderer, StreamingVertexBuffer is used when RendererConf::vertex_storage is StreamDraw.
+pub enum RendererVertexBuffer<B: GlBackend> {
+    Dynamic(VertexBufferObject<B>),
+    Streaming(StreamingVertexBuffer<B>),
+}
+
+impl<B: GlBackend> RendererVertexBuffer<B> {
+    pub fn get_vbo(&self) -> &VertexBufferObject<B> {
+        match self {
+            Self::Dynamic(vbo) => vbo,
+            Self::Streaming(stream) => stream.get_vbo(),
+        }
+    }
+
+    /// This method uploads VBO data and returns index of the first uploaded vertex.
+    pub fn upload(&mut self, context: &B, data: &[u8]) -> SWGLResult<u32> {
+        match self {
+            Self::Dynamic(vbo) => {
+                vbo.update_bytes_safe(context, data, 0)?;
+                Ok(0)
+            }
+            Self::Streaming(stream) => {
+                let (offset, slice) = stream.allocate(data.len())?;
+                slice.copy_from_slice(data);
+                stream.flush(context);
+                Ok((offset / stream.get_row_size()) as u32)
+            }
+        }
+    }
+}
+
+/// This function also checks if the vertex type matches inputs of the program used for rendering.
+pub fn create_buffers<T, B>(
+    context: &B,
+    program: &Program<B>,
     max_vertices_number: usize,
-) -> SWGLResult<(VertexBufferObject, VertexArrayObject)>
+    conf: &RendererConf,
+) -> SWGLResult<(RendererVertexBuffer<B>, VertexArrayObject<B>)>
 where
     T: VertexType,
+    B: GlBackend,
 {
-    let vbo = VertexBufferObject::new_empty(
-        context,
-        max_vertices_number * T::get_row_width() as usize,
-        vertex_buffer_object::DataStorageType::DynamicDraw,
-    )?;
+    program.reflect(context).check_vao_signature(&T::get_vao_signature())?;
+
+    let vbo = match conf.vertex_storage {
+        vertex_buffer_object::DataStorageType::StreamDraw => RendererVertexBuffer::Streaming(StreamingVertexBuffer::new(
+            context,
+            T::get_row_width() as usize * 4,
+            max_vertices_number,
+            conf.streaming_strategy,
+        )?),
+        storage => RendererVertexBuffer::Dynamic(VertexBufferObject::new_empty(
+            context,
+            max_vertices_number * T::get_row_width() as usize,
+            storage,
+        )?),
+    };
 
     let vao = VertexArrayObject::new(
         context,
-        &vbo,
+        vbo.get_vbo(),
         T::get_row_width(),
         T::get_vao_signature(),
     )?;
@@ -28,7 +79,39 @@ where
     Ok((vbo, vao))
 }
 
-pub fn create_ebo_buffer(context: &crate::AppContext, count: usize) -> SWGLResult<ElementBufferObject> {
+/// This function connects camera uniform block of the program (if it is used by the configuration).
+pub fn bind_camera_uniform_block<B: GlBackend>(context: &B, program: &Program<B>, conf: &RendererConf) -> SWGLResult<()> {
+    if let Some(name) = &conf.camera_uniform_block_name {
+        program.bind_uniform_block(context, name, conf.camera_uniform_block_binding)?;
+    }
+    Ok(())
+}
+
+/// This function uploads camera matrices as separate uniforms, unless they are provided by the camera uniform block.
+pub fn set_camera_uniforms<B: GlBackend>(
+    context: &B,
+    program: &Program<B>,
+    conf: &RendererConf,
+    camera: &dyn CameraType,
+) -> SWGLResult<()> {
+    if conf.camera_uniform_block_name.is_some() {
+        return Ok(());
+    }
+
+    program.set_mat_4x4f(
+        context,
+        &conf.projection_matrix_uniform_name,
+        glm::value_ptr(&camera.matrix()),
+    )?;
+
+    program.set_mat_4x4f(
+        context,
+        &conf.model_matrix_uniform_name,
+        glm::value_ptr(&camera.dedicated_model()),
+    )
+}
+
+pub fn create_ebo_buffer<B: GlBackend>(context: &B, count: usize) -> SWGLResult<ElementBufferObject<B>> {
     let mut indices = vec![];
     for i in 0..count {
         let i = i as u32;
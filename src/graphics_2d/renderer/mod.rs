@@ -3,4 +3,5 @@
 pub mod renderer_conf;
 pub mod geometry_renderer;
 pub mod rectangle_renderer;
+pub mod camera_uniform_buffer;
 mod helpers; 
\ No newline at end of file
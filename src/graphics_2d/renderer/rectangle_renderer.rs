@@ -1,72 +1,79 @@
 use std::marker::PhantomData;
-use nalgebra_glm as glm;
 
 use super::super::super::runtime_error::SWGLResult;
 use super::super::vertex_2d::interface::{DedicatedShader, VertexType, TextureCoords};
 use super::super::super::camera2d::interface::CameraType;
 use super::super::super::gl_wrapper::shader::{self, Program};
 use super::super::super::gl_wrapper::texture::texture_2d::Texture2D;
+use super::super::super::gl_wrapper::texture::sampler::Sampler;
 use super::super::super::gl_wrapper::vertex_array_object::PrimitiveType;
 use super::super::super::gl_wrapper::vertex_array_object::VertexArrayObject;
-use super::super::super::gl_wrapper::vertex_buffer_object::VertexBufferObject;
 use super::super::super::gl_wrapper::element_buffer_object::ElementBufferObject;
+use super::super::super::gl_wrapper::vertex_buffer_object::DataStorageType;
+use super::super::super::gl_wrapper::backend::interface::GlBackend;
 use super::super::super::global_tools::vector2::Vector2;
 use super::renderer_conf::RendererConf;
-use super::helpers::{create_buffers, create_ebo_buffer};
+use super::helpers::{bind_camera_uniform_block, create_buffers, create_ebo_buffer, set_camera_uniforms, RendererVertexBuffer};
 
 // -----------------------------------------------------------------------------------------------------------
 
 /// This type is used for renders rectangular geometry for given vertex type. 
-pub struct RectangleRenderer<T>
+pub struct RectangleRenderer<T, B = crate::AppContext>
 where
     T: VertexType,
+    B: GlBackend,
 {
     phantom: PhantomData<T>,
-    program: Program,
-    vao: VertexArrayObject,
-    vbo: VertexBufferObject,
-    ebo: ElementBufferObject,
+    program: Program<B>,
+    vao: VertexArrayObject<B>,
+    vbo: RendererVertexBuffer<B>,
+    ebo: ElementBufferObject<B>,
     vertices: Vec<T>, 
+    /// VBO data of vertices, reused in every flush.
+    staging: Vec<u8>,
+    texture_uniform_name: String,
     conf: RendererConf,
 }
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl<T> RectangleRenderer<T>
+impl<T, B> RectangleRenderer<T, B>
 where
     T: VertexType + DedicatedShader,
+    B: GlBackend,
 {
-    pub fn init(context: &crate::AppContext, max_rectangle_number: usize) -> SWGLResult<Self> {
+    pub fn init(context: &B, max_rectangle_number: usize) -> SWGLResult<Self> {
+        Self::init_with_conf(context, max_rectangle_number, RendererConf::default())
+    }
+
+    /// This method uses the dedicated shader with given configuration (e.g. DataStorageType::StreamDraw vertex storage).
+    pub fn init_with_conf(context: &B, max_rectangle_number: usize, conf: RendererConf) -> SWGLResult<Self> {
         let program = Program::new(
             context,
             &shader::split_vfshader_to_shader_source(T::get_dedicated_shader()),
         )?;
 
-        let (vbo, vao) = create_buffers::<T>(context, max_rectangle_number * 4)?;
-        let ebo = create_ebo_buffer(context, max_rectangle_number * 6)?; 
-
-        Ok(Self {
-            phantom: PhantomData,
-            program,
-            vbo,
-            vao,
-            ebo,
-            vertices: Vec::with_capacity(max_rectangle_number * 4), 
-            conf: RendererConf::default(),
-        })
+        Self::init_with_custom_shader(context, max_rectangle_number, program, conf)
     }
 }
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl<T> RectangleRenderer<T>
+impl<T, B> RectangleRenderer<T, B>
 where
     T: VertexType,
+    B: GlBackend,
 {
-    pub fn init_with_custom_shader(context: &crate::AppContext, max_rectangle_number: usize, program: Program, conf: RendererConf) -> SWGLResult<Self> {
+    pub fn init_with_custom_shader(context: &B, max_rectangle_number: usize, program: Program<B>, conf: RendererConf) -> SWGLResult<Self> {
        
-        let (vbo, vao) = create_buffers::<T>(context, max_rectangle_number * 4)?;
-        let ebo = create_ebo_buffer(context, max_rectangle_number * 6)?; 
+        let (vbo, vao) = create_buffers::<T, B>(context, &program, max_rectangle_number * 4, &conf)?;
+        // Streaming buffer keeps rectangles of all regions, so indices have to cover all of them.
+        let region_count = match conf.vertex_storage {
+            DataStorageType::StreamDraw => conf.streaming_strategy.get_region_count() as usize,
+            _ => 1,
+        };
+        let ebo = create_ebo_buffer(context, max_rectangle_number * region_count * 6)?; 
+        bind_camera_uniform_block(context, &program, &conf)?;
 
         Ok(Self {
             phantom: PhantomData,
@@ -75,6 +82,8 @@ where
             vao,
             ebo,
             vertices: Vec::with_capacity(max_rectangle_number * 4), 
+            staging: Vec::with_capacity(max_rectangle_number * 4 * T::get_row_width() as usize * 4),
+            texture_uniform_name: format!("{}{}", conf.texture_uniform_prefix, 0),
             conf,
         })
     }
@@ -82,12 +91,13 @@ where
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl<T> RectangleRenderer<T>
+impl<T, B> RectangleRenderer<T, B>
 where
     T: VertexType + Clone,
+    B: GlBackend,
 {
     /// This method prepares given number of textures for shader (for the most scenarios you don't need to use it). 
-    pub fn prepare_textures(&self, context: &crate::AppContext, count: usize) -> SWGLResult<()> {
+    pub fn prepare_textures(&self, context: &B, count: usize) -> SWGLResult<()> {
         for i in 0..count {
             self.program.set1i(context, &format!("{}{}", self.conf.texture_uniform_prefix, i), i as i32)?;
         }
@@ -95,88 +105,88 @@ where
     }
 
     /// This method activates textures for shader before draw (for the most scenarios you don't need to use it). 
-    pub fn bind_textures(&self, context: &crate::AppContext, texture_set: &[&Texture2D]) {
+    /// Textures are sampled with their own parameters (samplers bound to their units are unbound).
+    pub fn bind_textures(&self, context: &B, texture_set: &[&Texture2D<B>]) {
+        self.bind_textures_with_samplers(context, texture_set, &[]);
+    }
+
+    /// This method activates textures like bind_textures, sampler of every texture unit is given by samplers at the
+    /// same index. Units without sampler (None or missing entry) use parameters of their textures.
+    pub fn bind_textures_with_samplers(&self, context: &B, texture_set: &[&Texture2D<B>], samplers: &[Option<&Sampler<B>>]) {
         for (i, tex) in texture_set.iter().enumerate() {
             tex.active(context, i as u32);
+            match samplers.get(i).copied().flatten() {
+                Some(sampler) => sampler.bind(context, i as u32),
+                None => Sampler::unbind(context, i as u32),
+            }
         }
     }
 
-    /// This method renders all vertices.
-    pub fn flush(&mut self, context: &crate::AppContext, camera: &dyn CameraType, texture: Option<&Texture2D>) -> SWGLResult<()> {
+    /// This method renders all vertices. It doesn't allocate memory as long as the number of vertices doesn't exceed
+    /// max_rectangle_number given in init.
+    pub fn flush(&mut self, context: &B, camera: &dyn CameraType, texture: Option<&Texture2D<B>>) -> SWGLResult<()> {
         self.program.use_program(context);
 
         if let Some(tex) = texture {
-            self.program.set1i(context, &format!("{}{}", self.conf.texture_uniform_prefix, 0), 0 as i32)?;
+            self.program.set1i(context, &self.texture_uniform_name, 0 as i32)?;
             tex.active(context, 0 as u32);
         }
 
-        self.program.set_mat_4x4f(
-            context,
-            &self.conf.projection_matrix_uniform_name,
-            glm::value_ptr(&camera.matrix()),
-        ).unwrap();
-
-        self.program.set_mat_4x4f(
-            context,
-            &self.conf.model_matrix_uniform_name,
-            glm::value_ptr(&camera.dedicated_model()),
-        ).unwrap();
+        set_camera_uniforms(context, &self.program, &self.conf, camera)?;
 
         self.vao.bind(context);
-        let mut vbo_data = vec![];
-        for v in &self.vertices {
-            let mut portion = v.to_vec();
-            vbo_data.append(&mut portion);
-        }
+        T::write_all_bytes(&self.vertices, &mut self.staging);
 
-        self.vbo.update_data_safe(context, &vbo_data, 0)?;
-        self.vao.draw_elements(context, PrimitiveType::Triangles, 0, (self.vertices.len() / 4 * 6) as u32, &self.ebo);
+        let first_vertex = self.vbo.upload(context, &self.staging)?;
+        self.vao.draw_elements(context, PrimitiveType::Triangles, first_vertex / 4 * 6, (self.vertices.len() / 4 * 6) as u32, &self.ebo);
 
         self.vertices.clear(); 
 
         Ok(())
     }
 
-    pub fn program(&self) -> &Program {
+    pub fn program(&self) -> &Program<B> {
         &self.program
     }
 }
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl<T> RectangleRenderer<T>
+impl<T, B> RectangleRenderer<T, B>
 where
     T: VertexType + Clone,
+    B: GlBackend,
 {
     pub fn add_rect(&mut self, vertex: T, position: &Vector2<f32>, size: &Vector2<f32>) {
-        let mut tmp = vec![vertex; 4];
+        let mut tmp = [vertex.clone(), vertex.clone(), vertex.clone(), vertex];
         tmp[0].set_position(&Vector2::new(position.x, position.y));
         tmp[1].set_position(&Vector2::new(position.x + size.x, position.y));
         tmp[2].set_position(&Vector2::new(position.x + size.x, position.y + size.y));
         tmp[3].set_position(&Vector2::new(position.x, position.y + size.y));
-        self.vertices.append(&mut tmp);
+        self.vertices.extend_from_slice(&tmp);
     }
 
     pub fn add_rect_with_trans(&mut self, vertex: T, position: &Vector2<f32>, size: &Vector2<f32>, origin: &Vector2<f32>, rotation: f32) {
-        let mut tmp = vec![vertex; 4];
+        let mut tmp = [vertex.clone(), vertex.clone(), vertex.clone(), vertex];
 
         tmp[0].set_position(&(Vector2::new(0.0, 0.0) - *origin + *position).rotated_around(rotation, &position));
         tmp[1].set_position(&(Vector2::new(size.x, 0.0) - *origin + *position).rotated_around(rotation, &position));
         tmp[2].set_position(&(Vector2::new(size.x, size.y) - *origin + *position).rotated_around(rotation, &position));
         tmp[3].set_position(&(Vector2::new(0.0, size.y) - *origin + *position).rotated_around(rotation, &position));
 
-        self.vertices.append(&mut tmp);
+        self.vertices.extend_from_slice(&tmp);
     }
 }
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl<T> RectangleRenderer<T>
+impl<T, B> RectangleRenderer<T, B>
 where
     T: VertexType + TextureCoords + Clone,
+    B: GlBackend,
 {
     pub fn add_sprite(&mut self, vertex: T, position: &Vector2<f32>, size: &Vector2<f32>) {
-        let mut tmp = vec![vertex; 4];
+        let mut tmp = [vertex.clone(), vertex.clone(), vertex.clone(), vertex];
 
         tmp[0].set_position(&Vector2::new(position.x, position.y));
         tmp[0].set_tex_coords(&Vector2::new(0.0, 0.0));
@@ -190,11 +200,11 @@ where
         tmp[3].set_position(&Vector2::new(position.x, position.y + size.y));
         tmp[3].set_tex_coords(&Vector2::new(0.0, 1.0));
 
-        self.vertices.append(&mut tmp);
+        self.vertices.extend_from_slice(&tmp);
     }
 
     pub fn add_sprite_with_trans(&mut self, vertex: T, position: &Vector2<f32>, size: &Vector2<f32>, origin: &Vector2<f32>, rotation: f32) {
-        let mut tmp = vec![vertex; 4];
+        let mut tmp = [vertex.clone(), vertex.clone(), vertex.clone(), vertex];
 
         tmp[0].set_position(&(Vector2::new(0.0, 0.0) - *origin + *position).rotated_around(rotation, &position));
         tmp[0].set_tex_coords(&Vector2::new(0.0, 0.0));
@@ -208,6 +218,186 @@ where
         tmp[3].set_position(&(Vector2::new(0.0, size.y) - *origin + *position).rotated_around(rotation, &position));
         tmp[3].set_tex_coords(&Vector2::new(0.0, 1.0));
 
-        self.vertices.append(&mut tmp);
+        self.vertices.extend_from_slice(&tmp);
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm as glm;
+    use super::super::super::color::Color;
+    use super::super::super::vertex_2d::predefined::color_vertex2d::ColorVertex2D;
+    use super::super::super::vertex_2d::predefined::single_tex_vertex2d::SingleTexVertex2D;
+    use super::super::super::super::camera2d::ratio_view::RatioView;
+    use super::super::super::super::gl_wrapper::backend::recording_backend::{GlCall, RecordingBackend, UniformData};
+    use super::super::super::super::gl_wrapper::texture::texture_config::{TextureConfiguration, TextureFilter, TextureFormat, TextureWrap};
+    use super::super::super::super::gl_wrapper::vertex_buffer_object::streaming::StreamingStrategy;
+
+    /// Size of ColorVertex2D in bytes (position, color and z_index).
+    const COLOR_VERTEX_SIZE: usize = 4 * 4;
+
+    fn red_vertex() -> ColorVertex2D {
+        ColorVertex2D::new_general(Color::new(1.0, 0.0, 0.0, 1.0), 0.0)
+    }
+
+    fn camera() -> RatioView {
+        RatioView::new(Vector2::new(800.0, 600.0), 1.0)
+    }
+
+    #[test]
+    fn flush_uploads_rectangles_and_draws_their_indices() {
+        let context = RecordingBackend::new();
+        let mut renderer = RectangleRenderer::<ColorVertex2D, _>::init(&context, 4).unwrap();
+        let camera = camera();
+        context.clear_calls();
+
+        renderer.add_rect(red_vertex(), &Vector2::new(0.0, 0.0), &Vector2::new(1.0, 1.0));
+        renderer.add_rect(red_vertex(), &Vector2::new(2.0, 2.0), &Vector2::new(1.0, 2.0));
+        renderer.flush(&context, &camera, None).unwrap();
+
+        let calls = context.calls();
+        assert!(calls.contains(&GlCall::BufferSubData {
+            target: crate::AppContext::ARRAY_BUFFER,
+            offset: 0,
+            size: 2 * 4 * COLOR_VERTEX_SIZE,
+        }));
+        assert_eq!(
+            context.draw_calls(),
+            vec![GlCall::DrawElements {
+                mode: crate::AppContext::TRIANGLES,
+                count: 12,
+                data_type: crate::AppContext::UNSIGNED_INT,
+                offset: 0,
+            }]
+        );
+
+        let program = *renderer.program().get_program();
+        assert_eq!(
+            context.uniform(program, "projection"),
+            Some(UniformData::Matrix4(glm::value_ptr(&camera.matrix()).to_vec()))
+        );
+        assert_eq!(
+            context.uniform(program, "model"),
+            Some(UniformData::Matrix4(glm::value_ptr(&camera.dedicated_model()).to_vec()))
+        );
+
+        let vertices = context.buffer_data_f32(*renderer.vbo.get_vbo().get_raw_id()).unwrap();
+        // position of the third vertex of the second rectangle
+        assert_eq!(&vertices[6 * 4..6 * 4 + 2], &[3.0, 4.0]);
+        assert!(renderer.vertices.is_empty());
+    }
+
+    #[test]
+    fn add_sprite_sets_texture_coords_and_texture_uniform() {
+        let context = RecordingBackend::new();
+        let mut renderer = RectangleRenderer::<SingleTexVertex2D, _>::init(&context, 1).unwrap();
+        let conf = TextureConfiguration::new(
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::Nearest,
+            TextureFilter::Nearest,
+            TextureFormat::Rgba8,
+        );
+        let texture = Texture2D::new_from_data(&context, 1, 1, &[255u8; 4], conf).unwrap();
+        context.clear_calls();
+
+        renderer.add_sprite(SingleTexVertex2D::new_general(0.0, 1.0), &Vector2::new(1.0, 1.0), &Vector2::new(2.0, 2.0));
+        renderer.flush(&context, &camera(), Some(&texture)).unwrap();
+
+        let program = *renderer.program().get_program();
+        assert_eq!(context.uniform(program, "tex_0"), Some(UniformData::Int(vec![0])));
+        assert!(context.calls().contains(&GlCall::ActiveTexture { texture: crate::AppContext::TEXTURE0 }));
+        assert_eq!(context.draw_calls().len(), 1);
+
+        // position, texture coords, z_index and texture_factor of every vertex
+        let vertices = context.buffer_data_f32(*renderer.vbo.get_vbo().get_raw_id()).unwrap();
+        assert_eq!(&vertices[6..12], &[3.0, 1.0, 1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(&vertices[18..24], &[1.0, 3.0, 0.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn bind_textures_with_samplers_binds_sampler_per_unit() {
+        let context = RecordingBackend::new();
+        let renderer = RectangleRenderer::<SingleTexVertex2D, _>::init(&context, 1).unwrap();
+        let conf = TextureConfiguration::new(
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::Linear,
+            TextureFilter::Linear,
+            TextureFormat::Rgba8,
+        );
+        let first = Texture2D::new_from_data(&context, 1, 1, &[255u8; 4], conf).unwrap();
+        let second = Texture2D::new_from_data(&context, 1, 1, &[0u8; 4], TextureConfiguration::default()).unwrap();
+        let nearest = Sampler::new(
+            &context,
+            TextureWrap::ClampToEdge,
+            TextureWrap::ClampToEdge,
+            TextureFilter::Nearest,
+            TextureFilter::Nearest,
+        )
+        .unwrap();
+
+        renderer.bind_textures_with_samplers(&context, &[&first, &second], &[Some(&nearest)]);
+        assert_eq!(context.bound_sampler(0), Some(*nearest.get_raw_id()));
+        assert_eq!(context.bound_sampler(1), None);
+
+        // textures bound without samplers use their own parameters again
+        renderer.bind_textures(&context, &[&first, &second]);
+        assert_eq!(context.bound_sampler(0), None);
+        assert!(context.calls().ends_with(&[
+            GlCall::ActiveTexture { texture: crate::AppContext::TEXTURE0 },
+            GlCall::BindTexture { target: crate::AppContext::TEXTURE_2D, texture: *first.get_raw_id() },
+            GlCall::BindSampler { unit: 0, sampler: None },
+            GlCall::ActiveTexture { texture: crate::AppContext::TEXTURE1 },
+            GlCall::BindTexture { target: crate::AppContext::TEXTURE_2D, texture: *second.get_raw_id() },
+            GlCall::BindSampler { unit: 1, sampler: None },
+        ]));
+    }
+
+    #[test]
+    fn streaming_ring_buffer_draws_indices_of_uploaded_region() {
+        let context = RecordingBackend::new();
+        let mut conf = RendererConf::default();
+        conf.vertex_storage = DataStorageType::StreamDraw;
+        conf.streaming_strategy = StreamingStrategy::RingBuffer(3);
+        let mut renderer = RectangleRenderer::<ColorVertex2D, _>::init_with_conf(&context, 2, conf).unwrap();
+        let camera = camera();
+        context.clear_calls();
+
+        for _ in 0..4 {
+            renderer.add_rect(red_vertex(), &Vector2::new(0.0, 0.0), &Vector2::new(1.0, 1.0));
+            renderer.flush(&context, &camera, None).unwrap();
+        }
+
+        let upload_offsets: Vec<usize> = context
+            .calls()
+            .iter()
+            .filter_map(|call| match call {
+                GlCall::BufferSubData { target: crate::AppContext::ARRAY_BUFFER, offset, size } => {
+                    assert_eq!(*size, 4 * COLOR_VERTEX_SIZE);
+                    Some(*offset)
+                }
+                _ => None,
+            })
+            .collect();
+        let draw_offsets: Vec<i32> = context
+            .draw_calls()
+            .iter()
+            .map(|call| match call {
+                GlCall::DrawElements { count: 6, offset, .. } => *offset,
+                call => panic!("unexpected draw call {:?}", call),
+            })
+            .collect();
+
+        assert_eq!(upload_offsets, vec![0, 64, 128, 192]);
+        // every rectangle uses 6 indices (4 bytes each) of its first vertex
+        let expected: Vec<i32> = upload_offsets
+            .iter()
+            .map(|offset| (offset / COLOR_VERTEX_SIZE / 4 * 6 * 4) as i32)
+            .collect();
+        assert_eq!(draw_offsets, expected);
     }
 }
@@ -1,10 +1,21 @@
 
+use super::super::super::gl_wrapper::vertex_buffer_object::DataStorageType;
+use super::super::super::gl_wrapper::vertex_buffer_object::streaming::StreamingStrategy;
+
 /// This type may be helpful when you need to use your own shader for a given renderer. 
 #[derive(Debug, Clone)]
 pub struct RendererConf {
     pub projection_matrix_uniform_name: String,
     pub model_matrix_uniform_name: String,
     pub texture_uniform_prefix: String,
+    /// Name of uniform block with camera matrices (see CameraUniformBuffer). When it is set, matrices are not uploaded
+    /// as separate uniforms, the block is connected with camera_uniform_block_binding instead.
+    pub camera_uniform_block_name: Option<String>,
+    pub camera_uniform_block_binding: u32,
+    /// Storage of the renderer VBO. With DataStorageType::StreamDraw vertices are written to StreamingVertexBuffer
+    /// (with streaming_strategy), so uploads don't wait for draws of previous frames.
+    pub vertex_storage: DataStorageType,
+    pub streaming_strategy: StreamingStrategy,
 }
 
 impl RendererConf {
@@ -13,6 +24,10 @@ impl RendererConf {
             projection_matrix_uniform_name: String::from("projection"),
             model_matrix_uniform_name: String::from("model"),
             texture_uniform_prefix: String::from("tex_"),
+            camera_uniform_block_name: None,
+            camera_uniform_block_binding: 0,
+            vertex_storage: DataStorageType::DynamicDraw,
+            streaming_strategy: StreamingStrategy::RingBuffer(3),
         }
     }
 }
@@ -1,3 +1,11 @@
+/// Camera uniform block (see renderer::camera_uniform_buffer), it can be registered as a ShaderPreprocessor snippet.
+pub const CAMERA_UNIFORM_BLOCK: &str = "
+layout (std140) uniform Camera {
+    mat4 projection;
+    mat4 model;
+};
+";
+
 
 pub const COLOR_VERTEX2D_SHADER: &'static str = "
 #shader vertex
@@ -38,7 +46,7 @@ pub const FULL_VERTEX2D_SHADER: &str = "
 layout (location=0) in vec2 pos;
 layout (location=1) in vec4 color;
 layout (location=2) in vec2 texture_coords;
-layout (location=3) in float array_index;
+layout (location=3) in uint array_index;
 layout (location=4) in float z_index;
 layout (location=5) in float texture_factor;
 
@@ -47,7 +55,7 @@ uniform mat4 projection;
 
 out vec2 TexCoord;
 out vec4 Col;
-out float ArrayIndex;
+flat out uint ArrayIndex;
 out float TextureFactor;
 
 void main() {
@@ -64,7 +72,7 @@ precision mediump float;
 precision highp sampler2DArray;
 
 in vec2 TexCoord;
-in float ArrayIndex;
+flat in uint ArrayIndex;
 in vec4 Col;
 in float TextureFactor;
 
@@ -73,7 +81,7 @@ uniform sampler2DArray tex_0;
 out vec4 FragColor;
 
 void main() {
-    FragColor = texture(tex_0, vec3(TexCoord * TextureFactor, ArrayIndex)) + Col;
+    FragColor = texture(tex_0, vec3(TexCoord * TextureFactor, float(ArrayIndex))) + Col;
 }
 ";
 
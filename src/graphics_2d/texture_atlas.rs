@@ -1,4 +1,5 @@
 use super::super::gl_wrapper::texture::{texture_2d, texture_config};
+use super::super::gl_wrapper::backend::interface::GlBackend;
 use super::super::global_tools::vector2::Vector2;
 
 // --------------------------------------------------------------------------------------------------
@@ -16,7 +17,7 @@ pub struct TextureAtlas {
 // --------------------------------------------------------------------------------------------------
 
 impl TextureAtlas {
-    pub fn new(texture: &texture_2d::Texture2D, grid_x: u32, grid_y: u32) -> Self {
+    pub fn new<B: GlBackend>(texture: &texture_2d::Texture2D<B>, grid_x: u32, grid_y: u32) -> Self {
 
         let (texture_pixel_width, texture_pixel_height) = texture.get_size();
 
@@ -1,14 +1,68 @@
 use super::super::super::gl_wrapper::vertex_array_object;
 use super::super::super::global_tools::vector2::Vector2;
 
+/// Derive macro generating VertexType from struct fields (see swgl_derive crate and vertex_field::VertexField).
+pub use swgl_derive::VertexType;
+
+/// Rows up to this width (in 4-byte items) are serialized by VertexType::write_bytes without heap allocation.
+/// It fits 16 attributes with 4 components each.
+pub const MAX_STACK_ROW_WIDTH: usize = 64;
+
 pub trait VertexType {
     fn get_vao_signature() -> Vec<vertex_array_object::VertexBufferAttribute>;
+    /// Size of one vertex in 4-byte items (number of floats for vertex types with float attributes only).
     fn get_row_width() -> u32;
     fn to_vec(&self) -> Vec<f32>;
+
+    /// This method writes vertex components (the same as to_vec) at the beginning of data, which must be long enough
+    /// for them (get_row_width for vertex types with float attributes only). Override it to avoid allocation of to_vec
+    /// in every frame (#[derive(VertexType)] does it).
+    fn write_into(&self, data: &mut [f32]) {
+        let values = self.to_vec();
+        data[..values.len()].copy_from_slice(&values);
+    }
+
+    /// This method appends vertex data in VBO format. Override it when the signature has non-float attributes
+    /// (e.g. color packed as 4 normalized bytes).
+    fn write_bytes(&self, data: &mut Vec<u8>) {
+        let row_width = Self::get_row_width() as usize;
+        if row_width > MAX_STACK_ROW_WIDTH {
+            for value in self.to_vec() {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            return;
+        }
+
+        let mut row = [0.0f32; MAX_STACK_ROW_WIDTH];
+        self.write_into(&mut row[..row_width]);
+        for value in &row[..row_width] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    /// This method replaces content of data with VBO data of all vertices. Capacity of data is reused, so the same
+    /// staging vector doesn't allocate when the number of vertices doesn't grow.
+    fn write_all_bytes(vertices: &[Self], data: &mut Vec<u8>)
+    where
+        Self: Sized,
+    {
+        data.clear();
+        data.reserve(vertices.len() * Self::get_row_width() as usize * 4);
+        for vertex in vertices {
+            vertex.write_bytes(data);
+        }
+    }
+
     fn get_position(&self) -> Vector2<f32>;
     fn set_position(&mut self, position: &Vector2<f32>);
 }
 
+/// This trait is implemented by #[derive(VertexType)] with #[vertex(glsl)] struct attribute.
+pub trait VertexGlslLayout {
+    /// This method returns shader input declarations ("layout (location=N) in type name;" lines) matching the VAO signature.
+    fn get_glsl_layout() -> String;
+}
+
 pub trait DedicatedShader {
     fn get_dedicated_shader() -> &'static str;
 } 
@@ -1,4 +1,5 @@
 //! Vertex like types are used for creates orderly data-sets.
 
 pub mod predefined;
-pub mod interface;
\ No newline at end of file
+pub mod interface;
+pub mod vertex_field;
\ No newline at end of file
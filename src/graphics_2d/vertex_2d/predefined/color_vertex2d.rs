@@ -1,4 +1,3 @@
-use super::super::super::super::gl_wrapper::vertex_array_object;
 use super::super::super::color::Color;
 use super::super::super::super::global_tools::vector2::Vector2;
 
@@ -8,9 +7,13 @@ use super::super::super::shader_collections;
 
 // -----------------------------------------------------------------------------------------------------------
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, VertexType)]
+#[vertex(glsl)]
 pub struct ColorVertex2D {
+    #[vertex(name = "pos")]
     pub position: Vector2<f32>,
+    /// Color is packed into 4 normalized bytes, so components are clamped to 0-1 range.
+    #[vertex(normalized)]
     pub color: Color,
     pub z_index: f32,
 }
@@ -38,42 +41,6 @@ impl ColorVertex2D {
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl VertexType for ColorVertex2D {
-    fn get_vao_signature() -> Vec<vertex_array_object::VertexBufferAttribute> {
-        vec![
-            vertex_array_object::VertexBufferAttribute::create(2), // position
-            vertex_array_object::VertexBufferAttribute::create(4), // color
-            vertex_array_object::VertexBufferAttribute::create(1), // z-index
-        ]
-    }
-
-    fn get_row_width() -> u32 {
-        7
-    }
-
-    fn to_vec(&self) -> Vec<f32> {
-        vec![
-            self.position.x,
-            self.position.y,
-            self.color.red,
-            self.color.green,
-            self.color.blue,
-            self.color.alpha,
-            self.z_index,
-        ]
-    }
-
-    fn get_position(&self) -> Vector2<f32> {
-        self.position
-    }
-
-    fn set_position(&mut self, position: &Vector2<f32>) {
-        self.position = *position; 
-    }
-}
-
-// -----------------------------------------------------------------------------------------------------------
-
 impl DedicatedShader for ColorVertex2D {
     fn get_dedicated_shader() -> &'static str {
         shader_collections::COLOR_VERTEX2D_SHADER
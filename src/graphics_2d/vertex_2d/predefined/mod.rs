@@ -1,3 +1,131 @@
 pub mod vertex2d;
 pub mod color_vertex2d;
 pub mod single_tex_vertex2d;
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::color_vertex2d::ColorVertex2D;
+    use super::single_tex_vertex2d::SingleTexVertex2D;
+    use super::vertex2d::Vertex2D;
+    use super::super::interface::{DedicatedShader, VertexGlslLayout, VertexType};
+    use super::super::super::color::Color;
+    use super::super::super::super::gl_wrapper::backend::recording_backend::{GlCall, RecordingBackend};
+    use super::super::super::super::gl_wrapper::vertex_array_object::VertexArrayObject;
+    use super::super::super::super::gl_wrapper::vertex_buffer_object::{DataStorageType, VertexBufferObject};
+    use super::super::super::super::global_tools::vector2::Vector2;
+
+    const FLOAT: u32 = crate::AppContext::FLOAT;
+    const UNSIGNED_BYTE: u32 = crate::AppContext::UNSIGNED_BYTE;
+    const UNSIGNED_INT: u32 = crate::AppContext::UNSIGNED_INT;
+
+    /// This function returns (size, data type, normalized, offset) of attributes set by VAO of the vertex type and
+    /// its stride. Integer attributes (vertexAttribIPointer) are returned as not normalized.
+    fn pointers<T: VertexType>() -> (Vec<(i32, u32, bool, i32)>, i32) {
+        let context = RecordingBackend::new();
+        let vbo = VertexBufferObject::new_empty(&context, 64, DataStorageType::DynamicDraw).unwrap();
+        let _vao = VertexArrayObject::new(&context, &vbo, T::get_row_width(), T::get_vao_signature()).unwrap();
+
+        let mut stride = 0;
+        let mut pointers = vec![];
+        for call in context.calls() {
+            let (index, pointer, s) = match call {
+                GlCall::VertexAttribPointer { index, size, data_type, normalized, stride, offset } => {
+                    (index, (size, data_type, normalized, offset), stride)
+                },
+                GlCall::VertexAttribIPointer { index, size, data_type, stride, offset } => {
+                    (index, (size, data_type, false, offset), stride)
+                },
+                _ => continue,
+            };
+            assert_eq!(index as usize, pointers.len());
+            pointers.push(pointer);
+            stride = s;
+        }
+        (pointers, stride)
+    }
+
+    /// Shader inputs generated by the derive have to be the same as in the dedicated shader.
+    fn assert_layout_in_dedicated_shader<T: VertexGlslLayout + DedicatedShader>(line_count: usize) {
+        let layout = T::get_glsl_layout();
+        assert_eq!(layout.lines().count(), line_count);
+        for line in layout.lines() {
+            assert!(T::get_dedicated_shader().contains(line), "{}", line);
+        }
+    }
+
+    fn bytes_of(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn vertex2d_packs_color_and_array_index() {
+        let vertex = Vertex2D::new(Vector2::new(1.0, 2.0), Color::from_bytes(10, 20, 30, 255), Vector2::new(0.5, 0.6), 3, 7.0, 0.8);
+
+        assert_eq!(Vertex2D::get_row_width(), 8);
+        assert_eq!(
+            pointers::<Vertex2D>(),
+            (
+                vec![
+                    (2, FLOAT, false, 0),
+                    (4, UNSIGNED_BYTE, true, 8),
+                    (2, FLOAT, false, 12),
+                    (1, UNSIGNED_INT, false, 20),
+                    (1, FLOAT, false, 24),
+                    (1, FLOAT, false, 28),
+                ],
+                32
+            )
+        );
+        let mut data = vec![];
+        vertex.write_bytes(&mut data);
+        let mut expected = bytes_of(&[1.0, 2.0]);
+        expected.extend_from_slice(&[10, 20, 30, 255]);
+        expected.extend(bytes_of(&[0.5, 0.6]));
+        expected.extend_from_slice(&3u32.to_le_bytes());
+        expected.extend(bytes_of(&[7.0, 0.8]));
+        assert_eq!(data, expected);
+        assert_layout_in_dedicated_shader::<Vertex2D>(6);
+        assert!(Vertex2D::get_glsl_layout().contains("layout (location=3) in uint array_index;"));
+    }
+
+    #[test]
+    fn color_vertex2d_packs_color() {
+        // components out of 0-1 range are clamped
+        let vertex = ColorVertex2D::new(Vector2::new(1.0, 2.0), Color::new(0.0, 1.0, 2.0, -1.0), 5.0);
+
+        assert_eq!(ColorVertex2D::get_row_width(), 4);
+        assert_eq!(
+            pointers::<ColorVertex2D>(),
+            (vec![(2, FLOAT, false, 0), (4, UNSIGNED_BYTE, true, 8), (1, FLOAT, false, 12)], 16)
+        );
+        let mut data = vec![];
+        vertex.write_bytes(&mut data);
+        let mut expected = bytes_of(&[1.0, 2.0]);
+        expected.extend_from_slice(&[0, 255, 255, 0]);
+        expected.extend(bytes_of(&[5.0]));
+        assert_eq!(data, expected);
+        assert_layout_in_dedicated_shader::<ColorVertex2D>(3);
+    }
+
+    #[test]
+    fn single_tex_vertex2d_keeps_float_layout() {
+        let mut vertex = SingleTexVertex2D::new(Vector2::new(1.0, 2.0), Vector2::new(0.5, 0.6), 5.0, 0.7);
+        let expected = [1.0, 2.0, 0.5, 0.6, 5.0, 0.7];
+
+        assert_eq!(SingleTexVertex2D::get_row_width(), 6);
+        assert_eq!(
+            pointers::<SingleTexVertex2D>(),
+            (vec![(2, FLOAT, false, 0), (2, FLOAT, false, 8), (1, FLOAT, false, 16), (1, FLOAT, false, 20)], 24)
+        );
+        assert_eq!(vertex.to_vec(), expected.to_vec());
+        let mut data = vec![];
+        vertex.write_bytes(&mut data);
+        assert_eq!(data, bytes_of(&expected));
+        assert_layout_in_dedicated_shader::<SingleTexVertex2D>(4);
+
+        vertex.set_position(&Vector2::new(3.0, 4.0));
+        assert_eq!(vertex.to_vec()[..2].to_vec(), vec![3.0, 4.0]);
+    }
+}
@@ -1,4 +1,3 @@
-use super::super::super::super::gl_wrapper::vertex_array_object;
 use super::super::super::color::Color;
 use super::super::super::super::global_tools::vector2::Vector2;
 
@@ -10,12 +9,18 @@ use super::super::super::shader_collections;
 
 // -----------------------------------------------------------------------------------------------------------
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, VertexType)]
+#[vertex(glsl)]
 pub struct Vertex2D {
+    #[vertex(name = "pos")]
     pub position: Vector2<f32>,
+    /// Color is packed into 4 normalized bytes, so components are clamped to 0-1 range.
+    #[vertex(normalized)]
     pub color: Color,
+    #[vertex(name = "texture_coords")]
     pub texture_coord: Vector2<f32>,
-    pub array_index: f32,
+    /// Layer of the texture array (uint shader input).
+    pub array_index: u32,
     pub z_index: f32,
     pub texture_factor: f32,
 }
@@ -35,7 +40,7 @@ impl Vertex2D {
             position,
             color,
             texture_coord,
-            array_index: array_index as f32,
+            array_index,
             z_index,
             texture_factor,
         }
@@ -52,7 +57,7 @@ impl Vertex2D {
             position: Vector2::zero(),
             color,
             texture_coord: Vector2::zero(), 
-            array_index: array_index as f32,
+            array_index,
             z_index,
             texture_factor,
         }
@@ -61,49 +66,6 @@ impl Vertex2D {
 
 // -----------------------------------------------------------------------------------------------------------
 
-impl VertexType for Vertex2D {
-    fn get_vao_signature() -> Vec<vertex_array_object::VertexBufferAttribute> {
-        vec![
-            vertex_array_object::VertexBufferAttribute::create(2), // position
-            vertex_array_object::VertexBufferAttribute::create(4), // color
-            vertex_array_object::VertexBufferAttribute::create(2), // texture coords
-            vertex_array_object::VertexBufferAttribute::create(1), // array index
-            vertex_array_object::VertexBufferAttribute::create(1), // z-index
-            vertex_array_object::VertexBufferAttribute::create(1), // texture factor
-        ]
-    }
-
-    fn get_row_width() -> u32 {
-        11
-    }
-
-    fn to_vec(&self) -> Vec<f32> {
-        vec![
-            self.position.x,
-            self.position.y,
-            self.color.red,
-            self.color.green,
-            self.color.blue,
-            self.color.alpha,
-            self.texture_coord.x,
-            self.texture_coord.y,
-            self.array_index,
-            self.z_index,
-            self.texture_factor,
-        ]
-    }
-
-    fn get_position(&self) -> Vector2<f32> {
-        self.position
-    }
-
-    fn set_position(&mut self, position: &Vector2<f32>) {
-        self.position = *position; 
-    }
-}
-
-// -----------------------------------------------------------------------------------------------------------
-
 impl DedicatedShader for Vertex2D {
     fn get_dedicated_shader() -> &'static str {
         shader_collections::FULL_VERTEX2D_SHADER
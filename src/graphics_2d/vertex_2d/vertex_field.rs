@@ -0,0 +1,331 @@
+//! Field types which can be used in vertex structs with #[derive(VertexType)].
+
+use super::super::super::gl_wrapper::vertex_array_object::{AttributeDataType, VertexBufferAttribute};
+use super::super::super::global_tools::vector2::Vector2;
+use super::super::color::Color;
+
+// -----------------------------------------------------------------------------------------------------------
+
+/// This trait describes how a single field of the vertex struct is stored in VBO. Every field is a separate attribute.
+/// Normalized flag is set by #[vertex(normalized)], it can be used only with types that have NORMALIZABLE set.
+///
+/// Fields of other types fail to compile:
+///
+/// ```compile_fail,E0277
+/// use swgl::global_tools::vector2::Vector2;
+/// use swgl::graphics_2d::vertex_2d::interface::VertexType;
+///
+/// #[derive(Clone, Copy, VertexType)]
+/// struct Point {
+///     position: Vector2<f32>,
+///     weight: f64,
+/// }
+/// ```
+///
+/// The same applies to float arrays outside of 1-4 elements:
+///
+/// ```compile_fail,E0080
+/// use swgl::global_tools::vector2::Vector2;
+/// use swgl::graphics_2d::vertex_2d::interface::VertexType;
+///
+/// #[derive(Clone, Copy, VertexType)]
+/// struct Point {
+///     position: Vector2<f32>,
+///     weights: [f32; 5],
+/// }
+///
+/// fn main() {
+///     Point::get_vao_signature();
+/// }
+/// ```
+///
+/// ```compile_fail,E0080
+/// use swgl::global_tools::vector2::Vector2;
+/// use swgl::graphics_2d::vertex_2d::interface::VertexType;
+///
+/// #[derive(Clone, Copy, VertexType)]
+/// struct Point {
+///     position: Vector2<f32>,
+///     weights: [f32; 0],
+/// }
+///
+/// fn main() {
+///     Point::get_vao_signature();
+/// }
+/// ```
+///
+/// And to normalized fields of types which can't be normalized:
+///
+/// ```compile_fail,E0080
+/// use swgl::global_tools::vector2::Vector2;
+/// use swgl::graphics_2d::vertex_2d::interface::VertexType;
+///
+/// #[derive(Clone, Copy, VertexType)]
+/// struct Point {
+///     position: Vector2<f32>,
+///     #[vertex(normalized)]
+///     weight: f32,
+/// }
+/// ```
+pub trait VertexField {
+    const NORMALIZABLE: bool = false;
+
+    fn get_attribute(normalized: bool) -> VertexBufferAttribute;
+    /// GLSL type of the shader input (e.g. "vec2").
+    fn get_glsl_type(normalized: bool) -> &'static str;
+    /// This method writes field components converted to floats at the beginning of data (used by
+    /// VertexType::write_into) and returns number of written items.
+    fn write_floats(&self, data: &mut [f32]) -> usize;
+    /// This method appends field data in the VBO format.
+    fn write_bytes(&self, normalized: bool, data: &mut Vec<u8>);
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+impl VertexField for f32 {
+    fn get_attribute(_: bool) -> VertexBufferAttribute {
+        VertexBufferAttribute::create(1)
+    }
+
+    fn get_glsl_type(_: bool) -> &'static str {
+        "float"
+    }
+
+    fn write_floats(&self, data: &mut [f32]) -> usize {
+        data[0] = *self;
+        1
+    }
+
+    fn write_bytes(&self, _: bool, data: &mut Vec<u8>) {
+        data.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl VertexField for u32 {
+    fn get_attribute(_: bool) -> VertexBufferAttribute {
+        VertexBufferAttribute::create_integer(1, AttributeDataType::UnsignedInt)
+    }
+
+    fn get_glsl_type(_: bool) -> &'static str {
+        "uint"
+    }
+
+    fn write_floats(&self, data: &mut [f32]) -> usize {
+        data[0] = *self as f32;
+        1
+    }
+
+    fn write_bytes(&self, _: bool, data: &mut Vec<u8>) {
+        data.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl VertexField for i32 {
+    fn get_attribute(_: bool) -> VertexBufferAttribute {
+        VertexBufferAttribute::create_integer(1, AttributeDataType::Int)
+    }
+
+    fn get_glsl_type(_: bool) -> &'static str {
+        "int"
+    }
+
+    fn write_floats(&self, data: &mut [f32]) -> usize {
+        data[0] = *self as f32;
+        1
+    }
+
+    fn write_bytes(&self, _: bool, data: &mut Vec<u8>) {
+        data.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+/// Arrays with 1-4 elements are stored as float vectors, other lengths fail to compile.
+impl<const N: usize> VertexField for [f32; N] {
+    fn get_attribute(_: bool) -> VertexBufferAttribute {
+        const { assert!(N >= 1 && N <= 4, "vertex field array has to have 1-4 elements") }
+        VertexBufferAttribute::create(N as u32)
+    }
+
+    fn get_glsl_type(_: bool) -> &'static str {
+        const { assert!(N >= 1 && N <= 4, "vertex field array has to have 1-4 elements") }
+        match N {
+            1 => "float",
+            2 => "vec2",
+            3 => "vec3",
+            _ => "vec4",
+        }
+    }
+
+    fn write_floats(&self, data: &mut [f32]) -> usize {
+        data[..N].copy_from_slice(self);
+        N
+    }
+
+    fn write_bytes(&self, _: bool, data: &mut Vec<u8>) {
+        for value in self {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+/// Normalized bytes are read as floats in 0-1 range, otherwise as floats in 0-255 range.
+impl VertexField for [u8; 4] {
+    const NORMALIZABLE: bool = true;
+
+    fn get_attribute(normalized: bool) -> VertexBufferAttribute {
+        VertexBufferAttribute::create_typed(4, AttributeDataType::UnsignedByte, normalized)
+    }
+
+    fn get_glsl_type(_: bool) -> &'static str {
+        "vec4"
+    }
+
+    fn write_floats(&self, data: &mut [f32]) -> usize {
+        for (item, value) in data.iter_mut().zip(self) {
+            *item = *value as f32;
+        }
+        4
+    }
+
+    fn write_bytes(&self, _: bool, data: &mut Vec<u8>) {
+        data.extend_from_slice(self);
+    }
+}
+
+impl VertexField for Vector2<f32> {
+    fn get_attribute(_: bool) -> VertexBufferAttribute {
+        VertexBufferAttribute::create(2)
+    }
+
+    fn get_glsl_type(_: bool) -> &'static str {
+        "vec2"
+    }
+
+    fn write_floats(&self, data: &mut [f32]) -> usize {
+        data[0] = self.x;
+        data[1] = self.y;
+        2
+    }
+
+    fn write_bytes(&self, _: bool, data: &mut Vec<u8>) {
+        data.extend_from_slice(&self.x.to_le_bytes());
+        data.extend_from_slice(&self.y.to_le_bytes());
+    }
+}
+
+/// Normalized color is packed into 4 bytes (see Color::to_bytes), otherwise it takes 4 floats.
+impl VertexField for Color {
+    const NORMALIZABLE: bool = true;
+
+    fn get_attribute(normalized: bool) -> VertexBufferAttribute {
+        if normalized {
+            VertexBufferAttribute::create_typed(4, AttributeDataType::UnsignedByte, true)
+        } else {
+            VertexBufferAttribute::create(4)
+        }
+    }
+
+    fn get_glsl_type(_: bool) -> &'static str {
+        "vec4"
+    }
+
+    fn write_floats(&self, data: &mut [f32]) -> usize {
+        data[..4].copy_from_slice(&[self.red, self.green, self.blue, self.alpha]);
+        4
+    }
+
+    fn write_bytes(&self, normalized: bool, data: &mut Vec<u8>) {
+        if normalized {
+            data.extend_from_slice(&self.to_bytes());
+        } else {
+            for value in &[self.red, self.green, self.blue, self.alpha] {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::super::gl_wrapper::vertex_array_object::AttributeDataType;
+    use super::super::interface::{VertexGlslLayout, VertexType};
+
+    #[derive(Clone, Copy, VertexType)]
+    #[vertex(glsl)]
+    struct Particle {
+        #[vertex(position, name = "pos")]
+        center: Vector2<f32>,
+        #[vertex(normalized)]
+        color: Color,
+        layer: u32,
+        #[vertex(skip)]
+        #[allow(dead_code)]
+        velocity: Vector2<f32>,
+        mask: [u8; 4],
+        weights: [f32; 3],
+    }
+
+    fn particle() -> Particle {
+        Particle {
+            center: Vector2::new(1.0, 2.0),
+            color: Color::from_bytes(255, 0, 51, 255),
+            layer: 7,
+            velocity: Vector2::new(9.0, 9.0),
+            mask: [1, 2, 3, 4],
+            weights: [0.25, 0.5, 0.75],
+        }
+    }
+
+    #[test]
+    fn derives_typed_attributes() {
+        let signature: Vec<_> = Particle::get_vao_signature()
+            .iter()
+            .map(|a| (a.get_element_count(), a.get_data_type(), a.is_normalized(), a.is_integer()))
+            .collect();
+        assert_eq!(
+            signature,
+            vec![
+                (2, AttributeDataType::Float, false, false),
+                (4, AttributeDataType::UnsignedByte, true, false),
+                (1, AttributeDataType::UnsignedInt, false, true),
+                (4, AttributeDataType::UnsignedByte, false, false),
+                (3, AttributeDataType::Float, false, false),
+            ]
+        );
+        // 8 + 4 + 4 + 4 + 12 bytes
+        assert_eq!(Particle::get_row_width(), 8);
+        assert_eq!(
+            Particle::get_glsl_layout(),
+            "layout (location=0) in vec2 pos;\n\
+             layout (location=1) in vec4 color;\n\
+             layout (location=2) in uint layer;\n\
+             layout (location=3) in vec4 mask;\n\
+             layout (location=4) in vec3 weights;\n"
+        );
+    }
+
+    #[test]
+    fn derives_serialization_of_typed_fields() {
+        let mut particle = particle();
+        let mut data = vec![];
+        particle.write_bytes(&mut data);
+
+        let mut expected = vec![];
+        expected.extend_from_slice(&1.0f32.to_le_bytes());
+        expected.extend_from_slice(&2.0f32.to_le_bytes());
+        expected.extend_from_slice(&[255, 0, 51, 255]);
+        expected.extend_from_slice(&7u32.to_le_bytes());
+        expected.extend_from_slice(&[1, 2, 3, 4]);
+        for value in &[0.25f32, 0.5, 0.75] {
+            expected.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(data, expected);
+
+        particle.set_position(&Vector2::new(3.0, 4.0));
+        assert_eq!((particle.get_position().x, particle.get_position().y), (3.0, 4.0));
+        assert_eq!(particle.to_vec()[..7].to_vec(), vec![3.0, 4.0, 1.0, 0.0, 0.2, 1.0, 7.0]);
+    }
+}
@@ -1,3 +1,6 @@
+// Lets derive macros (swgl_derive) refer to ::swgl paths inside this crate too.
+extern crate self as swgl;
+
 pub type AppContext = web_sys::WebGl2RenderingContext;
 
 pub mod camera2d;
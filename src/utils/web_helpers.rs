@@ -1,4 +1,5 @@
 use super::super::runtime_error::{SWGLResult, SWGLRuntimeError};
+use super::super::gl_wrapper::basics::render_state::RenderState;
 use wasm_bindgen::JsCast;
 use web_sys::*;
 
@@ -12,6 +13,7 @@ pub fn window() -> web_sys::Window {
 // -----------------------------------------------------------------------------------------
 
 /// This function creates and returns canvas object and GL renderer object based on a given selector. 
+/// Context has default RenderState applied (alpha blending, no depth test).
 pub fn app_handler(canvas_selector: &str) -> SWGLResult<(web_sys::HtmlCanvasElement, crate::AppContext)> {
     let window = window();
 
@@ -51,8 +53,7 @@ pub fn app_handler(canvas_selector: &str) -> SWGLResult<(web_sys::HtmlCanvasElem
         .ok()
         .ok_or(SWGLRuntimeError::new(get_context_err_msg))?;
 
-    gl.enable(crate::AppContext::BLEND);
-    gl.blend_func(crate::AppContext::SRC_ALPHA, crate::AppContext::ONE_MINUS_SRC_ALPHA);
+    RenderState::default().apply(&gl);
 
     Ok((canvas, gl))
 }
\ No newline at end of file
@@ -0,0 +1,225 @@
+//! Derive macros for SWGL.
+//!
+//! #[derive(VertexType)] implements swgl VertexType for struct with named fields. Every field (in declaration order)
+//! is a separate attribute with the next location, field type has to implement swgl VertexField.
+//!
+//! Field attributes:
+//! - #[vertex(skip)] - field is not stored in VBO,
+//! - #[vertex(normalized)] - integer data is read as float in 0-1 range (e.g. Color packed into 4 bytes),
+//! - #[vertex(position)] - field used by get_position/set_position (by default field named "position"),
+//! - #[vertex(name = "pos")] - name of the shader input (by default field name).
+//!
+//! Struct attribute #[vertex(glsl)] additionally implements swgl VertexGlslLayout.
+//!
+//! ```text
+//! #[derive(Clone, Copy, VertexType)]
+//! #[vertex(glsl)]
+//! struct Particle {
+//!     #[vertex(name = "pos")]
+//!     position: Vector2<f32>,
+//!     #[vertex(normalized)]
+//!     color: Color,
+//!     size: f32,
+//!     #[vertex(skip)]
+//!     velocity: Vector2<f32>,
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitStr, Type};
+
+// -----------------------------------------------------------------------------------------------------------
+
+struct VertexFieldOptions {
+    ident: Ident,
+    ty: Type,
+    normalized: bool,
+    position: bool,
+    name: String,
+}
+
+// -----------------------------------------------------------------------------------------------------------
+// private:
+
+/// This function returns None for skipped fields.
+fn parse_field(field: &syn::Field) -> syn::Result<Option<VertexFieldOptions>> {
+    let ident = field.ident.clone().unwrap();
+    let mut options = VertexFieldOptions {
+        name: ident.to_string(),
+        ident,
+        ty: field.ty.clone(),
+        normalized: false,
+        position: false,
+    };
+    let mut skip = false;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+            } else if meta.path.is_ident("normalized") {
+                options.normalized = true;
+            } else if meta.path.is_ident("position") {
+                options.position = true;
+            } else if meta.path.is_ident("name") {
+                options.name = meta.value()?.parse::<LitStr>()?.value();
+            } else {
+                return Err(meta.error("unknown vertex field attribute (expected skip, normalized, position or name)"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(if skip { None } else { Some(options) })
+}
+
+/// This function returns true if the struct has #[vertex(glsl)] attribute.
+fn parse_struct_attributes(input: &DeriveInput) -> syn::Result<bool> {
+    let mut glsl = false;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("vertex")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("glsl") {
+                glsl = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown vertex struct attribute (expected glsl)"))
+            }
+        })?;
+    }
+    Ok(glsl)
+}
+
+fn expand_vertex_type(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let struct_name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "VertexType can't be derived for generic struct"));
+    }
+
+    let named_fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(struct_name, "VertexType can be derived only for struct with named fields")),
+        },
+        _ => return Err(Error::new_spanned(struct_name, "VertexType can be derived only for struct")),
+    };
+
+    let mut fields = vec![];
+    for field in named_fields {
+        if let Some(options) = parse_field(field)? {
+            fields.push(options);
+        }
+    }
+
+    if fields.is_empty() {
+        return Err(Error::new_spanned(struct_name, "VertexType needs at least one not skipped field"));
+    }
+
+    let position = match fields.iter().filter(|f| f.position).count() {
+        0 => fields.iter().find(|f| f.ident == "position"),
+        1 => fields.iter().find(|f| f.position),
+        _ => return Err(Error::new_spanned(struct_name, "only one field can have #[vertex(position)] attribute")),
+    };
+    let position = match position {
+        Some(position) => &position.ident,
+        None => {
+            return Err(Error::new_spanned(
+                struct_name,
+                "VertexType needs Vector2<f32> field named \"position\" or marked with #[vertex(position)]",
+            ))
+        }
+    };
+
+    let field_trait = quote!(::swgl::graphics_2d::vertex_2d::vertex_field::VertexField);
+    let idents: Vec<&Ident> = fields.iter().map(|f| &f.ident).collect();
+    let types: Vec<&Type> = fields.iter().map(|f| &f.ty).collect();
+    let normalized: Vec<bool> = fields.iter().map(|f| f.normalized).collect();
+
+    let normalizable_checks = fields.iter().filter(|f| f.normalized).map(|f| {
+        let ty = &f.ty;
+        let message = format!("field \"{}\" of type {} can't be normalized", f.ident, quote!(#ty));
+        quote! {
+            const _: () = ::core::assert!(<#ty as #field_trait>::NORMALIZABLE, #message);
+        }
+    });
+
+    let mut result = quote! {
+        #(#normalizable_checks)*
+
+        impl ::swgl::graphics_2d::vertex_2d::interface::VertexType for #struct_name {
+            fn get_vao_signature() -> ::std::vec::Vec<::swgl::gl_wrapper::vertex_array_object::VertexBufferAttribute> {
+                ::std::vec![
+                    #(<#types as #field_trait>::get_attribute(#normalized),)*
+                ]
+            }
+
+            fn get_row_width() -> u32 {
+                let byte_size: u32 = 0 #(+ <#types as #field_trait>::get_attribute(#normalized).get_byte_size())*;
+                byte_size.div_ceil(4)
+            }
+
+            fn to_vec(&self) -> ::std::vec::Vec<f32> {
+                let count: u32 = 0 #(+ <#types as #field_trait>::get_attribute(#normalized).get_element_count())*;
+                let mut data = ::std::vec![0.0; count as usize];
+                <Self as ::swgl::graphics_2d::vertex_2d::interface::VertexType>::write_into(self, &mut data);
+                data
+            }
+
+            fn write_into(&self, data: &mut [f32]) {
+                let mut position = 0;
+                #(position += #field_trait::write_floats(&self.#idents, &mut data[position..]);)*
+                let _ = position;
+            }
+
+            fn write_bytes(&self, data: &mut ::std::vec::Vec<u8>) {
+                let start = data.len();
+                #(#field_trait::write_bytes(&self.#idents, #normalized, data);)*
+                // Row is padded to the multiple of 4 bytes (see get_row_width).
+                let row_size = <Self as ::swgl::graphics_2d::vertex_2d::interface::VertexType>::get_row_width() as usize * 4;
+                data.resize(start + row_size, 0);
+            }
+
+            fn get_position(&self) -> ::swgl::global_tools::vector2::Vector2<f32> {
+                self.#position
+            }
+
+            fn set_position(&mut self, position: &::swgl::global_tools::vector2::Vector2<f32>) {
+                self.#position = *position;
+            }
+        }
+    };
+
+    if parse_struct_attributes(&input)? {
+        let names: Vec<&String> = fields.iter().map(|f| &f.name).collect();
+        let locations = 0..fields.len();
+        result.extend(quote! {
+            impl ::swgl::graphics_2d::vertex_2d::interface::VertexGlslLayout for #struct_name {
+                fn get_glsl_layout() -> ::std::string::String {
+                    let mut layout = ::std::string::String::new();
+                    #(
+                        layout.push_str(&::std::format!(
+                            "layout (location={}) in {} {};\n",
+                            #locations,
+                            <#types as #field_trait>::get_glsl_type(#normalized),
+                            #names
+                        ));
+                    )*
+                    layout
+                }
+            }
+        });
+    }
+
+    Ok(result)
+}
+
+// -----------------------------------------------------------------------------------------------------------
+
+#[proc_macro_derive(VertexType, attributes(vertex))]
+pub fn derive_vertex_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_vertex_type(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}